@@ -6,7 +6,9 @@ use hourai::models::id::{Id, marker::*};
 use hourai::models::user::User;
 use hourai::models::voice::VoiceState;
 use hourai::proto::guild_configs::*;
+use hourai::util::template::{self, TemplateContext};
 use hourai_storage::Storage;
+use rand::seq::SliceRandom;
 use std::sync::Arc;
 
 async fn get_config(
@@ -16,29 +18,71 @@ async fn get_config(
     storage.redis().guild(guild_id).configs().fetch().await
 }
 
+/// Picks one of the custom messages for the announcement and renders it. Falls back to the
+/// default message if no custom messages are configured.
+async fn render_message(
+    storage: &Storage,
+    guild_id: Id<GuildMarker>,
+    config: &AnnouncementTypeConfig,
+    mut ctx: TemplateContext,
+    default: impl FnOnce() -> String,
+) -> Result<String> {
+    let message = config
+        .get_messages()
+        .choose(&mut rand::thread_rng())
+        .cloned();
+    match message {
+        Some(message) => {
+            hourai_storage::populate_template_context(guild_id, &message, storage, &mut ctx)
+                .await?;
+            Ok(template::render(&message, &ctx))
+        }
+        None => Ok(default()),
+    }
+}
+
+fn user_context(user: &User) -> TemplateContext {
+    TemplateContext {
+        user_id: Some(user.id),
+        user_name: Some(user.name.clone()),
+        ..Default::default()
+    }
+}
+
 pub async fn on_member_join(client: &Client, guild: Id<GuildMarker>, user: User) -> Result<()> {
     if let Some(config) = get_config(client.storage(), guild).await? {
-        // TODO(james7132): let this be customizable.
-        let msg = format!("<@{}> has joined the server.", user.id);
-        broadcast(client, config.get_leaves(), msg);
+        let joins = config.get_joins();
+        let msg = render_message(client.storage(), guild, joins, user_context(&user), || {
+            format!("<@{}> has joined the server.", user.id)
+        })
+        .await?;
+        broadcast(client, joins, msg);
     }
     Ok(())
 }
 
 pub async fn on_member_leave(client: &Client, evt: MemberRemove) -> Result<()> {
     if let Some(config) = get_config(client.storage(), evt.guild_id).await? {
-        // TODO(james7132): let this be customizable.
-        let msg = format!("**{}** has left the server.", evt.user.name);
-        broadcast(client, config.get_leaves(), msg);
+        let leaves = config.get_leaves();
+        let ctx = user_context(&evt.user);
+        let msg = render_message(client.storage(), evt.guild_id, leaves, ctx, || {
+            format!("**{}** has left the server.", evt.user.name)
+        })
+        .await?;
+        broadcast(client, leaves, msg);
     }
     Ok(())
 }
 
 pub async fn on_member_ban(client: &Client, evt: BanAdd) -> Result<()> {
     if let Some(config) = get_config(client.storage(), evt.guild_id).await? {
-        // TODO(james7132): let this be customizable.
-        let msg = format!("**{}** has been banned.", evt.user.name);
-        broadcast(client, config.get_bans(), msg);
+        let bans = config.get_bans();
+        let ctx = user_context(&evt.user);
+        let msg = render_message(client.storage(), evt.guild_id, bans, ctx, || {
+            format!("**{}** has been banned.", evt.user.name)
+        })
+        .await?;
+        broadcast(client, bans, msg);
     }
     Ok(())
 }
@@ -67,26 +111,33 @@ pub async fn on_voice_update(
         return Ok(());
     }
     let user = match state.member {
-        Some(member) => member.user.name,
+        Some(member) => member.user,
         None => return Ok(()),
     };
     if let Some(config) = get_config(client.storage(), guild_id).await? {
-        // TODO(james7132): let this be customizable.
-        let msg = match (before_channel, after_channel) {
-            (Some(b), Some(a)) => {
-                format!(
-                    "**{}** moved from **{}** to **{}**.",
-                    user,
-                    b.get_name(),
-                    a.get_name()
-                )
+        let voice = config.get_voice();
+        let mut ctx = user_context(&user);
+        ctx.channel_id = state.channel_id.or(before);
+        let msg = render_message(client.storage(), guild_id, voice, ctx, || {
+            match (before_channel, after_channel) {
+                (Some(b), Some(a)) => {
+                    format!(
+                        "**{}** moved from **{}** to **{}**.",
+                        user.name,
+                        b.get_name(),
+                        a.get_name()
+                    )
+                }
+                (None, Some(ch)) => format!("**{}** joined **{}**.", user.name, ch.get_name()),
+                (Some(ch), None) => format!("**{}** left **{}**.", user.name, ch.get_name()),
+                (None, None) => String::new(),
             }
-            (None, Some(ch)) => format!("**{}** joined **{}**.", user, ch.get_name()),
-            (Some(ch), None) => format!("**{}** left **{}**.", user, ch.get_name()),
-            (None, None) => return Ok(()),
-        };
+        })
+        .await?;
 
-        broadcast(client, config.get_voice(), msg);
+        if !msg.is_empty() {
+            broadcast(client, voice, msg);
+        }
     }
 
     Ok(())
@@ -1,54 +1,397 @@
 use anyhow::Result;
+use chrono::Utc;
+use dashmap::DashMap;
 use hourai::{
     models::{
         MessageLike, Snowflake, UserLike,
-        guild::Member,
+        channel::{Channel, message::EmojiReactionType},
+        gateway::{GatewayReaction, payload::incoming::MemberUpdate},
+        guild::{Member, PartialMember},
         id::{Id, marker::*},
         user::User,
+        util::Timestamp,
     },
     proto::{
-        auto_config::{AutoConfig, MessageEvent_Type},
-        util::FilterSettings,
+        action::Action,
+        auto_config::{AutoConfig, Cooldown_Scope},
+        event::{AutoConfigTrigger, DiscordMessage, EventSource},
     },
+    util::{
+        auto_config::{CompiledAutoConfig, CompiledEventGroup, CompiledUserChangeEvent, Rule},
+        condition::ConditionContext,
+        template::TemplateContext,
+    },
+};
+use hourai_storage::{
+    Storage,
+    actions::{ActionExecutor, Operation},
+};
+use std::{
+    collections::HashSet,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
 };
-use hourai_storage::actions::ActionExecutor;
-use regex::Regex;
 
-fn meets_filter(val: Option<&str>, filter: Option<&FilterSettings>) -> bool {
-    let val = match val {
-        Some(v) => v,
-        None => return false,
-    };
-    let filter = match filter {
-        Some(f) => f,
-        None => return true,
+/// The maximum number of parents to walk when resolving a channel's ancestry. Threads are
+/// the most deeply nested channels: thread -> channel -> category.
+const MAX_CHANNEL_DEPTH: usize = 3;
+
+/// How long a compiled config is used before being rebuilt. Writes made by this process
+/// invalidate the cache immediately, this only bounds how long writes from other processes
+/// take to be picked up.
+const COMPILED_CONFIG_TTL: Duration = Duration::from_secs(60);
+
+struct CachedAutoConfig {
+    config: Arc<CompiledAutoConfig>,
+    generation: u64,
+    compiled_at: Instant,
+}
+
+/// Events that matched a trigger, as their rules and the actions to run for them.
+type MatchedEvents<'a> = Vec<(&'a Rule, &'a [Action])>;
+
+static COMPILED_CONFIGS: LazyLock<dashmap::DashMap<Id<GuildMarker>, CachedAutoConfig>> =
+    LazyLock::new(dashmap::DashMap::new);
+
+/// Gets the compiled AutoConfig for a guild, compiling it if it is not cached or out of date.
+pub async fn compiled_config(
+    storage: &Storage,
+    guild_id: Id<GuildMarker>,
+) -> Result<Arc<CompiledAutoConfig>> {
+    let configs = storage.redis().guild(guild_id).configs();
+    let generation = configs.generation::<AutoConfig>();
+    if let Some(cached) = COMPILED_CONFIGS.get(&guild_id)
+        && cached.generation == generation
+        && cached.compiled_at.elapsed() < COMPILED_CONFIG_TTL
+    {
+        return Ok(cached.config.clone());
+    }
+
+    let mut configs = configs;
+    let config = Arc::new(CompiledAutoConfig::new(&configs.get::<AutoConfig>().await?));
+    let previous = COMPILED_CONFIGS.insert(
+        guild_id,
+        CachedAutoConfig {
+            config: config.clone(),
+            generation,
+            compiled_at: Instant::now(),
+        },
+    );
+
+    // Schedules only need to be synced when they change, or the first time the config is
+    // compiled by this process.
+    let schedules = |config: &CompiledAutoConfig| {
+        config
+            .scheduled_events()
+            .iter()
+            .map(|evt| evt.rule.id)
+            .collect::<Vec<_>>()
     };
+    let synced = previous
+        .as_ref()
+        .is_some_and(|prev| schedules(&prev.config) == schedules(&config));
+    if !synced && let Err(err) = sync_schedules(storage, guild_id, &config).await {
+        tracing::error!(
+            "Failed to sync AutoConfig schedules for guild {}: {}",
+            guild_id,
+            err
+        );
+    }
 
-    let meets_blacklist = filter
-        .get_blacklist()
-        .iter()
-        .any(|p| Regex::new(p).map(|r| r.is_match(val)).unwrap_or(false));
-    let meets_whitelist = filter
-        .get_whitelist()
+    // Only report invalid patterns when they first show up, not every time the cache expires.
+    let reported = previous
+        .map(|prev| {
+            let patterns = |config: &CompiledAutoConfig| {
+                config
+                    .errors()
+                    .iter()
+                    .map(|err| err.pattern.clone())
+                    .collect::<Vec<_>>()
+            };
+            patterns(&prev.config) == patterns(&config)
+        })
+        .unwrap_or(false);
+    if !reported {
+        for err in config.errors() {
+            tracing::warn!(
+                "Invalid pattern in AutoConfig for guild {}: {}",
+                guild_id,
+                err
+            );
+        }
+    }
+
+    Ok(config)
+}
+
+/// Stores the next run of each of a guild's scheduled events, and removes events that are no
+/// longer in the config. Events that are already stored keep their next run, so missed runs
+/// are still caught up on after the config is rewritten.
+async fn sync_schedules(
+    storage: &Storage,
+    guild_id: Id<GuildMarker>,
+    config: &CompiledAutoConfig,
+) -> Result<()> {
+    let now = Utc::now();
+    let mut rule_ids = Vec::new();
+    for evt in config.scheduled_events() {
+        let schedule = match &evt.schedule {
+            Ok(schedule) => schedule,
+            Err(err) => {
+                tracing::warn!(
+                    "Invalid schedule in AutoConfig for guild {}: {}",
+                    guild_id,
+                    err
+                );
+                continue;
+            }
+        };
+        rule_ids.push(evt.rule.id);
+        if let Some(next_run) = schedule.next_after(now) {
+            hourai_sql::ScheduledRule::insert(guild_id, evt.rule.id, next_run, evt.event.clone())
+                .execute(storage.sql())
+                .await?;
+        }
+    }
+    hourai_sql::ScheduledRule::delete_others(guild_id, rule_ids)
+        .execute(storage.sql())
+        .await?;
+    Ok(())
+}
+
+/// Resolves the channel a message was sent in and all of its parents, ordered from most to
+/// least specific.
+async fn channel_ancestry(
+    actions: &ActionExecutor,
+    guild_id: Id<GuildMarker>,
+    channel_id: Id<ChannelMarker>,
+) -> Result<Vec<Id<ChannelMarker>>> {
+    let mut guild = actions.storage().redis().guild(guild_id);
+    let mut ancestry = vec![channel_id];
+    let mut current = channel_id;
+    while ancestry.len() < MAX_CHANNEL_DEPTH {
+        let channel = guild.fetch_resource::<Channel>(current).await?;
+        match channel {
+            Some(channel) if channel.has_parent_id() => {
+                current = Id::new(channel.get_parent_id());
+                ancestry.push(current);
+            }
+            _ => break,
+        }
+    }
+    Ok(ancestry)
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Suppression {
+    Cooldown { rule: u64, scope: u64 },
+    RateLimit,
+}
+
+/// How long a logged suppression stays quiet. Runs of suppressions longer than this are logged
+/// again, and older entries are pruned so the log does not grow without bound.
+const SUPPRESSION_LOG_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// How many logged suppressions are kept before stale ones are pruned.
+const MAX_LOGGED_SUPPRESSIONS: usize = 10_000;
+
+/// Suppressions that have already been logged, and when. Entries are removed once the rule, or
+/// any rule for rate limits, fires again so that each run of suppressions is only logged once.
+static LOGGED_SUPPRESSIONS: LazyLock<DashMap<(Id<GuildMarker>, Suppression), Instant>> =
+    LazyLock::new(DashMap::new);
+
+fn log_suppression(guild_id: Id<GuildMarker>, rule: &Rule, suppression: Suppression) {
+    let now = Instant::now();
+    let key = (guild_id, suppression);
+    if let Some(logged) = LOGGED_SUPPRESSIONS.get(&key)
+        && now.duration_since(*logged) < SUPPRESSION_LOG_INTERVAL
+    {
+        return;
+    }
+    if LOGGED_SUPPRESSIONS.len() >= MAX_LOGGED_SUPPRESSIONS {
+        LOGGED_SUPPRESSIONS
+            .retain(|_, logged| now.duration_since(*logged) < SUPPRESSION_LOG_INTERVAL);
+    }
+    LOGGED_SUPPRESSIONS.insert(key, now);
+    match suppression {
+        Suppression::Cooldown { .. } => tracing::info!(
+            "Suppressed AutoConfig rule {:x} in guild {}: rule is on cooldown",
+            rule.id,
+            guild_id
+        ),
+        Suppression::RateLimit => tracing::info!(
+            "Suppressed AutoConfig rule {:x} in guild {}: guild exceeded its firings per minute",
+            rule.id,
+            guild_id
+        ),
+    }
+}
+
+/// Checks if a rule can fire, starting the rule's cooldown and recording the firing against the
+/// guild's limit. Rules are allowed to fire if the limits cannot be checked, and always fire in
+/// dry runs.
+async fn try_fire(
+    actions: &ActionExecutor,
+    config: &CompiledAutoConfig,
+    guild_id: Id<GuildMarker>,
+    rule: &Rule,
+    ctx: &TemplateContext,
+) -> bool {
+    // Dry runs must not start cooldowns or count towards the guild's limit.
+    if actions.is_dry_run() {
+        return true;
+    }
+    let mut limits = actions.storage().redis().guild(guild_id).auto_limits();
+
+    let mut cooldown_scope = None;
+    if let Some(cooldown) = rule.cooldown.as_ref() {
+        let scope = match cooldown.get_scope() {
+            Cooldown_Scope::GLOBAL => None,
+            Cooldown_Scope::USER => ctx.user_id.map(|id| id.get()),
+            Cooldown_Scope::CHANNEL => ctx.channel_id.map(|id| id.get()),
+        }
+        .unwrap_or(0);
+        let started = limits
+            .try_start_cooldown(rule.id, scope, cooldown.get_seconds())
+            .await
+            .unwrap_or(true);
+        let suppression = Suppression::Cooldown {
+            rule: rule.id,
+            scope,
+        };
+        if !started {
+            log_suppression(guild_id, rule, suppression);
+            return false;
+        }
+        LOGGED_SUPPRESSIONS.remove(&(guild_id, suppression));
+        cooldown_scope = Some(scope);
+    }
+
+    // Rules on cooldown are not counted towards the guild's limit. Rules suppressed by the limit
+    // do not fire, so their cooldown is ended again.
+    let max_firings = u64::from(config.max_firings_per_minute());
+    if max_firings > 0 && limits.record_firing().await.unwrap_or(0) > max_firings {
+        if let Some(scope) = cooldown_scope
+            && let Err(err) = limits.clear_cooldown(rule.id, scope).await
+        {
+            tracing::warn!(
+                "Failed to end cooldown of AutoConfig rule {:x}: {}",
+                rule.id,
+                err
+            );
+        }
+        log_suppression(guild_id, rule, Suppression::RateLimit);
+        return false;
+    }
+    LOGGED_SUPPRESSIONS.remove(&(guild_id, Suppression::RateLimit));
+    true
+}
+
+/// Describes what caused a rule to fire. `trigger` is the name of the event group field the
+/// rule is in.
+fn trigger(trigger: &str, message: Option<DiscordMessage>) -> AutoConfigTrigger {
+    let mut proto = AutoConfigTrigger::new();
+    proto.set_trigger(trigger.to_owned());
+    if let Some(message) = message {
+        proto.set_message(message);
+    }
+    proto
+}
+
+/// Executes a rule's actions against a user, recording them as an event.
+async fn execute_actions(
+    actions: &ActionExecutor,
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+    rule: &Rule,
+    mut trigger: AutoConfigTrigger,
+    to_execute: &[Action],
+    ctx: &TemplateContext,
+) {
+    let to_execute = to_execute
         .iter()
-        .any(|p| Regex::new(p).map(|r| r.is_match(val)).unwrap_or(false));
+        .map(|action| {
+            let mut act = action.clone();
+            act.set_guild_id(guild_id.get());
+            act.set_user_id(user_id.get());
+            act
+        })
+        .collect();
+    trigger.set_rule_id(rule.id);
+    let mut source = EventSource::new();
+    if let Some(channel_id) = ctx.channel_id {
+        source.set_channel_id(channel_id.get());
+    }
+    source.set_auto_config(trigger);
+    actions
+        .execute_event(guild_id, source, to_execute, ctx)
+        .await;
+}
+
+/// Executes the actions of all matching events against a user, skipping rules that are
+/// suppressed by cooldowns or rate limits. Takes the events as a `Vec` so callers do not hold
+/// borrowing iterators across awaits, which keeps the event handler futures `Send`.
+async fn execute_events<'a>(
+    actions: &ActionExecutor,
+    config: &CompiledAutoConfig,
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+    trigger: AutoConfigTrigger,
+    events: MatchedEvents<'a>,
+    ctx: &TemplateContext,
+) {
+    for (rule, to_execute) in events {
+        if try_fire(actions, config, guild_id, rule, ctx).await {
+            let trigger = trigger.clone();
+            execute_actions(actions, guild_id, user_id, rule, trigger, to_execute, ctx).await;
+        }
+    }
+}
+
+/// The stored member information of a user, for events that do not include it.
+#[derive(Default)]
+struct StoredMember {
+    roles: Option<Vec<Id<RoleMarker>>>,
+    joined_at: Option<Timestamp>,
+}
 
-    if !filter.get_blacklist().is_empty() {
-        !meets_blacklist || meets_whitelist
-    } else if !filter.get_whitelist().is_empty() {
-        meets_whitelist
-    } else {
-        true
+/// Looks up the stored member information of a user for events that do not include it. The
+/// member is only looked up if a condition in the config needs it.
+async fn stored_member(
+    actions: &ActionExecutor,
+    config: &CompiledAutoConfig,
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+) -> StoredMember {
+    if !config.needs_member() {
+        return StoredMember::default();
+    }
+    let member = hourai_sql::Member::fetch(guild_id, user_id)
+        .fetch_optional(actions.storage().sql())
+        .await
+        .ok()
+        .flatten();
+    match member {
+        Some(member) => StoredMember {
+            roles: Some(member.role_ids().collect()),
+            joined_at: member
+                .joined_at
+                .and_then(|joined_at| Timestamp::from_micros(joined_at.timestamp_micros()).ok()),
+        },
+        None => StoredMember::default(),
     }
 }
 
 pub struct AutoEngine;
 
 impl AutoEngine {
+    /// Runs the message events for a created or edited message. `member` is the author's member
+    /// information if it was provided with the message. Edits do not provide it, so the stored
+    /// member is used instead.
     pub async fn on_message(
         actions: &ActionExecutor,
-        config: &AutoConfig,
+        config: &CompiledAutoConfig,
         msg: &impl MessageLike,
+        member: Option<&PartialMember>,
         is_edit: bool,
     ) -> Result<()> {
         let guild_id = match msg.guild_id() {
@@ -59,134 +402,538 @@ impl AutoEngine {
             return Ok(());
         }
 
-        let event_mask = if is_edit {
-            MessageEvent_Type::MESSAGE_EDITS
-        } else {
-            MessageEvent_Type::MESSAGE_CREATES
+        let ancestry = Self::ancestry(actions, config, guild_id, msg.channel_id()).await?;
+        let stored = match member {
+            Some(_) => StoredMember::default(),
+            None => stored_member(actions, config, guild_id, msg.author().id()).await,
+        };
+        let condition = ConditionContext {
+            user_id: Some(msg.author().id()),
+            joined_at: member
+                .and_then(|member| member.joined_at)
+                .or(stored.joined_at),
+            roles: member
+                .map(|member| member.roles.as_slice())
+                .or(stored.roles.as_deref()),
+            ancestry: &ancestry,
+            content: Some(msg.content()),
+            attachment_count: Some(msg.attachments().len()),
+            username: Some(msg.author().name()),
+            nickname: member.and_then(|member| member.nick.as_deref()),
         };
 
+        let ctx = TemplateContext {
+            user_id: Some(msg.author().id()),
+            user_name: Some(msg.author().name().to_owned()),
+            channel_id: Some(msg.channel_id()),
+            message: Some(msg.content().to_owned()),
+            ..Default::default()
+        };
+        let source = trigger("on_message", Some(DiscordMessage::from_message(msg)));
         let mut delete = false;
-        let mut to_execute = Vec::new();
-
-        if let Some(events) = config.guild_events.as_ref() {
-            for evt in events.get_on_message() {
-                let evt_type = evt.get_field_type();
-                if evt_type != MessageEvent_Type::ALL_MESSAGES && evt_type != event_mask {
-                    continue;
-                }
-                if !meets_filter(Some(msg.content()), evt.content_filter.as_ref()) {
-                    continue;
-                }
-                if evt.get_delete_message() {
-                    delete = true;
-                }
-                for action in evt.get_action() {
-                    let mut act = action.clone();
-                    act.set_guild_id(guild_id.get());
-                    act.set_user_id(msg.author().id().get());
-                    to_execute.push(act);
-                }
+        for evt in config.message_events(&ancestry, msg.content(), is_edit, &condition) {
+            if !try_fire(actions, config, guild_id, &evt.rule, &ctx).await {
+                continue;
             }
+            if evt.event.get_delete_message() {
+                delete = true;
+            }
+            execute_actions(
+                actions,
+                guild_id,
+                msg.author().id(),
+                &evt.rule,
+                source.clone(),
+                evt.event.get_action(),
+                &ctx,
+            )
+            .await;
         }
 
-        for act in to_execute {
-            let _ = actions.execute_action(&act).await;
-        }
-
-        if delete {
-            let _ = actions
+        if delete
+            && !actions.record_dry_run(|| Operation::DeleteMessages {
+                channel_id: msg.channel_id(),
+                message_ids: vec![msg.id()],
+            })
+            && let Err(err) = actions
                 .http()
                 .delete_message(msg.channel_id(), msg.id())
-                .await;
+                .await
+        {
+            tracing::error!(
+                "Failed to delete message {} for AutoConfig in guild {}: {}",
+                msg.id(),
+                guild_id,
+                err
+            );
         }
 
         Ok(())
     }
 
-    pub async fn on_member_join(
+    pub async fn on_message_delete(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        msg: &impl MessageLike,
+    ) -> Result<()> {
+        let guild_id = match msg.guild_id() {
+            Some(g) => g,
+            None => return Ok(()),
+        };
+        if msg.author().bot() {
+            return Ok(());
+        }
+
+        let ancestry = Self::ancestry(actions, config, guild_id, msg.channel_id()).await?;
+        let stored = stored_member(actions, config, guild_id, msg.author().id()).await;
+        let condition = ConditionContext {
+            user_id: Some(msg.author().id()),
+            joined_at: stored.joined_at,
+            roles: stored.roles.as_deref(),
+            ancestry: &ancestry,
+            content: Some(msg.content()),
+            attachment_count: Some(msg.attachments().len()),
+            username: Some(msg.author().name()),
+            ..Default::default()
+        };
+        let events = config
+            .channel_events(&ancestry, |group| group.on_message_delete.as_slice())
+            .into_iter()
+            .filter(|evt| evt.matches(msg.content(), &condition))
+            .map(|evt| (&evt.rule, evt.event.get_action()))
+            .collect();
+        let ctx = TemplateContext {
+            user_id: Some(msg.author().id()),
+            user_name: Some(msg.author().name().to_owned()),
+            channel_id: Some(msg.channel_id()),
+            message: Some(msg.content().to_owned()),
+            ..Default::default()
+        };
+        let source = trigger("on_message_delete", Some(DiscordMessage::from_message(msg)));
+        execute_events(
+            actions,
+            config,
+            guild_id,
+            msg.author().id(),
+            source,
+            events,
+            &ctx,
+        )
+        .await;
+        Ok(())
+    }
+
+    pub async fn on_reaction_add(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        reaction: &GatewayReaction,
+    ) -> Result<()> {
+        let guild_id = match reaction.guild_id {
+            Some(g) => g,
+            None => return Ok(()),
+        };
+        let member = reaction.member.as_ref();
+        if member.map(|member| member.user.bot).unwrap_or(false) {
+            return Ok(());
+        }
+
+        let emoji = match &reaction.emoji {
+            EmojiReactionType::Unicode { name } => name.as_str(),
+            EmojiReactionType::Custom { name, .. } => name.as_deref().unwrap_or(""),
+        };
+        let username = member.map(|member| member.user.name.as_str());
+        let ancestry = Self::ancestry(actions, config, guild_id, reaction.channel_id).await?;
+        let condition = ConditionContext {
+            user_id: Some(reaction.user_id),
+            joined_at: member.and_then(|member| member.joined_at),
+            roles: member.map(|member| member.roles.as_slice()),
+            ancestry: &ancestry,
+            username,
+            nickname: member.and_then(|member| member.nick.as_deref()),
+            ..Default::default()
+        };
+        let events = config
+            .channel_events(&ancestry, |group| group.on_reaction_add.as_slice())
+            .into_iter()
+            .filter(|evt| evt.matches(emoji, username, &condition))
+            .map(|evt| (&evt.rule, evt.event.get_action()))
+            .collect();
+        let ctx = TemplateContext {
+            user_id: Some(reaction.user_id),
+            user_name: username.map(String::from),
+            channel_id: Some(reaction.channel_id),
+            ..Default::default()
+        };
+        let source = trigger("on_reaction_add", None);
+        execute_events(
+            actions,
+            config,
+            guild_id,
+            reaction.user_id,
+            source,
+            events,
+            &ctx,
+        )
+        .await;
+        Ok(())
+    }
+
+    /// Runs the voice events for a member moving between voice channels. Moving from one channel
+    /// to another counts as leaving the first and joining the second.
+    pub async fn on_voice_update(
         actions: &ActionExecutor,
-        config: &AutoConfig,
+        config: &CompiledAutoConfig,
         guild_id: Id<GuildMarker>,
         member: &Member,
+        before: Option<Id<ChannelMarker>>,
+        after: Option<Id<ChannelMarker>>,
     ) -> Result<()> {
-        if member.user.bot {
+        let user = &member.user;
+        if user.bot || before == after {
             return Ok(());
         }
-        let mut to_execute = Vec::new();
-        if let Some(events) = config.guild_events.as_ref() {
-            for evt in events.get_on_join() {
-                if !meets_filter(Some(&member.user.name), evt.username_filter.as_ref()) {
-                    continue;
-                }
-                for action in evt.get_action() {
-                    let mut act = action.clone();
-                    act.set_guild_id(guild_id.get());
-                    act.set_user_id(member.user.id.get());
-                    to_execute.push(act);
-                }
-            }
-        }
-        for act in to_execute {
-            let _ = actions.execute_action(&act).await;
+
+        let transitions = [(before, true), (after, false)];
+        for (channel_id, is_leave) in transitions {
+            let name = if is_leave {
+                "on_voice_leave"
+            } else {
+                "on_voice_join"
+            };
+            let channel_id = match channel_id {
+                Some(id) => id,
+                None => continue,
+            };
+            let ancestry = Self::ancestry(actions, config, guild_id, channel_id).await?;
+            let condition = ConditionContext {
+                ancestry: &ancestry,
+                ..member_condition(member)
+            };
+            let events = config
+                .channel_events(&ancestry, |group| {
+                    if is_leave {
+                        group.on_voice_leave.as_slice()
+                    } else {
+                        group.on_voice_join.as_slice()
+                    }
+                })
+                .into_iter()
+                .filter(|evt| evt.matches(&user.name, &condition))
+                .map(|evt| (&evt.rule, evt.event.get_action()))
+                .collect();
+            let ctx = TemplateContext {
+                user_id: Some(user.id),
+                user_name: Some(user.name.clone()),
+                channel_id: Some(channel_id),
+                ..Default::default()
+            };
+            let source = trigger(name, None);
+            execute_events(actions, config, guild_id, user.id, source, events, &ctx).await;
         }
         Ok(())
     }
 
-    pub async fn on_member_remove(
+    /// Runs the nickname and role change events for a member update. `before` is the last known
+    /// state of the member, if any. Nothing is run if the previous state is not known.
+    pub async fn on_member_update(
         actions: &ActionExecutor,
-        config: &AutoConfig,
-        guild_id: Id<GuildMarker>,
-        user: &User,
+        config: &CompiledAutoConfig,
+        before: Option<&hourai_sql::Member>,
+        after: &MemberUpdate,
     ) -> Result<()> {
-        if user.bot {
+        let before = match before {
+            Some(before) => before,
+            None => return Ok(()),
+        };
+        if after.user.bot {
             return Ok(());
         }
-        let mut to_execute = Vec::new();
-        if let Some(events) = config.guild_events.as_ref() {
-            for evt in events.get_on_leave() {
-                if !meets_filter(Some(&user.name), evt.username_filter.as_ref()) {
-                    continue;
-                }
-                for action in evt.get_action() {
-                    let mut act = action.clone();
-                    act.set_guild_id(guild_id.get());
-                    act.set_user_id(user.id.get());
-                    to_execute.push(act);
-                }
-            }
+        let events = match config.guild_events() {
+            Some(events) => events,
+            None => return Ok(()),
+        };
+
+        let condition = member_update_condition(after);
+        let mut to_execute: Vec<(&str, MatchedEvents)> = Vec::new();
+        if before.nickname != after.nick {
+            to_execute.push((
+                "on_nickname_change",
+                events
+                    .on_nickname_change
+                    .iter()
+                    .filter(|evt| evt.matches(after.nick.as_deref(), &condition))
+                    .map(|evt| (&evt.rule, evt.event.get_action()))
+                    .collect(),
+            ));
         }
-        for act in to_execute {
-            let _ = actions.execute_action(&act).await;
+
+        let previous: HashSet<_> = before.role_ids().collect();
+        let current: HashSet<_> = after.roles.iter().copied().collect();
+        let gained: Vec<_> = current.difference(&previous).copied().collect();
+        let lost: Vec<_> = previous.difference(&current).copied().collect();
+        to_execute.push((
+            "on_role_gained",
+            events
+                .on_role_gained
+                .iter()
+                .filter(|evt| evt.matches(&gained, &condition))
+                .map(|evt| (&evt.rule, evt.event.get_action()))
+                .collect(),
+        ));
+        to_execute.push((
+            "on_role_lost",
+            events
+                .on_role_lost
+                .iter()
+                .filter(|evt| evt.matches(&lost, &condition))
+                .map(|evt| (&evt.rule, evt.event.get_action()))
+                .collect(),
+        ));
+
+        let ctx = TemplateContext {
+            user_id: Some(after.user.id),
+            user_name: Some(after.user.name.clone()),
+            ..Default::default()
+        };
+        for (name, events) in to_execute {
+            execute_events(
+                actions,
+                config,
+                after.guild_id,
+                after.user.id,
+                trigger(name, None),
+                events,
+                &ctx,
+            )
+            .await;
         }
         Ok(())
     }
 
+    pub async fn on_member_timeout(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        member: &MemberUpdate,
+    ) -> Result<()> {
+        let condition = member_update_condition(member);
+        Self::on_user_change(
+            actions,
+            config,
+            member.guild_id,
+            &member.user,
+            &condition,
+            "on_timeout",
+            |group| &group.on_timeout,
+        )
+        .await
+    }
+
+    pub async fn on_member_join(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        guild_id: Id<GuildMarker>,
+        member: &Member,
+    ) -> Result<()> {
+        let condition = member_condition(member);
+        Self::on_user_change(
+            actions,
+            config,
+            guild_id,
+            &member.user,
+            &condition,
+            "on_join",
+            |group| &group.on_join,
+        )
+        .await
+    }
+
+    pub async fn on_member_remove(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        guild_id: Id<GuildMarker>,
+        user: &User,
+    ) -> Result<()> {
+        let stored = stored_member(actions, config, guild_id, user.id).await;
+        let condition = ConditionContext {
+            joined_at: stored.joined_at,
+            ..user_condition(user, stored.roles.as_deref())
+        };
+        Self::on_user_change(
+            actions,
+            config,
+            guild_id,
+            user,
+            &condition,
+            "on_leave",
+            |group| &group.on_leave,
+        )
+        .await
+    }
+
     pub async fn on_member_ban(
         actions: &ActionExecutor,
-        config: &AutoConfig,
+        config: &CompiledAutoConfig,
         guild_id: Id<GuildMarker>,
         user: &User,
+    ) -> Result<()> {
+        let stored = stored_member(actions, config, guild_id, user.id).await;
+        let condition = ConditionContext {
+            joined_at: stored.joined_at,
+            ..user_condition(user, stored.roles.as_deref())
+        };
+        Self::on_user_change(
+            actions,
+            config,
+            guild_id,
+            user,
+            &condition,
+            "on_ban",
+            |group| &group.on_ban,
+        )
+        .await
+    }
+
+    async fn on_user_change(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        guild_id: Id<GuildMarker>,
+        user: &User,
+        condition: &ConditionContext<'_>,
+        name: &str,
+        select: fn(&CompiledEventGroup) -> &[CompiledUserChangeEvent],
     ) -> Result<()> {
         if user.bot {
             return Ok(());
         }
-        let mut to_execute = Vec::new();
-        if let Some(events) = config.guild_events.as_ref() {
-            for evt in events.get_on_ban() {
-                if !meets_filter(Some(&user.name), evt.username_filter.as_ref()) {
-                    continue;
-                }
-                for action in evt.get_action() {
-                    let mut act = action.clone();
-                    act.set_guild_id(guild_id.get());
-                    act.set_user_id(user.id.get());
-                    to_execute.push(act);
-                }
-            }
+        let events = config
+            .guild_events()
+            .into_iter()
+            .flat_map(select)
+            .filter(|evt| evt.matches(&user.name, condition))
+            .map(|evt| (&evt.rule, evt.event.get_action()))
+            .collect();
+        let ctx = TemplateContext {
+            user_id: Some(user.id),
+            user_name: Some(user.name.clone()),
+            ..Default::default()
+        };
+        let source = trigger(name, None);
+        execute_events(actions, config, guild_id, user.id, source, events, &ctx).await;
+        Ok(())
+    }
+
+    /// Resolves a channel's ancestry, skipping the lookup if no channel has its own events and
+    /// no condition checks the channel.
+    async fn ancestry(
+        actions: &ActionExecutor,
+        config: &CompiledAutoConfig,
+        guild_id: Id<GuildMarker>,
+        channel_id: Id<ChannelMarker>,
+    ) -> Result<Vec<Id<ChannelMarker>>> {
+        if config.has_channel_events() || config.needs_ancestry() {
+            channel_ancestry(actions, guild_id, channel_id).await
+        } else {
+            Ok(vec![channel_id])
         }
-        for act in to_execute {
-            let _ = actions.execute_action(&act).await;
+    }
+}
+
+fn user_condition<'a>(user: &'a User, roles: Option<&'a [Id<RoleMarker>]>) -> ConditionContext<'a> {
+    ConditionContext {
+        user_id: Some(user.id),
+        roles,
+        username: Some(&user.name),
+        ..Default::default()
+    }
+}
+
+fn member_condition(member: &Member) -> ConditionContext<'_> {
+    ConditionContext {
+        joined_at: member.joined_at,
+        nickname: member.nick.as_deref(),
+        ..user_condition(&member.user, Some(&member.roles))
+    }
+}
+
+fn member_update_condition(member: &MemberUpdate) -> ConditionContext<'_> {
+    ConditionContext {
+        joined_at: member.joined_at,
+        nickname: member.nick.as_deref(),
+        ..user_condition(&member.user, Some(&member.roles))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hourai::{
+        http,
+        proto::{
+            auto_config::{Cooldown, MessageEvent, UserChangeEvent},
+            cache::CachedMessageProto,
+        },
+    };
+    use hourai_testing::{guild_id, storage, user};
+
+    #[tokio::test]
+    async fn test_rule_dry_run() {
+        let Some(storage) = storage()
+            .await
+            .expect("Failed to connect to the test databases")
+        else {
+            return;
+        };
+        let guild_id = guild_id();
+        let user = user(2);
+
+        let mut kick = Action::new();
+        kick.mut_kick();
+        let mut cooldown = Cooldown::new();
+        cooldown.set_seconds(3600);
+        let mut event = UserChangeEvent::new();
+        event.mut_action().push(kick);
+        event.set_cooldown(cooldown);
+        let mut delete = MessageEvent::new();
+        delete.set_delete_message(true);
+        let mut config = AutoConfig::new();
+        config.mut_guild_events().mut_on_leave().push(event);
+        config.mut_guild_events().mut_on_message().push(delete);
+        let config = CompiledAutoConfig::new(&config);
+
+        let http = Arc::new(http::Client::new(String::new()));
+        let actions = ActionExecutor::new(user.clone(), http, storage).dry_run();
+        // Dry runs do not start the rule's cooldown, so it fires both times.
+        for _ in 0..2 {
+            AutoEngine::on_member_remove(&actions, &config, guild_id, &user)
+                .await
+                .expect("Failed to run the rule");
         }
-        Ok(())
+        let mut msg = CachedMessageProto::new();
+        msg.set_id(3);
+        msg.set_channel_id(4);
+        msg.set_guild_id(guild_id.get());
+        msg.mut_author().set_id(user.id.get());
+        msg.set_content("spam".to_owned());
+        AutoEngine::on_message(&actions, &config, &msg, None, false)
+            .await
+            .expect("Failed to run the message rule");
+
+        let kick = Operation::Kick {
+            guild_id,
+            user_id: user.id,
+        };
+        let operations = actions.take_operations();
+        assert_eq!(operations.iter().filter(|op| **op == kick).count(), 2);
+        assert_eq!(
+            operations
+                .iter()
+                .filter(|op| matches!(op, Operation::RecordEvent { .. }))
+                .count(),
+            3
+        );
+        assert!(operations.contains(&Operation::DeleteMessages {
+            channel_id: Id::new(4),
+            message_ids: vec![Id::new(3)],
+        }));
     }
 }
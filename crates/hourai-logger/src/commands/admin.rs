@@ -11,7 +11,6 @@ use hourai::{
             marker::{ChannelMarker, MessageMarker},
         },
         user::User,
-        util::Timestamp,
     },
     proto::action::{Action, BanMember_Type, StatusType},
 };
@@ -25,7 +24,7 @@ use std::{
 const MAX_PRUNED_MESSAGES: usize = 2000;
 const MAX_PRUNED_MESSAGES_PER_BATCH: usize = 100;
 
-fn parse_duration(duration: &str) -> Result<Duration> {
+pub(super) fn parse_duration(duration: &str) -> Result<Duration> {
     humantime::parse_duration(duration).map_err(|err| {
         anyhow::anyhow!(InteractionError::InvalidArgument(format!(
             "Cannot parse `{}` as a duration: {}",
@@ -34,7 +33,7 @@ fn parse_duration(duration: &str) -> Result<Duration> {
     })
 }
 
-fn build_reason(action: &str, authorizer: &User, reason: Option<&String>) -> String {
+pub(super) fn build_reason(action: &str, authorizer: &User, reason: Option<&String>) -> String {
     if let Some(reason) = reason {
         format!(
             "{} by {}#{:04} for: {}",
@@ -61,18 +60,17 @@ pub(super) async fn ban(ctx: &CommandContext, executor: &ActionExecutor) -> Resu
         .member
         .as_ref()
         .ok_or(InteractionError::NotInGuild)?;
-    let storage = executor.storage();
-    let authorizer_roles = storage
-        .redis()
-        .guild(guild_id)
-        .role_set(&authorizer.roles)
-        .await?;
 
-    // TODO(james7132): Properly display the errors.
+    let preview = ctx.get_flag("preview").unwrap_or(false);
+    let dry_run = preview.then(|| executor.dry_run());
+    let executor = dry_run.as_ref().unwrap_or(executor);
+
     let mut total = 0;
     let mut errors = Vec::new();
+    let mut previewed = Vec::new();
     let mut base = Action::new();
     base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
     base.mut_ban().set_field_type(if soft {
         BanMember_Type::SOFTBAN
     } else {
@@ -89,35 +87,32 @@ pub(super) async fn ban(ctx: &CommandContext, executor: &ActionExecutor) -> Resu
 
     for user_id in ctx.all_users("user") {
         total += 1;
-        if let Some(member) = ctx.resolve_member(user_id) {
-            let roles = storage
-                .redis()
-                .guild(guild_id)
-                .role_set(&member.roles)
-                .await?;
-            if roles >= authorizer_roles {
-                errors.push(format!(
-                    "{}: Has higher roles, not authorized to {}.",
-                    user_id,
-                    if soft { "softban" } else { "ban" }
-                ));
-                continue;
-            }
-        }
-
         let mut action = base.clone();
         action.set_user_id(user_id.get());
-        if let Err(err) = executor.execute_action(&action).await {
-            tracing::error!("Error while running /ban on {}: {}", user_id, err);
-            errors.push(format!("{}: {}", user_id, err));
+        match executor.execute_action(&action).await {
+            Ok(()) if preview => previewed.push(action),
+            Ok(()) => {}
+            Err(err) => {
+                tracing::error!("Error while running /ban on {}: {}", user_id, err);
+                errors.push(format!("<@{}>: {}", user_id, err));
+            }
         }
     }
 
+    if preview {
+        return super::preview::confirmable_preview(
+            executor,
+            previewed,
+            Permissions::BAN_MEMBERS,
+            &errors,
+        )
+        .await;
+    }
     Ok(Response::direct().content(format!("{} {} users.", action, total - errors.len())))
 }
 
-pub(super) async fn timeout(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
-    const DAY_SECS: i64 = 86400;
+pub(super) async fn timeout(ctx: &CommandContext, executor: &ActionExecutor) -> Result<Response> {
+    const DAY_SECS: u64 = 86400;
 
     ctx.defer().await?;
     let guild_id = ctx.guild_id()?;
@@ -130,16 +125,9 @@ pub(super) async fn timeout(ctx: &CommandContext, storage: &Storage) -> Result<R
         .member
         .as_ref()
         .ok_or(InteractionError::NotInGuild)?;
-    let mut guild = storage.redis().guild(guild_id);
-    let authorizer_roles = guild.role_set(&authorizer.roles).await?;
-    let reason = build_reason(
-        "Timed out",
-        authorizer.user.as_ref().unwrap_or_else(|| ctx.user()),
-        ctx.get_string("reason").ok(),
-    );
 
     let duration = if let Ok(duration) = ctx.get_string("duration") {
-        parse_duration(duration)?.as_secs() as i64
+        parse_duration(duration)?.as_secs()
     } else {
         // Default to 1 day
         DAY_SECS
@@ -151,34 +139,24 @@ pub(super) async fn timeout(ctx: &CommandContext, storage: &Storage) -> Result<R
         ));
     }
 
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
-    let expiration = Timestamp::from_secs(now + duration)
-        .map_err(|e| anyhow::anyhow!("Invalid timestamp: {}", e))?;
+    let mut base = Action::new();
+    base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
+    base.set_duration(duration);
+    base.mut_timeout().set_field_type(StatusType::APPLY);
+    base.set_reason(build_reason(
+        "Timed out",
+        authorizer.user.as_ref().unwrap_or_else(|| ctx.user()),
+        ctx.get_string("reason").ok(),
+    ));
 
     let mut total = 0;
     let mut errors = Vec::new();
     for member_id in ctx.all_users("user") {
         total += 1;
-        if let Some(member) = ctx.resolve_member(member_id) {
-            let roles = guild.role_set(&member.roles).await?;
-            if roles >= authorizer_roles {
-                errors.push(format!(
-                    "{}: Has higher or equal roles, not authorized to time out.",
-                    member_id
-                ));
-                continue;
-            }
-        }
-
-        let request = ctx
-            .http
-            .update_guild_member(guild_id, member_id)
-            .communication_disabled_until(Some(expiration))
-            .reason(&reason);
-        if let Err(err) = request.await {
+        let mut action = base.clone();
+        action.set_user_id(member_id.get());
+        if let Err(err) = executor.execute_action(&action).await {
             tracing::error!("Error while running /timeout on {}: {}", member_id, err);
             errors.push(format!("{}: {}", member_id, err));
         }
@@ -187,7 +165,7 @@ pub(super) async fn timeout(ctx: &CommandContext, storage: &Storage) -> Result<R
     Ok(Response::direct().content(format!("Timed out {} users.", total - errors.len())))
 }
 
-pub(super) async fn kick(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+pub(super) async fn kick(ctx: &CommandContext, executor: &ActionExecutor) -> Result<Response> {
     ctx.defer().await?;
     let guild_id = ctx.guild_id()?;
     if !ctx.has_user_permission(Permissions::KICK_MEMBERS) {
@@ -199,39 +177,46 @@ pub(super) async fn kick(ctx: &CommandContext, storage: &Storage) -> Result<Resp
         .member
         .as_ref()
         .ok_or(InteractionError::NotInGuild)?;
-    let mut guild = storage.redis().guild(guild_id);
-    let authorizer_roles = guild.role_set(&authorizer.roles).await?;
-    let reason = build_reason(
+    let mut base = Action::new();
+    base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
+    base.mut_kick();
+    base.set_reason(build_reason(
         "Kicked",
         authorizer.user.as_ref().unwrap_or_else(|| ctx.user()),
         ctx.get_string("reason").ok(),
-    );
+    ));
+
+    let preview = ctx.get_flag("preview").unwrap_or(false);
+    let dry_run = preview.then(|| executor.dry_run());
+    let executor = dry_run.as_ref().unwrap_or(executor);
 
     let mut total = 0;
     let mut errors = Vec::new();
+    let mut previewed = Vec::new();
     for member_id in ctx.all_users("user") {
         total += 1;
-        if let Some(member) = ctx.resolve_member(member_id) {
-            let roles = guild.role_set(&member.roles).await?;
-            if roles >= authorizer_roles {
-                errors.push(format!(
-                    "{}: Has higher or equal roles, not authorized to kick.",
-                    member_id
-                ));
-                continue;
+        let mut action = base.clone();
+        action.set_user_id(member_id.get());
+        match executor.execute_action(&action).await {
+            Ok(()) if preview => previewed.push(action),
+            Ok(()) => {}
+            Err(err) => {
+                tracing::error!("Error while running /kick on {}: {}", member_id, err);
+                errors.push(format!("<@{}>: {}", member_id, err));
             }
         }
-
-        let request = ctx
-            .http
-            .remove_guild_member(guild_id, member_id)
-            .reason(&reason);
-        if let Err(err) = request.await {
-            tracing::error!("Error while running /kick on {}: {}", member_id, err);
-            errors.push(format!("{}: {}", member_id, err));
-        }
     }
 
+    if preview {
+        return super::preview::confirmable_preview(
+            executor,
+            previewed,
+            Permissions::KICK_MEMBERS,
+            &errors,
+        )
+        .await;
+    }
     Ok(Response::direct().content(format!("Kicked {} users.", total - errors.len())))
 }
 
@@ -249,6 +234,7 @@ pub(super) async fn change_role(
     let authorizer = ctx.member().ok_or(InteractionError::NotInGuild)?;
     let mut base = Action::new();
     base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
     base.mut_change_role()
         .mut_role_ids()
         .push(ctx.get_role("role")?.get());
@@ -303,6 +289,7 @@ pub(super) async fn deafen(ctx: &CommandContext, executor: &ActionExecutor) -> R
     let authorizer = ctx.member().ok_or(InteractionError::NotInGuild)?;
     let mut base = Action::new();
     base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
     base.mut_deafen().set_field_type(StatusType::APPLY);
     base.set_reason(build_reason(
         "Deafened",
@@ -338,6 +325,7 @@ pub(super) async fn mute(ctx: &CommandContext, executor: &ActionExecutor) -> Res
     let authorizer = ctx.member().ok_or(InteractionError::NotInGuild)?;
     let mut base = Action::new();
     base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
     base.mut_mute().set_field_type(StatusType::APPLY);
     base.set_reason(build_reason(
         "Muted",
@@ -542,17 +530,17 @@ pub(super) async fn role_allow(ctx: &CommandContext, storage: &Storage) -> Resul
     let mut config: hourai::proto::guild_configs::RoleConfig =
         storage.redis().guild(guild_id).configs().get().await?;
     let mut ids = config.get_self_serve_role_ids().to_vec();
-    if !ids.contains(&role_id.get()) {
+    let warnings = if !ids.contains(&role_id.get()) {
         ids.push(role_id.get());
         config.set_self_serve_role_ids(ids);
-        storage
-            .redis()
-            .guild(guild_id)
-            .configs()
-            .set(config)
-            .await?;
-    }
-    Ok(Response::direct().content(format!("Allowed self-serve for <@&{}>.", role_id)))
+        hourai_storage::set_config(guild_id, storage, config).await?
+    } else {
+        Vec::new()
+    };
+    Ok(Response::direct().content(with_warnings(
+        format!("Allowed self-serve for <@&{}>.", role_id),
+        &warnings,
+    )))
 }
 
 pub(super) async fn role_forbid(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
@@ -571,13 +559,11 @@ pub(super) async fn role_forbid(ctx: &CommandContext, storage: &Storage) -> Resu
         .filter(|id| *id != role_id.get())
         .collect();
     config.set_self_serve_role_ids(ids);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-    Ok(Response::direct().content(format!("Disallowed self-serve for <@&{}>.", role_id)))
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+    Ok(Response::direct().content(with_warnings(
+        format!("Disallowed self-serve for <@&{}>.", role_id),
+        &warnings,
+    )))
 }
 
 pub(super) async fn role_get(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
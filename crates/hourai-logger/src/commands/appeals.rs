@@ -0,0 +1,180 @@
+use super::prelude::*;
+use hourai::{
+    interactions::{ComponentContext, ModalContext},
+    models::{
+        channel::message::{
+            Component,
+            component::{ActionRow, TextInput, TextInputStyle},
+        },
+        http::interaction::{InteractionResponse, InteractionResponseType},
+    },
+    proto::message_components::{AppealButton, AppealButtonOption, MessageComponentProto},
+};
+use hourai_sql::{Appeal, AppealStatus};
+use hourai_storage::appeals::{self, AppealError, MAX_APPEAL_LENGTH};
+
+/// The custom ID of the text input in the appeal form.
+const CONTENT_ID: &str = "content";
+
+pub(super) async fn handle_component(
+    ctx: ComponentContext,
+    executor: &ActionExecutor,
+) -> Result<()> {
+    let metadata = ctx.metadata()?;
+    let button = metadata.get_appeal_button();
+    let appeal = match Appeal::fetch(button.get_appeal_id())
+        .fetch_optional(executor.storage().sql())
+        .await?
+    {
+        Some(appeal) => appeal,
+        None => return reply_error(&ctx, &AppealError::NotFound.to_string()).await,
+    };
+    match button.get_button_option() {
+        AppealButtonOption::APPEAL_BUTTON_APPEAL => open_form(&ctx, &appeal).await,
+        AppealButtonOption::APPEAL_BUTTON_ACCEPT => resolve(&ctx, appeal, true, executor).await,
+        AppealButtonOption::APPEAL_BUTTON_DENY => resolve(&ctx, appeal, false, executor).await,
+        AppealButtonOption::APPEAL_BUTTON_UNSPECIFIED => Ok(()),
+    }
+}
+
+/// Handles the appeal form submitted by the appealed user.
+pub(super) async fn handle_modal(ctx: ModalContext, executor: &ActionExecutor) -> Result<()> {
+    let metadata = ctx.metadata()?;
+    let appeal_id = metadata.get_appeal_button().get_appeal_id();
+    ctx.defer_ephemeral().await?;
+
+    let appeal = Appeal::fetch(appeal_id)
+        .fetch_optional(executor.storage().sql())
+        .await?;
+    if appeal.map(|appeal| appeal.user_id()) != Some(ctx.user().id) {
+        ctx.reply(Response::ephemeral().content(format!(":x: Error: {}", AppealError::NotFound)))
+            .await?;
+        return Ok(());
+    }
+
+    let content = ctx.text_value(CONTENT_ID).unwrap_or_default();
+    let result = appeals::submit(executor.http(), executor.storage(), appeal_id, content).await;
+    let response = Response::ephemeral();
+    match result {
+        Ok(_) => {
+            ctx.reply(response.content(
+                "Your appeal has been submitted. You will be messaged once the moderators have \
+                 reviewed it.",
+            ))
+            .await
+        }
+        Err(err) => {
+            if let Some(appeal_err) = err.downcast_ref::<AppealError>() {
+                ctx.reply(response.content(format!(":x: Error: {}", appeal_err)))
+                    .await?;
+                Ok(())
+            } else {
+                ctx.reply(response.content(":x: Fatal Error: Internal Error has occured."))
+                    .await?;
+                Err(err)
+            }
+        }
+    }
+}
+
+async fn open_form(ctx: &ComponentContext, appeal: &Appeal) -> Result<()> {
+    if appeal.status != AppealStatus::Open {
+        return reply_error(ctx, &AppealError::AlreadySubmitted.to_string()).await;
+    }
+
+    let mut button = AppealButton::new();
+    button.set_button_option(AppealButtonOption::APPEAL_BUTTON_APPEAL);
+    button.set_appeal_id(appeal.id);
+    let mut proto = MessageComponentProto::new();
+    proto.set_appeal_button(button);
+
+    let input = Component::ActionRow(ActionRow {
+        components: vec![Component::TextInput(TextInput {
+            custom_id: CONTENT_ID.to_owned(),
+            label: "Why should this be reconsidered?".to_owned(),
+            max_length: Some(MAX_APPEAL_LENGTH as u16),
+            min_length: Some(1),
+            placeholder: None,
+            required: Some(true),
+            style: TextInputStyle::Paragraph,
+            value: None,
+        })],
+    });
+    let form = Response::modal(hourai::interactions::proto_to_custom_id(&proto)?, "Appeal")
+        .components(&[input]);
+    ctx.reply_raw(InteractionResponse {
+        kind: InteractionResponseType::Modal,
+        data: Some(form.into()),
+    })
+    .await
+}
+
+async fn resolve(
+    ctx: &ComponentContext,
+    appeal: Appeal,
+    accept: bool,
+    executor: &ActionExecutor,
+) -> Result<()> {
+    let guild_id = ctx.guild_id()?;
+    let member_roles = ctx
+        .member()
+        .map(|member| member.roles.as_slice())
+        .unwrap_or_default();
+    if appeal.guild_id() != guild_id
+        || !hourai_storage::is_moderator(
+            guild_id,
+            member_roles.iter().copied(),
+            executor.storage().redis(),
+        )
+        .await?
+    {
+        let err = InteractionError::MissingPermission("Only moderators can resolve appeals.");
+        return reply_error(ctx, &err.to_string()).await;
+    }
+
+    ctx.defer_update().await?;
+    let appeal = match appeals::resolve(executor, appeal.id, ctx.user(), accept).await {
+        Ok(appeal) => appeal,
+        // Another moderator got to it first. Show how they resolved it instead.
+        Err(err) if matches!(err.downcast_ref(), Some(AppealError::AlreadyResolved)) => {
+            Appeal::fetch(appeal.id)
+                .fetch_one(executor.storage().sql())
+                .await?
+        }
+        // Nothing was marked as resolved, so keep the buttons to let the moderator retry.
+        Err(err) => {
+            tracing::error!("Failed to resolve appeal {}: {}", appeal.id, err);
+            let verb = if accept { "accept" } else { "deny" };
+            return ctx
+                .reply(
+                    Response::direct()
+                        .content(format!(
+                            "{}\n:x: Error: Failed to {} this appeal: {}",
+                            appeals::describe_appeal(&appeal),
+                            verb,
+                            err
+                        ))
+                        .components(&[appeals::resolution_buttons(appeal.id)?]),
+                )
+                .await;
+        }
+    };
+    ctx.reply(
+        Response::direct()
+            .content(appeals::describe_appeal(&appeal))
+            .components(&[]),
+    )
+    .await
+}
+
+async fn reply_error(ctx: &ComponentContext, error: &str) -> Result<()> {
+    ctx.reply_raw(InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(
+            Response::ephemeral()
+                .content(format!(":x: Error: {}", error))
+                .into(),
+        ),
+    })
+    .await
+}
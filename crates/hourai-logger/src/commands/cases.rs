@@ -0,0 +1,105 @@
+use super::prelude::*;
+use anyhow::Result;
+use hourai_sql::{Case, CaseReasonEdit};
+use hourai_storage::cases;
+use twilight_util::builder::embed::*;
+
+/// The most cases shown by `/cases`.
+const MAX_LISTED_CASES: i64 = 25;
+
+/// The error shown to users who are not moderators.
+const MODERATORS_ONLY: &str = "Only moderators can view or edit cases.";
+
+async fn fetch_case(ctx: &CommandContext, storage: &Storage) -> Result<Case> {
+    let number = ctx.get_int("number")?;
+    let not_found =
+        || InteractionError::InvalidArgument(format!("Case #{} does not exist.", number));
+    let case_number = i32::try_from(number).map_err(|_| not_found())?;
+    Ok(Case::fetch(ctx.guild_id()?, case_number)
+        .fetch_optional(storage.sql())
+        .await?
+        .ok_or_else(not_found)?)
+}
+
+pub(super) async fn case_view(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let case = fetch_case(ctx, storage).await?;
+    let edits = CaseReasonEdit::fetch(guild_id, case.case_number)
+        .fetch_all(storage.sql())
+        .await?;
+    let mut content = cases::describe_case(&case);
+    if !edits.is_empty() {
+        content.push_str("\n\n**Reason History**");
+        for edit in edits.iter() {
+            content.push_str(&format!(
+                "\n<t:{}:f> <@{}>: {} -> {}",
+                edit.timestamp.timestamp(),
+                edit.editor_id,
+                edit.previous_reason
+                    .as_deref()
+                    .unwrap_or("No reason provided"),
+                edit.reason
+            ));
+        }
+    }
+    Ok(Response::direct().content(content))
+}
+
+pub(super) async fn case_reason(
+    ctx: &CommandContext,
+    actions: &ActionExecutor,
+) -> Result<Response> {
+    ctx.defer().await?;
+    let storage = actions.storage();
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let reason = ctx.get_string("reason")?;
+    if reason.is_empty() {
+        anyhow::bail!(InteractionError::InvalidArgument(
+            "A non-empty reason must be provided.".to_owned()
+        ));
+    }
+    let mut case = fetch_case(ctx, storage).await?;
+    cases::update_reason(actions.http(), storage, &mut case, ctx.user().id, reason).await?;
+    Ok(Response::direct().content(format!(
+        "Updated the reason for case #{}.",
+        case.case_number
+    )))
+}
+
+pub(super) async fn cases(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let user_id = ctx.get_user("user")?;
+    let cases = Case::fetch_user(guild_id, user_id, MAX_LISTED_CASES)
+        .fetch_all(storage.sql())
+        .await?;
+    if cases.is_empty() {
+        return Ok(Response::ephemeral().content(format!("<@{}> has no cases.", user_id)));
+    }
+
+    let mut desc = String::new();
+    for case in cases.iter() {
+        desc.push_str(&format!(
+            "**#{}** {} by <@{}> <t:{}:R>: {}\n",
+            case.case_number,
+            case.action,
+            case.moderator_id,
+            case.timestamp.timestamp(),
+            case.reason.as_deref().unwrap_or("No reason provided"),
+        ));
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!("Cases for {}", user_id))
+        .description(desc)
+        .footer(EmbedFooterBuilder::new(format!(
+            "Showing up to the {} most recent cases.",
+            MAX_LISTED_CASES
+        )))
+        .build();
+    Ok(Response::direct().embed(embed))
+}
@@ -2,7 +2,10 @@ use super::prelude::*;
 use anyhow::Result;
 use hourai::{
     models::guild::Permissions,
-    proto::guild_configs::{AnnouncementConfig, LoggingConfig, MusicConfig},
+    proto::guild_configs::{
+        AnnouncementConfig, AnnouncementTypeConfig, LoggingConfig, ModerationConfig, MusicConfig,
+    },
+    util::template,
 };
 
 pub async fn reddit_add(_ctx: &CommandContext) -> Result<Response> {
@@ -26,15 +29,10 @@ pub async fn setdj(ctx: &CommandContext, storage: &Storage) -> Result<Response>
     let role_id = ctx.get_role("role")?;
     let mut config: MusicConfig = storage.redis().guild(guild_id).configs().get().await?;
     config.set_dj_role_id(vec![role_id.get()]);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-    Ok(Response::direct().content(format!(
-        "Set <@&{}> as the DJ role for this server.",
-        role_id
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+    Ok(Response::direct().content(with_warnings(
+        format!("Set <@&{}> as the DJ role for this server.", role_id),
+        &warnings,
     )))
 }
 
@@ -49,18 +47,16 @@ pub async fn log_edited(ctx: &CommandContext, storage: &Storage) -> Result<Respo
     let enabled = !edited.get_enabled();
     edited.set_enabled(enabled);
     edited.set_output_channel_id(ctx.channel_id().get());
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
 
     let state = if enabled { "enabled" } else { "disabled" };
-    Ok(Response::direct().content(format!(
-        "Logging of edited messages has been {} in <#{}>.",
-        state,
-        ctx.channel_id()
+    Ok(Response::direct().content(with_warnings(
+        format!(
+            "Logging of edited messages has been {} in <#{}>.",
+            state,
+            ctx.channel_id()
+        ),
+        &warnings,
     )))
 }
 
@@ -75,18 +71,16 @@ pub async fn log_deleted(ctx: &CommandContext, storage: &Storage) -> Result<Resp
     let enabled = !deleted.get_enabled();
     deleted.set_enabled(enabled);
     deleted.set_output_channel_id(ctx.channel_id().get());
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
 
     let state = if enabled { "enabled" } else { "disabled" };
-    Ok(Response::direct().content(format!(
-        "Logging of deleted messages has been {} in <#{}>.",
-        state,
-        ctx.channel_id()
+    Ok(Response::direct().content(with_warnings(
+        format!(
+            "Logging of deleted messages has been {} in <#{}>.",
+            state,
+            ctx.channel_id()
+        ),
+        &warnings,
     )))
 }
 
@@ -101,49 +95,51 @@ pub async fn setmodlog(ctx: &CommandContext, storage: &Storage) -> Result<Respon
         .unwrap_or_else(|_| ctx.channel_id());
     let mut config: LoggingConfig = storage.redis().guild(guild_id).configs().get().await?;
     config.set_modlog_channel_id(channel_id.get());
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-
-    Ok(Response::direct().content(format!("Set <#{}> as the modlog channel.", channel_id)))
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+
+    Ok(Response::direct().content(with_warnings(
+        format!("Set <#{}> as the modlog channel.", channel_id),
+        &warnings,
+    )))
 }
 
-pub async fn announce_join(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+/// Sets the channel submitted appeals are posted to. Banned and escalated users are only offered
+/// appeals while one is set.
+pub async fn setappeals(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
     ctx.defer().await?;
     let guild_id = ctx.guild_id()?;
     if !ctx.has_user_permission(Permissions::MANAGE_GUILD) {
         anyhow::bail!(InteractionError::MissingPermission("Manage Server"));
     }
+    let mut config: ModerationConfig = storage.redis().guild(guild_id).configs().get().await?;
+    if ctx.get_flag("disable").unwrap_or(false) {
+        config.clear_appeals();
+        let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+        return Ok(Response::direct().content(with_warnings("Disabled appeals.", &warnings)));
+    }
     let channel_id = ctx
         .get_channel("channel")
         .unwrap_or_else(|_| ctx.channel_id());
-    let mut config: AnnouncementConfig = storage.redis().guild(guild_id).configs().get().await?;
-    let joins = config.mut_joins();
-    let mut ids = joins.get_channel_ids().to_vec();
-    let state = if ids.contains(&channel_id.get()) {
-        ids.retain(|&id| id != channel_id.get());
-        "disabled"
-    } else {
-        ids.push(channel_id.get());
-        "enabled"
-    };
-    joins.set_channel_ids(ids);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-    Ok(Response::direct().content(format!(
-        "Join announcements {} in <#{}>.",
-        state, channel_id
+    config.mut_appeals().set_channel_id(channel_id.get());
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+
+    Ok(Response::direct().content(with_warnings(
+        format!(
+            "Enabled appeals. Submitted appeals will be posted in <#{}>.",
+            channel_id
+        ),
+        &warnings,
     )))
 }
 
-pub async fn announce_leave(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+/// Toggles an announcement type in the channel. If a message is provided, announcements are
+/// instead enabled in the channel with the message replacing any existing custom messages.
+async fn update_announcement(
+    ctx: &CommandContext,
+    storage: &Storage,
+    name: &str,
+    select: fn(&mut AnnouncementConfig) -> &mut AnnouncementTypeConfig,
+) -> Result<Response> {
     ctx.defer().await?;
     let guild_id = ctx.guild_id()?;
     if !ctx.has_user_permission(Permissions::MANAGE_GUILD) {
@@ -152,86 +148,49 @@ pub async fn announce_leave(ctx: &CommandContext, storage: &Storage) -> Result<R
     let channel_id = ctx
         .get_channel("channel")
         .unwrap_or_else(|_| ctx.channel_id());
+    let message = ctx.get_string("message").ok();
+    if let Some(message) = message {
+        template::validate(message)
+            .map_err(|err| InteractionError::InvalidArgument(err.to_string()))?;
+    }
+
     let mut config: AnnouncementConfig = storage.redis().guild(guild_id).configs().get().await?;
-    let leaves = config.mut_leaves();
-    let mut ids = leaves.get_channel_ids().to_vec();
-    let state = if ids.contains(&channel_id.get()) {
+    let announcement = select(&mut config);
+    let mut ids = announcement.get_channel_ids().to_vec();
+    let state = if message.is_none() && ids.contains(&channel_id.get()) {
         ids.retain(|&id| id != channel_id.get());
         "disabled"
     } else {
-        ids.push(channel_id.get());
+        if !ids.contains(&channel_id.get()) {
+            ids.push(channel_id.get());
+        }
         "enabled"
     };
-    leaves.set_channel_ids(ids);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-    Ok(Response::direct().content(format!(
-        "Leave announcements {} in <#{}>.",
-        state, channel_id
-    )))
+    announcement.set_channel_ids(ids);
+    if let Some(message) = message {
+        announcement.set_messages(protobuf::RepeatedField::from_vec(vec![message.to_owned()]));
+    }
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+
+    let mut response = format!("{} announcements {} in <#{}>.", name, state, channel_id);
+    if let Some(message) = message {
+        response.push_str(&format!(" Message set to: {}", message));
+    }
+    Ok(Response::direct().content(with_warnings(response, &warnings)))
+}
+
+pub async fn announce_join(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    update_announcement(ctx, storage, "Join", AnnouncementConfig::mut_joins).await
+}
+
+pub async fn announce_leave(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    update_announcement(ctx, storage, "Leave", AnnouncementConfig::mut_leaves).await
 }
 
 pub async fn announce_ban(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
-    ctx.defer().await?;
-    let guild_id = ctx.guild_id()?;
-    if !ctx.has_user_permission(Permissions::MANAGE_GUILD) {
-        anyhow::bail!(InteractionError::MissingPermission("Manage Server"));
-    }
-    let channel_id = ctx
-        .get_channel("channel")
-        .unwrap_or_else(|_| ctx.channel_id());
-    let mut config: AnnouncementConfig = storage.redis().guild(guild_id).configs().get().await?;
-    let bans = config.mut_bans();
-    let mut ids = bans.get_channel_ids().to_vec();
-    let state = if ids.contains(&channel_id.get()) {
-        ids.retain(|&id| id != channel_id.get());
-        "disabled"
-    } else {
-        ids.push(channel_id.get());
-        "enabled"
-    };
-    bans.set_channel_ids(ids);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-    Ok(Response::direct().content(format!("Ban announcements {} in <#{}>.", state, channel_id)))
+    update_announcement(ctx, storage, "Ban", AnnouncementConfig::mut_bans).await
 }
 
 pub async fn announce_voice(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
-    ctx.defer().await?;
-    let guild_id = ctx.guild_id()?;
-    if !ctx.has_user_permission(Permissions::MANAGE_GUILD) {
-        anyhow::bail!(InteractionError::MissingPermission("Manage Server"));
-    }
-    let channel_id = ctx
-        .get_channel("channel")
-        .unwrap_or_else(|_| ctx.channel_id());
-    let mut config: AnnouncementConfig = storage.redis().guild(guild_id).configs().get().await?;
-    let voice = config.mut_voice();
-    let mut ids = voice.get_channel_ids().to_vec();
-    let state = if ids.contains(&channel_id.get()) {
-        ids.retain(|&id| id != channel_id.get());
-        "disabled"
-    } else {
-        ids.push(channel_id.get());
-        "enabled"
-    };
-    voice.set_channel_ids(ids);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
-    Ok(Response::direct().content(format!(
-        "Voice announcements {} in <#{}>.",
-        state, channel_id
-    )))
+    update_announcement(ctx, storage, "Voice", AnnouncementConfig::mut_voice).await
 }
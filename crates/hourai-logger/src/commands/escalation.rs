@@ -1,26 +1,44 @@
-use super::prelude::*;
-use hourai_storage::escalation::EscalationManager;
+use super::{admin, prelude::*};
+use hourai::{
+    interactions::ComponentContext,
+    models::{
+        channel::message::{
+            Component,
+            component::{ActionRow, Button, ButtonStyle},
+        },
+        guild::Permissions,
+        id::{Id, marker::*},
+    },
+    proto::{
+        action::{Action, Action_oneof_details, BanMember_Type, StatusType},
+        escalation::{EscalationLadderRung, EscalationMode},
+        guild_configs::ModerationConfig,
+        message_components::{EscalationHistoryButton, MessageComponentProto},
+    },
+    util::validation,
+};
+use hourai_sql::{EscalationEntry, PendingDeescalation};
+use hourai_storage::{
+    cases,
+    escalation::{self, EscalationError, EscalationManager},
+};
+use std::time::Duration;
+
+/// The number of `action_N` options on `/escalate ladder add` and `/escalate ladder edit`.
+const MAX_RUNG_ACTIONS: usize = 3;
+const DEFAULT_TIMEOUT_SECS: u64 = 24 * 60 * 60;
+/// The number of entries shown per page of `/escalate history`.
+const HISTORY_PAGE_SIZE: usize = 5;
 
 pub(super) async fn escalate(ctx: &CommandContext, actions: &ActionExecutor) -> Result<Response> {
     ctx.defer().await?;
     let guild_id = ctx.guild_id()?;
-    let member_roles = ctx
-        .command
-        .member
-        .as_ref()
-        .map(|m| m.roles.as_slice())
-        .unwrap_or_default();
-    if !hourai_storage::is_moderator(
-        guild_id,
-        member_roles.iter().copied(),
-        actions.storage().redis(),
+    require_moderator(
+        ctx,
+        actions.storage(),
+        "Only moderators can escalate users.",
     )
-    .await?
-    {
-        anyhow::bail!(InteractionError::MissingPermission(
-            "Only moderators can escalate users."
-        ));
-    }
+    .await?;
     let authorizer = ctx.user();
     let reason = ctx.get_string("reason")?.as_ref();
     let amount = ctx.get_int("amount").unwrap_or(1);
@@ -29,9 +47,16 @@ pub(super) async fn escalate(ctx: &CommandContext, actions: &ActionExecutor) ->
             "Non-positive `amounts` are not allowed. If you need to deescalate someone, please use \
              `/escalate down` instead.".to_owned()));
     }
+    let preview = ctx.get_flag("preview").unwrap_or(false);
+    let actions = if preview {
+        actions.dry_run()
+    } else {
+        actions.clone()
+    };
     let manager = EscalationManager::new(actions.clone());
     let guild = manager.guild(guild_id).await?;
     let mut results = Vec::new();
+    let mut errors = Vec::new();
     for user_id in ctx.all_users("user") {
         let history = guild.fetch_history(user_id).await?;
         let result = history
@@ -44,19 +69,27 @@ pub(super) async fn escalate(ctx: &CommandContext, actions: &ActionExecutor) ->
             .await;
         match result {
             Ok(escalation) => {
-                results.push(format!(
+                let mut result = format!(
                     "<@{}>: Action: {}. Expiration: {}",
                     user_id,
                     escalation.entry.display_name,
                     escalation.expiration()
-                ));
+                );
+                if let Some(err) = escalation.notify_error {
+                    result.push_str(&format!(" (Could not notify them: {})", err));
+                }
+                results.push(result);
             }
             Err(err) => {
                 tracing::error!("Error while escalating a user: {}", err);
+                errors.push(format!("<@{}>: {}", user_id, err));
             }
         }
     }
 
+    if preview {
+        return Ok(super::preview::preview_response(&actions, &errors));
+    }
     let response = format!(
         "Escalated {} users for: '{}'\n{}",
         results.len(),
@@ -69,23 +102,12 @@ pub(super) async fn escalate(ctx: &CommandContext, actions: &ActionExecutor) ->
 pub(super) async fn deescalate(ctx: &CommandContext, actions: &ActionExecutor) -> Result<Response> {
     ctx.defer().await?;
     let guild_id = ctx.guild_id()?;
-    let member_roles = ctx
-        .command
-        .member
-        .as_ref()
-        .map(|m| m.roles.as_slice())
-        .unwrap_or_default();
-    if !hourai_storage::is_moderator(
-        guild_id,
-        member_roles.iter().copied(),
-        actions.storage().redis(),
+    require_moderator(
+        ctx,
+        actions.storage(),
+        "Only moderators can escalate users.",
     )
-    .await?
-    {
-        anyhow::bail!(InteractionError::MissingPermission(
-            "Only moderators can escalate users."
-        ));
-    }
+    .await?;
     let authorizer = ctx.user();
     let reason = ctx.get_string("reason")?.as_ref();
     let amount = -ctx.get_int("amount").unwrap_or(1);
@@ -109,12 +131,16 @@ pub(super) async fn deescalate(ctx: &CommandContext, actions: &ActionExecutor) -
             .await;
         match result {
             Ok(escalation) => {
-                results.push(format!(
+                let mut result = format!(
                     "<@{}>: Action: {}. Expiration {}",
                     user_id,
                     escalation.entry.display_name,
                     escalation.expiration()
-                ));
+                );
+                if let Some(err) = escalation.notify_error {
+                    result.push_str(&format!(" (Could not notify them: {})", err));
+                }
+                results.push(result);
             }
             Err(err) => {
                 tracing::error!("Error while escalating a user: {}", err);
@@ -132,8 +158,593 @@ pub(super) async fn deescalate(ctx: &CommandContext, actions: &ActionExecutor) -
 }
 
 pub(super) async fn escalate_history(
-    _ctx: &CommandContext,
-    _actions: &ActionExecutor,
+    ctx: &CommandContext,
+    actions: &ActionExecutor,
+) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    let user_id = ctx.get_user("user")?;
+    history_page(guild_id, user_id, 0, actions).await
+}
+
+/// Handles the page buttons of an escalation history view.
+pub(super) async fn handle_history_component(
+    ctx: ComponentContext,
+    actions: &ActionExecutor,
+) -> Result<()> {
+    ctx.defer_update().await?;
+    let metadata = ctx.metadata()?;
+    let button = metadata.get_escalation_history_button();
+    let user_id = Id::new(button.get_user_id());
+    let page = button.get_page() as usize;
+    let response = history_page(ctx.guild_id()?, user_id, page, actions).await?;
+    ctx.reply(response).await?;
+    Ok(())
+}
+
+fn history_button(
+    user_id: Id<UserMarker>,
+    page: usize,
+    label: &str,
+    disabled: bool,
+) -> Result<Component> {
+    let mut button = EscalationHistoryButton::new();
+    button.set_user_id(user_id.get());
+    button.set_page(page as u32);
+    let mut proto = MessageComponentProto::new();
+    proto.set_escalation_history_button(button);
+
+    Ok(Component::Button(Button {
+        custom_id: Some(hourai::interactions::proto_to_custom_id(&proto)?),
+        disabled,
+        emoji: None,
+        label: Some(label.to_owned()),
+        sku_id: None,
+        style: ButtonStyle::Secondary,
+        url: None,
+    }))
+}
+
+fn describe_entry(
+    number: usize,
+    entry: &EscalationEntry,
+    pending: Option<&PendingDeescalation>,
+) -> String {
+    let set = &entry.action.0;
+    let mut reasons: Vec<&str> = Vec::new();
+    for reason in set.get_action().iter().map(|action| action.get_reason()) {
+        if !reason.is_empty() && !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    }
+    let applied: Vec<String> = set
+        .get_action()
+        .iter()
+        .filter(|action| !action.has_escalate())
+        .map(describe_action)
+        .collect();
+
+    let mut description = format!(
+        "**{}. {}** ({:+}) <t:{}:f> by <@{}>\nReason: {}\nActions: {}",
+        number,
+        entry.display_name,
+        entry.level_delta,
+        entry.timestamp.timestamp(),
+        entry.authorizer_id,
+        if reasons.is_empty() {
+            "None".to_owned()
+        } else {
+            reasons.join("; ")
+        },
+        if applied.is_empty() {
+            "None".to_owned()
+        } else {
+            applied.join(", ")
+        },
+    );
+    if let Some(revoked_at) = entry.revoked_at {
+        description.push_str(&format!("\nPardoned <t:{}:f>", revoked_at.timestamp()));
+        if let Some(revoked_by) = entry.revoked_by {
+            description.push_str(&format!(" by <@{}>", revoked_by));
+        }
+    }
+    if let Some(pending) = pending.filter(|pending| pending.entry_id == entry.id) {
+        description.push_str(&format!(
+            "\nDeescalates <t:{}:R>",
+            pending.expiration.timestamp()
+        ));
+    }
+    description
+}
+
+/// Renders one page of a user's escalation history, newest entries first.
+async fn history_page(
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+    page: usize,
+    actions: &ActionExecutor,
+) -> Result<Response> {
+    let manager = EscalationManager::new(actions.clone());
+    let history = manager
+        .guild(guild_id)
+        .await?
+        .fetch_history(user_id)
+        .await?;
+    let entries: Vec<&EscalationEntry> = history.entries().collect();
+    if entries.is_empty() {
+        return Ok(Response::direct().content(format!("<@{}> has no escalation history.", user_id)));
+    }
+    let pending = PendingDeescalation::fetch(guild_id, user_id)
+        .fetch_optional(actions.storage().sql())
+        .await?;
+
+    let pages = entries.len().div_ceil(HISTORY_PAGE_SIZE);
+    let page = page.min(pages - 1);
+    let level = escalation::describe_level(
+        history.config().get_escalation_ladder(),
+        history.current_level(),
+    );
+    let mut content = format!(
+        "**Escalation history of <@{}>** (currently {}, page {}/{})",
+        user_id,
+        level,
+        page + 1,
+        pages
+    );
+
+    let mut links = Vec::new();
+    for (idx, entry) in entries
+        .iter()
+        .enumerate()
+        .rev()
+        .skip(page * HISTORY_PAGE_SIZE)
+        .take(HISTORY_PAGE_SIZE)
+    {
+        content.push_str("\n\n");
+        content.push_str(&describe_entry(idx + 1, entry, pending.as_ref()));
+        if let Some((channel_id, message_id)) = entry.modlog_channel_id.zip(entry.modlog_message_id)
+        {
+            links.push(Component::Button(Button {
+                custom_id: None,
+                disabled: false,
+                emoji: None,
+                label: Some(format!("Entry {}", idx + 1)),
+                sku_id: None,
+                style: ButtonStyle::Link,
+                url: Some(format!(
+                    "https://discord.com/channels/{}/{}/{}",
+                    guild_id, channel_id, message_id
+                )),
+            }));
+        }
+    }
+
+    let mut rows = Vec::new();
+    if !links.is_empty() {
+        rows.push(Component::ActionRow(ActionRow { components: links }));
+    }
+    if pages > 1 {
+        rows.push(Component::ActionRow(ActionRow {
+            components: vec![
+                history_button(user_id, page.saturating_sub(1), "Newer", page == 0)?,
+                history_button(user_id, page + 1, "Older", page + 1 >= pages)?,
+            ],
+        }));
+    }
+    Ok(Response::direct().content(content).components(&rows))
+}
+
+pub(super) async fn escalate_pardon(
+    ctx: &CommandContext,
+    actions: &ActionExecutor,
+) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    require_moderator(
+        ctx,
+        actions.storage(),
+        "Only moderators can pardon escalations.",
+    )
+    .await?;
+    let user_id = ctx.get_user("user")?;
+    let entry = ctx.get_int("entry")?;
+    let reason = ctx.get_string("reason")?;
+    let undo = ctx.get_flag("undo").unwrap_or(false);
+
+    let manager = EscalationManager::new(actions.clone());
+    let guild = manager.guild(guild_id).await?;
+    let history = guild.fetch_history(user_id).await?;
+    let result = history
+        .pardon(ctx.user(), entry.max(0) as usize, reason, undo)
+        .await;
+    let pardon = match result {
+        Ok(pardon) => pardon,
+        Err(err) => match err.downcast_ref::<EscalationError>() {
+            Some(err) => anyhow::bail!(InteractionError::InvalidArgument(err.to_string())),
+            None => return Err(err),
+        },
+    };
+
+    let level =
+        escalation::describe_level(guild.config().get_escalation_ladder(), pardon.current_level);
+    let mut response = format!(
+        "Pardoned entry {} ({}) of <@{}>. They are now {}.",
+        pardon.entry_number, pardon.display_name, user_id, level
+    );
+    if !pardon.undone.is_empty() {
+        let undone: Vec<String> = pardon.undone.iter().map(describe_action).collect();
+        response.push_str(&format!("\nUndid: {}", undone.join(", ")));
+    }
+    Ok(Response::direct().content(response))
+}
+
+pub(super) async fn escalate_preview(
+    ctx: &CommandContext,
+    actions: &ActionExecutor,
 ) -> Result<Response> {
-    anyhow::bail!("This command is unfortunately not implemented yet.");
+    ctx.defer_ephemeral().await?;
+    let guild_id = ctx.guild_id()?;
+    require_moderator(
+        ctx,
+        actions.storage(),
+        "Only moderators can escalate users.",
+    )
+    .await?;
+    let user_id = ctx.get_user("user")?;
+    let actions = actions.dry_run();
+    let manager = EscalationManager::new(actions.clone());
+    let history = manager
+        .guild(guild_id)
+        .await?
+        .fetch_history(user_id)
+        .await?;
+    if history
+        .config()
+        .get_escalation_ladder()
+        .get_rung()
+        .is_empty()
+    {
+        anyhow::bail!(InteractionError::FailedPrecondition(
+            "This server has no escalation ladder."
+        ));
+    }
+    let current_level = history.current_level();
+    let escalation = history
+        .apply_delta(
+            /*authorizer=*/ ctx.user(),
+            /*reason=*/ "Preview",
+            /*diff=*/ 1,
+            /*execute=*/ true,
+        )
+        .await?;
+
+    let ladder = history.config().get_escalation_ladder();
+    let header = format!(
+        "<@{}> is currently {}. Escalating them would put them {}: {}.\n",
+        user_id,
+        escalation::describe_level(ladder, current_level),
+        escalation::describe_level(ladder, escalation.current_level),
+        escalation.entry.display_name
+    );
+    Ok(Response::ephemeral().content(header + &super::preview::describe_operations(&actions, &[])))
+}
+
+fn describe_action(action: &Action) -> String {
+    let mut description = cases::case_action(action).unwrap_or("Other").to_owned();
+    if let Some(Action_oneof_details::change_role(info)) = action.details.as_ref() {
+        let roles: Vec<String> = info
+            .get_role_ids()
+            .iter()
+            .map(|id| format!("<@&{}>", id))
+            .collect();
+        description.push_str(&format!(" {}", roles.join(", ")));
+    }
+    if action.has_duration() {
+        description.push_str(&format!(
+            " for {}",
+            humantime::format_duration(Duration::from_secs(action.get_duration()))
+        ));
+    }
+    description
+}
+
+fn describe_rung(idx: usize, rung: &EscalationLadderRung) -> String {
+    let actions: Vec<String> = rung.get_action().iter().map(describe_action).collect();
+    let mut description = format!("**{}. {}**", idx + 1, rung.get_display_name());
+    if rung.has_threshold() {
+        description.push_str(&format!(" ({} points)", rung.get_threshold()));
+    }
+    description.push_str(&format!(": {}", actions.join(", ")));
+    if rung.has_deescalation_period() {
+        description.push_str(&format!(
+            ". Deescalates after {}",
+            humantime::format_duration(Duration::from_secs(rung.get_deescalation_period()))
+        ));
+    }
+    if rung.has_notification() {
+        description.push_str(". Has a custom notification");
+    }
+    description.push('.');
+    description
+}
+
+/// Builds a rung action from one of the action choices on `/escalate ladder add` and
+/// `/escalate ladder edit`.
+fn rung_action(
+    kind: &str,
+    role_id: Option<Id<RoleMarker>>,
+    duration: Option<u64>,
+) -> Result<Action> {
+    let mut action = Action::new();
+    match kind {
+        "ban" => action.mut_ban().set_field_type(BanMember_Type::BAN),
+        "softban" => action.mut_ban().set_field_type(BanMember_Type::SOFTBAN),
+        "kick" => {
+            action.mut_kick();
+        }
+        "timeout" => {
+            action.mut_timeout().set_field_type(StatusType::APPLY);
+            action.set_duration(duration.unwrap_or(DEFAULT_TIMEOUT_SECS));
+            return Ok(action);
+        }
+        "mute" => action.mut_mute().set_field_type(StatusType::APPLY),
+        "deafen" => action.mut_deafen().set_field_type(StatusType::APPLY),
+        "add_role" | "remove_role" => {
+            let role_id = role_id.ok_or_else(|| {
+                InteractionError::InvalidArgument(
+                    "A `role` must be provided to add or remove roles.".to_owned(),
+                )
+            })?;
+            let change = action.mut_change_role();
+            change.set_field_type(if kind == "add_role" {
+                StatusType::APPLY
+            } else {
+                StatusType::UNAPPLY
+            });
+            change.mut_role_ids().push(role_id.get());
+        }
+        _ => anyhow::bail!(InteractionError::InvalidArgument(format!(
+            "`{}` is not a supported action.",
+            kind
+        ))),
+    }
+    // Durations only apply to the actions that can be undone, so one duration can be shared by
+    // every action on the rung.
+    if let Some(duration) = duration.filter(|_| validation::can_undo(&action)) {
+        action.set_duration(duration);
+    }
+    Ok(action)
+}
+
+/// Reads the `duration` option, in seconds.
+fn rung_duration(ctx: &CommandContext) -> Result<Option<u64>> {
+    match ctx.get_string("duration") {
+        Ok(duration) => Ok(Some(admin::parse_duration(duration)?.as_secs())),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Reads the `action_N` options. Returns None if none were provided.
+fn rung_actions(ctx: &CommandContext) -> Result<Option<Vec<Action>>> {
+    const NAMES: [&str; MAX_RUNG_ACTIONS] = ["action_1", "action_2", "action_3"];
+    let role_id = ctx.get_role("role").ok();
+    let duration = rung_duration(ctx)?;
+    let mut actions = Vec::new();
+    for name in NAMES {
+        if let Ok(kind) = ctx.get_string(name) {
+            actions.push(rung_action(kind, role_id, duration)?);
+        }
+    }
+    Ok(Some(actions).filter(|actions| !actions.is_empty()))
+}
+
+/// Reads the `threshold` option, the points needed to reach a rung in points mode.
+fn threshold(ctx: &CommandContext) -> Result<Option<u32>> {
+    match ctx.get_int("threshold") {
+        Ok(threshold) if threshold < 1 => anyhow::bail!(InteractionError::InvalidArgument(
+            "A `threshold` must be at least 1 point.".to_owned()
+        )),
+        Ok(threshold) => Ok(Some(threshold.min(u32::MAX as i64) as u32)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Reads the `deescalation_period` option. `never` clears the period.
+fn deescalation_period(ctx: &CommandContext) -> Result<Option<Option<u64>>> {
+    Ok(match ctx.get_string("deescalation_period") {
+        Ok(period) if period.eq_ignore_ascii_case("never") => Some(None),
+        Ok(period) => Some(Some(admin::parse_duration(period)?.as_secs())),
+        Err(_) => None,
+    })
+}
+
+fn rung_index(ctx: &CommandContext, config: &ModerationConfig) -> Result<usize> {
+    let rungs = config.get_escalation_ladder().get_rung().len();
+    let rung = ctx.get_int("rung")?;
+    if rung < 1 || rung as usize > rungs {
+        anyhow::bail!(InteractionError::InvalidArgument(format!(
+            "Rung {} does not exist. This server's escalation ladder has {} rungs.",
+            rung, rungs
+        )));
+    }
+    Ok(rung as usize - 1)
+}
+
+async fn ladder_config(ctx: &CommandContext, storage: &Storage) -> Result<ModerationConfig> {
+    if !ctx.has_user_permission(Permissions::MANAGE_GUILD) {
+        anyhow::bail!(InteractionError::MissingPermission("Manage Server"));
+    }
+    storage.redis().guild(ctx.guild_id()?).configs().get().await
+}
+
+/// Warns that changing the rungs of a ladder that uses levels moves users between rungs, as
+/// their levels are kept. `moved` describes which users moved and where.
+fn shift_warning(config: &ModerationConfig, moved: &str) -> Option<String> {
+    (config.get_escalation_ladder().get_mode() != EscalationMode::ESCALATION_MODE_POINTS).then(
+        || {
+            format!(
+                "\n:warning: Users keep their escalation level, so users {} rung than before.",
+                moved
+            )
+        },
+    )
+}
+
+pub(super) async fn ladder_list(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    let config: ModerationConfig = storage.redis().guild(guild_id).configs().get().await?;
+    let rungs = config.get_escalation_ladder().get_rung();
+    if rungs.is_empty() {
+        return Ok(Response::direct().content("This server has no escalation ladder."));
+    }
+    let rungs: Vec<String> = rungs
+        .iter()
+        .enumerate()
+        .map(|(idx, rung)| describe_rung(idx, rung))
+        .collect();
+    Ok(Response::direct().content(format!("**Escalation Ladder**\n{}", rungs.join("\n"))))
+}
+
+pub(super) async fn ladder_add(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    let mut config = ladder_config(ctx, storage).await?;
+    let mut rung = EscalationLadderRung::new();
+    rung.set_display_name(ctx.get_string("name")?.clone());
+    rung.set_action(rung_actions(ctx)?.unwrap_or_default().into());
+    if let Some(Some(period)) = deescalation_period(ctx)? {
+        rung.set_deescalation_period(period);
+    }
+    if let Ok(notification) = ctx.get_string("notification") {
+        rung.set_notification(notification.clone());
+    }
+    if let Some(threshold) = threshold(ctx)? {
+        rung.set_threshold(threshold);
+    }
+
+    let rungs = config.mut_escalation_ladder().mut_rung();
+    let idx = match ctx.get_int("position") {
+        Ok(position) => (position.max(1) as usize - 1).min(rungs.len()),
+        Err(_) => rungs.len(),
+    };
+    let mut response = format!("Added rung {}", describe_rung(idx, &rung));
+    let shifted = idx < rungs.len();
+    rungs.insert(idx, rung);
+    if shifted {
+        let moved = format!("at rung {} or above are now one lower", idx + 1);
+        response.extend(shift_warning(&config, &moved));
+    }
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+    Ok(Response::direct().content(with_warnings(response, &warnings)))
+}
+
+pub(super) async fn ladder_remove(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    let mut config = ladder_config(ctx, storage).await?;
+    let idx = rung_index(ctx, &config)?;
+    let rung = config.mut_escalation_ladder().mut_rung().remove(idx);
+    // An empty ladder is not valid, so removing the last rung removes the ladder entirely.
+    if config.get_escalation_ladder().get_rung().is_empty() {
+        config.clear_escalation_ladder();
+    }
+    let mut response = format!("Removed rung {}", describe_rung(idx, &rung));
+    if idx < config.get_escalation_ladder().get_rung().len() {
+        let moved = format!("above rung {} are now one higher", idx + 1);
+        response.extend(shift_warning(&config, &moved));
+    }
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+    Ok(Response::direct().content(with_warnings(response, &warnings)))
+}
+
+pub(super) async fn ladder_edit(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    let mut config = ladder_config(ctx, storage).await?;
+    let idx = rung_index(ctx, &config)?;
+    let actions = rung_actions(ctx)?;
+    let duration = rung_duration(ctx)?;
+    let period = deescalation_period(ctx)?;
+    let threshold = threshold(ctx)?;
+
+    let rung = &mut config.mut_escalation_ladder().mut_rung()[idx];
+    if let Ok(name) = ctx.get_string("name") {
+        rung.set_display_name(name.clone());
+    }
+    match (actions, duration) {
+        (Some(actions), _) => rung.set_action(actions.into()),
+        // A duration on its own applies to the rung's existing actions.
+        (None, Some(duration)) => {
+            for action in rung.mut_action().iter_mut() {
+                if validation::can_undo(action) {
+                    action.set_duration(duration);
+                }
+            }
+        }
+        (None, None) => {}
+    }
+    match period {
+        Some(Some(period)) => rung.set_deescalation_period(period),
+        Some(None) => rung.clear_deescalation_period(),
+        None => {}
+    }
+    if let Some(threshold) = threshold {
+        rung.set_threshold(threshold);
+    }
+    match ctx.get_string("notification").map(|n| n.as_str()) {
+        Ok("default") => rung.clear_notification(),
+        Ok(notification) => rung.set_notification(notification.to_owned()),
+        Err(_) => {}
+    }
+    let description = describe_rung(idx, rung);
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+    Ok(Response::direct().content(with_warnings(
+        format!("Updated rung {}", description),
+        &warnings,
+    )))
+}
+
+pub(super) async fn ladder_mode(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    let mut config = ladder_config(ctx, storage).await?;
+    let ladder = config.mut_escalation_ladder();
+    let points = ctx.get_string("mode")? == "points";
+    let mode = if points {
+        EscalationMode::ESCALATION_MODE_POINTS
+    } else {
+        EscalationMode::ESCALATION_MODE_LEVELS
+    };
+    let changed = ladder.get_mode() != mode;
+    ladder.set_mode(mode);
+    match ctx.get_string("point_lifetime") {
+        Ok(lifetime) if lifetime.eq_ignore_ascii_case("never") => ladder.clear_point_lifetime(),
+        Ok(lifetime) => ladder.set_point_lifetime(admin::parse_duration(lifetime)?.as_secs()),
+        Err(_) => {}
+    }
+
+    let mut response = if !points {
+        "The escalation ladder now uses levels.".to_owned()
+    } else if ladder.has_point_lifetime() {
+        format!(
+            "The escalation ladder now uses points. Points expire after {}.",
+            humantime::format_duration(Duration::from_secs(ladder.get_point_lifetime()))
+        )
+    } else {
+        "The escalation ladder now uses points. Points never expire.".to_owned()
+    };
+    if changed && points {
+        response.push_str(
+            "\n:warning: Existing escalation histories are not converted: past escalations now \
+             add one point per level they moved users up.",
+        );
+    } else if changed {
+        response.push_str(
+            "\n:warning: Existing escalation histories are not converted: past escalations now \
+             move users up one rung per point they added.",
+        );
+    }
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
+    Ok(Response::direct().content(with_warnings(response, &warnings)))
 }
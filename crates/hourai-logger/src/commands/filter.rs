@@ -0,0 +1,50 @@
+use super::prelude::*;
+use anyhow::Result;
+use hourai::{models::guild::Permissions, proto::guild_configs::ModerationConfig};
+use twilight_util::builder::embed::*;
+
+const DAY: u64 = 24;
+const WEEK: u64 = 7 * DAY;
+const MONTH: u64 = 30 * DAY;
+
+pub async fn stats(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id()?;
+    if !ctx.has_user_permission(Permissions::MANAGE_GUILD) {
+        anyhow::bail!(InteractionError::MissingPermission("Manage Server"));
+    }
+
+    let guild = storage.redis().guild(guild_id);
+    let config: ModerationConfig = guild.configs().get().await?;
+    let rules = config.get_message_filter().get_rules();
+    if rules.is_empty() {
+        return Ok(Response::ephemeral().content("This server has no message filter rules."));
+    }
+
+    let [day, week, month] = guild.filter_stats().hits([DAY, WEEK, MONTH]).await?;
+
+    let mut desc = String::new();
+    for (idx, rule) in rules.iter().enumerate() {
+        let label = crate::message_filter::rule_label(idx, rule);
+        let key = crate::message_filter::rule_key(rule);
+        let count =
+            |hits: &std::collections::HashMap<String, u64>| hits.get(&key).copied().unwrap_or(0);
+        desc.push_str(&format!(
+            "**{}**: {} today, {} this week, {} this month\n",
+            label,
+            count(&day),
+            count(&week),
+            count(&month)
+        ));
+    }
+
+    let embed = EmbedBuilder::new()
+        .title("Message Filter Statistics")
+        .description(desc)
+        .footer(EmbedFooterBuilder::new(
+            "Hits over the last 24 hours, 7 days, and 30 days.",
+        ))
+        .build();
+
+    Ok(Response::direct().embed(embed))
+}
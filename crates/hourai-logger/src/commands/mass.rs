@@ -0,0 +1,331 @@
+use super::{admin, prelude::*, preview::push_lines};
+use chrono::{TimeZone, Utc};
+use hourai::{
+    interactions::ComponentContext,
+    models::{
+        channel::message::{
+            Component,
+            component::{ActionRow, Button, ButtonStyle},
+        },
+        guild::Permissions,
+        id::{Id, marker::*},
+    },
+    proto::{
+        action::{Action, ActionSet, BanMember_Type},
+        guild_configs::VerificationConfig,
+        message_components::{
+            MassActionButton, MassActionType, MemberCriteria, MessageComponentProto,
+        },
+    },
+};
+use hourai_sql::Member;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The most members a single mass action can affect.
+const MAX_MEMBERS: i64 = 1000;
+/// The number of matching members shown in the preview.
+const SAMPLE_SIZE: usize = 20;
+/// The delay between actions. Keeps a mass action from starving other requests of the shared
+/// rate limits, and finishes 1000 members well within the 15 minute interaction token lifetime.
+const ACTION_INTERVAL: Duration = Duration::from_millis(500);
+/// How many members to act on between progress updates.
+const PROGRESS_INTERVAL: usize = 10;
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+pub(super) async fn massban(ctx: &CommandContext, executor: &ActionExecutor) -> Result<Response> {
+    preview(ctx, executor, MassActionType::MASS_ACTION_BAN).await
+}
+
+pub(super) async fn masskick(ctx: &CommandContext, executor: &ActionExecutor) -> Result<Response> {
+    preview(ctx, executor, MassActionType::MASS_ACTION_KICK).await
+}
+
+fn required_permission(kind: MassActionType) -> (Permissions, &'static str) {
+    match kind {
+        MassActionType::MASS_ACTION_KICK => (Permissions::KICK_MEMBERS, "Kick Members"),
+        _ => (Permissions::BAN_MEMBERS, "Ban Members"),
+    }
+}
+
+fn verbs(kind: MassActionType) -> (&'static str, &'static str) {
+    match kind {
+        MassActionType::MASS_ACTION_KICK => ("kick", "Kicked"),
+        _ => ("ban", "Banned"),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn parse_criteria(ctx: &CommandContext) -> Result<MemberCriteria> {
+    let now = now_secs();
+    let mut criteria = MemberCriteria::new();
+    if let Ok(window) = ctx.get_string("joined_within") {
+        let window = admin::parse_duration(window)?.as_secs();
+        criteria.set_joined_after(now.saturating_sub(window));
+    }
+    if let Ok(age) = ctx.get_string("account_age") {
+        let age = admin::parse_duration(age)?.as_secs();
+        criteria.set_created_after(now.saturating_sub(age));
+    }
+    if ctx.get_flag("unverified").unwrap_or(false) {
+        criteria.set_unverified(true);
+    }
+    if let Ok(pattern) = ctx.get_string("name") {
+        criteria.set_name_pattern(pattern.clone());
+    }
+
+    if criteria == MemberCriteria::new() {
+        anyhow::bail!(InteractionError::InvalidArgument(
+            "At least one of `joined_within`, `account_age`, `unverified`, or `name` must be \
+             provided."
+                .to_owned()
+        ));
+    }
+    Ok(criteria)
+}
+
+async fn find_members(
+    guild_id: Id<GuildMarker>,
+    criteria: &MemberCriteria,
+    storage: &Storage,
+) -> Result<Vec<Member>> {
+    let joined_after = criteria
+        .has_joined_after()
+        .then(|| {
+            Utc.timestamp_opt(criteria.get_joined_after() as i64, 0)
+                .single()
+        })
+        .flatten();
+    // Snowflakes begin with their creation time, so every account created after a given time
+    // has an ID at least as large as the smallest snowflake from that time.
+    let created_after = criteria.has_created_after().then(|| {
+        let millis = criteria.get_created_after().saturating_mul(1000);
+        Id::new((millis.saturating_sub(DISCORD_EPOCH_MS) << 22).max(1))
+    });
+    let missing_role = if criteria.get_unverified() {
+        let config: VerificationConfig = storage.redis().guild(guild_id).configs().get().await?;
+        if !config.has_role_id() {
+            anyhow::bail!(InteractionError::InvalidArgument(
+                "`unverified` cannot be used without a verification role configured.".to_owned()
+            ));
+        }
+        Some(Id::new(config.get_role_id()))
+    } else {
+        None
+    };
+    let name_pattern = criteria
+        .has_name_pattern()
+        .then(|| criteria.get_name_pattern());
+    // Patterns are run by Postgres, so they are checked by it too.
+    if let Some(pattern) = name_pattern
+        && let Err(hourai_sql::Error::Database(err)) =
+            Member::check_pattern(pattern).execute(storage.sql()).await
+    {
+        anyhow::bail!(InteractionError::InvalidArgument(format!(
+            "`{}` is not a valid regex: {}",
+            pattern,
+            err.message()
+        )));
+    }
+
+    Ok(Member::find_matching(
+        guild_id,
+        joined_after,
+        created_after,
+        missing_role,
+        name_pattern,
+        MAX_MEMBERS,
+    )
+    .fetch_all(storage.sql())
+    .await?)
+}
+
+fn create_button(
+    kind: MassActionType,
+    stored_actions_id: u64,
+    cancel: bool,
+    label: &str,
+    style: ButtonStyle,
+) -> Result<Component> {
+    let mut button = MassActionButton::new();
+    button.set_field_type(kind);
+    button.set_stored_actions_id(stored_actions_id);
+    button.set_cancel(cancel);
+
+    let mut proto = MessageComponentProto::new();
+    proto.set_mass_action_button(button);
+
+    Ok(Component::Button(Button {
+        custom_id: Some(hourai::interactions::proto_to_custom_id(&proto)?),
+        disabled: false,
+        emoji: None,
+        label: Some(label.to_string()),
+        sku_id: None,
+        style,
+        url: None,
+    }))
+}
+
+async fn preview(
+    ctx: &CommandContext,
+    executor: &ActionExecutor,
+    kind: MassActionType,
+) -> Result<Response> {
+    ctx.defer_ephemeral().await?;
+    let guild_id = ctx.guild_id()?;
+    let (permission, permission_name) = required_permission(kind);
+    if !ctx.has_user_permission(permission) {
+        anyhow::bail!(InteractionError::MissingPermission(permission_name));
+    }
+
+    let criteria = parse_criteria(ctx)?;
+    let members = find_members(guild_id, &criteria, executor.storage()).await?;
+    if members.is_empty() {
+        return Ok(Response::ephemeral().content("No members match the provided criteria."));
+    }
+
+    // Only the previewed members are acted on, even if more match by the time the preview is
+    // confirmed.
+    let (verb, past_verb) = verbs(kind);
+    let mut base = Action::new();
+    base.set_guild_id(guild_id.get());
+    base.set_authorizer_id(ctx.user().id.get());
+    base.set_reason(admin::build_reason(
+        &format!("Mass {}", past_verb.to_lowercase()),
+        ctx.user(),
+        None,
+    ));
+    match kind {
+        MassActionType::MASS_ACTION_KICK => {
+            base.mut_kick();
+        }
+        _ => base.mut_ban().set_field_type(BanMember_Type::BAN),
+    }
+    let mut actions = ActionSet::new();
+    for member in members.iter() {
+        let mut action = base.clone();
+        action.set_user_id(member.user_id().get());
+        actions.mut_action().push(action);
+    }
+    let id = rand::random::<u64>();
+    executor
+        .storage()
+        .redis()
+        .stored_actions()
+        .store(id, actions)
+        .await?;
+
+    let confirm = create_button(kind, id, false, "Confirm", ButtonStyle::Danger)?;
+    let cancel = create_button(kind, id, true, "Cancel", ButtonStyle::Secondary)?;
+
+    let sample: Vec<String> = members
+        .iter()
+        .take(SAMPLE_SIZE)
+        .map(|member| format!("<@{}>", member.user_id))
+        .collect();
+    let mut content = format!(
+        "This will {} **{}** members, including: {}",
+        verb,
+        members.len(),
+        sample.join(", ")
+    );
+    if members.len() > SAMPLE_SIZE {
+        content.push_str(&format!(" and {} more", members.len() - SAMPLE_SIZE));
+    }
+    if members.len() as i64 >= MAX_MEMBERS {
+        content.push_str(&format!(
+            ".\nOnly {} members can be affected at once. Run the command again afterwards to \
+             act on the rest",
+            MAX_MEMBERS
+        ));
+    }
+    content.push('.');
+
+    Ok(Response::ephemeral()
+        .content(content)
+        .components(&[Component::ActionRow(ActionRow {
+            components: vec![confirm, cancel],
+        })]))
+}
+
+/// Handles the confirm and cancel buttons of a mass action preview. Only the previewed members
+/// are acted on, and a preview can only be confirmed once before it expires.
+pub(super) async fn handle_component(
+    ctx: ComponentContext,
+    executor: &ActionExecutor,
+) -> Result<()> {
+    let metadata = ctx.metadata()?;
+    let button = metadata.get_mass_action_button();
+    ctx.defer_update().await?;
+
+    let kind = button.get_field_type();
+    let (permission, permission_name) = required_permission(kind);
+    if !button.get_cancel() && !ctx.has_user_permission(permission) {
+        ctx.reply(Response::ephemeral().content(format!(
+            ":x: Error: {}",
+            InteractionError::MissingPermission(permission_name)
+        )))
+        .await?;
+        return Ok(());
+    }
+
+    let actions = executor
+        .storage()
+        .redis()
+        .stored_actions()
+        .take(button.get_stored_actions_id())
+        .await?;
+    let response = Response::ephemeral().components(&[]);
+    let actions = match actions {
+        Some(_) if button.get_cancel() => {
+            return ctx.reply(response.content("Cancelled.")).await;
+        }
+        Some(actions) => actions,
+        None => {
+            return ctx
+                .reply(response.content(":x: Error: This preview has expired or was already used."))
+                .await;
+        }
+    };
+
+    let (verb, past_verb) = verbs(kind);
+    let total = actions.get_action().len();
+    let mut errors = Vec::new();
+    for (idx, action) in actions.get_action().iter().enumerate() {
+        if idx % PROGRESS_INTERVAL == 0 {
+            let progress = Response::ephemeral().components(&[]).content(format!(
+                "Running mass {}: {}/{} members done...",
+                verb, idx, total
+            ));
+            if let Err(err) = ctx.reply(progress).await {
+                tracing::warn!("Failed to update mass {} progress: {}", verb, err);
+            }
+        }
+
+        if let Err(err) = executor.execute_action(action).await {
+            tracing::error!(
+                "Error while running mass {} on {}: {}",
+                verb,
+                action.get_user_id(),
+                err
+            );
+            errors.push(format!("<@{}>: {}", action.get_user_id(), err));
+        }
+        tokio::time::sleep(ACTION_INTERVAL).await;
+    }
+
+    let mut content = format!("{} {} members.", past_verb, total - errors.len());
+    if !errors.is_empty() {
+        content.push_str(&format!(" Failed to {} {} members:", verb, errors.len()));
+        push_lines(
+            &mut content,
+            errors.iter().map(|error| format!("- {}", error)),
+        );
+    }
+    ctx.reply(response.content(content)).await
+}
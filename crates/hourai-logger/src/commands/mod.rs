@@ -1,12 +1,19 @@
 mod admin;
+mod appeals;
+mod cases;
 mod config;
 mod escalation;
+mod filter;
+mod mass;
+mod notes;
 mod prelude;
+mod preview;
+mod scheduled;
 mod standard;
 mod verification;
 
 use anyhow::Result;
-use hourai::proto::action::StatusType;
+use hourai::{proto::action::StatusType, util::validation::InvalidConfig};
 use prelude::*;
 
 pub async fn handle_command(ctx: CommandContext, actions: &ActionExecutor) -> Result<()> {
@@ -44,8 +51,10 @@ pub async fn handle_command(ctx: CommandContext, actions: &ActionExecutor) -> Re
 
         // Admin Commands
         Command::Command("ban") => admin::ban(&ctx, actions).await,
-        Command::Command("kick") => admin::kick(&ctx, actions.storage()).await,
-        Command::Command("timeout") => admin::timeout(&ctx, actions.storage()).await,
+        Command::Command("kick") => admin::kick(&ctx, actions).await,
+        Command::Command("massban") => mass::massban(&ctx, actions).await,
+        Command::Command("masskick") => mass::masskick(&ctx, actions).await,
+        Command::Command("timeout") => admin::timeout(&ctx, actions).await,
         Command::Command("mute") => admin::mute(&ctx, actions).await,
         Command::Command("deafen") => admin::deafen(&ctx, actions).await,
         Command::Command("move") => admin::move_cmd(&ctx, actions.storage()).await,
@@ -61,12 +70,56 @@ pub async fn handle_command(ctx: CommandContext, actions: &ActionExecutor) -> Re
         Command::SubCommand("role", "get") => admin::role_get(&ctx, actions.storage()).await,
         Command::SubCommand("role", "drop") => admin::role_drop(&ctx, actions.storage()).await,
 
+        // Case commands
+        Command::SubCommand("case", "view") => cases::case_view(&ctx, actions.storage()).await,
+        Command::SubCommand("case", "reason") => cases::case_reason(&ctx, actions).await,
+        Command::Command("cases") => cases::cases(&ctx, actions.storage()).await,
+
+        // Note commands
+        Command::SubCommand("note", "add") => notes::note_add(&ctx, actions.storage()).await,
+        Command::SubCommand("note", "list") => notes::note_list(&ctx, actions.storage()).await,
+        Command::SubCommand("note", "remove") => notes::note_remove(&ctx, actions.storage()).await,
+
+        // Scheduled action commands
+        Command::SubCommand("scheduled", "list") => {
+            scheduled::scheduled_list(&ctx, actions.storage()).await
+        }
+        Command::SubCommand("scheduled", "cancel") => {
+            scheduled::scheduled_cancel(&ctx, actions.storage()).await
+        }
+        Command::SubCommand("scheduled", "run") => scheduled::scheduled_run(&ctx, actions).await,
+
         // Escalation commands
         Command::SubCommand("escalate", "up") => escalation::escalate(&ctx, actions).await,
         Command::SubCommand("escalate", "down") => escalation::deescalate(&ctx, actions).await,
         Command::SubCommand("escalate", "history") => {
             escalation::escalate_history(&ctx, actions).await
         }
+        Command::SubCommand("escalate", "pardon") => {
+            escalation::escalate_pardon(&ctx, actions).await
+        }
+        Command::SubCommand("escalate", "preview") => {
+            escalation::escalate_preview(&ctx, actions).await
+        }
+        Command::SubGroupCommand("escalate", "ladder", "list") => {
+            escalation::ladder_list(&ctx, actions.storage()).await
+        }
+        Command::SubGroupCommand("escalate", "ladder", "add") => {
+            escalation::ladder_add(&ctx, actions.storage()).await
+        }
+        Command::SubGroupCommand("escalate", "ladder", "remove") => {
+            escalation::ladder_remove(&ctx, actions.storage()).await
+        }
+        Command::SubGroupCommand("escalate", "ladder", "edit") => {
+            escalation::ladder_edit(&ctx, actions.storage()).await
+        }
+        Command::SubGroupCommand("escalate", "ladder", "mode") => {
+            escalation::ladder_mode(&ctx, actions.storage()).await
+        }
+
+        // Message filter commands
+        Command::SubCommand("filter", "stats") => filter::stats(&ctx, actions.storage()).await,
+
         // Config commands
         Command::SubGroupCommand("config", "reddit", "add") => config::reddit_add(&ctx).await,
         Command::SubGroupCommand("config", "reddit", "remove") => config::reddit_remove(&ctx).await,
@@ -78,6 +131,9 @@ pub async fn handle_command(ctx: CommandContext, actions: &ActionExecutor) -> Re
         Command::SubGroupCommand("config", "set", "modlog") => {
             config::setmodlog(&ctx, actions.storage()).await
         }
+        Command::SubGroupCommand("config", "set", "appeals") => {
+            config::setappeals(&ctx, actions.storage()).await
+        }
 
         Command::SubGroupCommand("config", "announce", "join") => {
             config::announce_join(&ctx, actions.storage()).await
@@ -111,6 +167,10 @@ pub async fn handle_command(ctx: CommandContext, actions: &ActionExecutor) -> Re
                 ctx.reply(response.content(format!(":x: Error: {}", command_err)))
                     .await?;
                 Ok(())
+            } else if let Some(config_err) = err.downcast_ref::<InvalidConfig>() {
+                ctx.reply(response.content(format!(":x: Error: {}", config_err)))
+                    .await?;
+                Ok(())
             } else {
                 ctx.reply(response.content(":x: Fatal Error: Internal Error has occured."))
                     .await?;
@@ -119,3 +179,34 @@ pub async fn handle_command(ctx: CommandContext, actions: &ActionExecutor) -> Re
         }
     }
 }
+
+/// Handles the modal forms opened by message components.
+pub async fn handle_modal(
+    ctx: hourai::interactions::ModalContext,
+    actions: &ActionExecutor,
+) -> Result<()> {
+    if ctx.metadata()?.has_appeal_button() {
+        appeals::handle_modal(ctx, actions).await
+    } else {
+        Ok(())
+    }
+}
+
+/// Handles the message components sent by commands. Verification buttons are handled separately.
+pub async fn handle_component(
+    ctx: hourai::interactions::ComponentContext,
+    actions: &ActionExecutor,
+) -> Result<()> {
+    let metadata = ctx.metadata()?;
+    if metadata.has_mass_action_button() {
+        mass::handle_component(ctx, actions).await
+    } else if metadata.has_escalation_history_button() {
+        escalation::handle_history_component(ctx, actions).await
+    } else if metadata.has_appeal_button() {
+        appeals::handle_component(ctx, actions).await
+    } else if metadata.has_action_button() {
+        preview::handle_component(ctx, actions).await
+    } else {
+        Ok(())
+    }
+}
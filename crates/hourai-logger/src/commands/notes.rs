@@ -0,0 +1,93 @@
+use super::prelude::*;
+use anyhow::Result;
+use chrono::Utc;
+use hourai_sql::Note;
+use twilight_util::builder::embed::*;
+
+/// The most notes shown by `/note list`.
+const MAX_LISTED_NOTES: i64 = 25;
+
+/// The error shown to users who are not moderators.
+const MODERATORS_ONLY: &str = "Only moderators can view or edit notes.";
+/// The longest note that can be added. Leaves room for the rest of the line in listings.
+const MAX_NOTE_LENGTH: usize = 500;
+
+pub(super) async fn note_add(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let user_id = ctx.get_user("user")?;
+    let content = ctx.get_string("content")?.trim();
+    if content.is_empty() {
+        anyhow::bail!(InteractionError::InvalidArgument(
+            "A note cannot be empty.".to_owned()
+        ));
+    } else if content.chars().count() > MAX_NOTE_LENGTH {
+        anyhow::bail!(InteractionError::InvalidArgument(format!(
+            "A note cannot be longer than {} characters.",
+            MAX_NOTE_LENGTH
+        )));
+    }
+
+    let note = Note {
+        id: 0,
+        guild_id: guild_id.get() as i64,
+        user_id: user_id.get() as i64,
+        author_id: ctx.user().id.get() as i64,
+        content: content.to_owned(),
+        timestamp: Utc::now(),
+    };
+    let (id,) = note.insert().fetch_one(storage.sql()).await?;
+    Ok(Response::ephemeral().content(format!("Added note #{} to <@{}>.", id, user_id)))
+}
+
+pub(super) async fn note_list(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let user_id = ctx.get_user("user")?;
+    let notes = Note::fetch_user(guild_id, user_id, MAX_LISTED_NOTES)
+        .fetch_all(storage.sql())
+        .await?;
+    if notes.is_empty() {
+        return Ok(Response::ephemeral().content(format!("<@{}> has no notes.", user_id)));
+    }
+
+    let mut desc = String::new();
+    for note in notes.iter() {
+        desc.push_str(&format!(
+            "**#{}** by <@{}> <t:{}:R>: {}\n",
+            note.id,
+            note.author_id,
+            note.timestamp.timestamp(),
+            note.content,
+        ));
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!("Notes for {}", user_id))
+        .description(desc)
+        .footer(EmbedFooterBuilder::new(format!(
+            "Showing up to the {} most recent notes.",
+            MAX_LISTED_NOTES
+        )))
+        .build();
+    Ok(Response::ephemeral().embed(embed))
+}
+
+pub(super) async fn note_remove(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let id = ctx.get_int("id")?;
+    let not_found = || InteractionError::InvalidArgument(format!("Note #{} does not exist.", id));
+    let note_id = i32::try_from(id).map_err(|_| not_found())?;
+    let note = Note::delete(guild_id, note_id)
+        .fetch_optional(storage.sql())
+        .await?
+        .ok_or_else(not_found)?;
+    Ok(Response::ephemeral().content(format!(
+        "Removed note #{} from <@{}>: {}",
+        note.id, note.user_id, note.content
+    )))
+}
@@ -3,3 +3,46 @@ pub use hourai::interactions::{
     Command, CommandContext, InteractionContext, InteractionError, Response,
 };
 pub use hourai_storage::{Storage, actions::ActionExecutor};
+
+use hourai::util::validation::ValidationError;
+
+/// Checks if the user running a command in a guild is one of its moderators.
+pub async fn is_moderator(ctx: &CommandContext, storage: &Storage) -> Result<bool> {
+    let member_roles = ctx
+        .command
+        .member
+        .as_ref()
+        .map(|m| m.roles.as_slice())
+        .unwrap_or_default();
+    hourai_storage::is_moderator(
+        ctx.guild_id()?,
+        member_roles.iter().copied(),
+        storage.redis(),
+    )
+    .await
+}
+
+/// Fails with `message` unless the user running a command in a guild is one of its moderators.
+pub async fn require_moderator(
+    ctx: &CommandContext,
+    storage: &Storage,
+    message: &'static str,
+) -> Result<()> {
+    if !is_moderator(ctx, storage).await? {
+        anyhow::bail!(InteractionError::MissingPermission(message));
+    }
+    Ok(())
+}
+
+/// Appends the problems a saved config already had before it was changed to a response, so
+/// they are not silently kept.
+pub fn with_warnings(content: impl Into<String>, warnings: &[ValidationError]) -> String {
+    let mut content = content.into();
+    if !warnings.is_empty() {
+        content.push_str("\n:warning: The config already had these problems:");
+        for warning in warnings {
+            content.push_str(&format!("\n- {}", warning));
+        }
+    }
+    content
+}
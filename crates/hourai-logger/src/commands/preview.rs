@@ -0,0 +1,189 @@
+use super::prelude::*;
+use hourai::{
+    interactions::ComponentContext,
+    models::{
+        channel::message::{
+            Component,
+            component::{ActionRow, Button, ButtonStyle},
+        },
+        guild::Permissions,
+    },
+    proto::{
+        action::{Action, ActionSet},
+        message_components::{ActionButton, MessageComponentProto},
+    },
+};
+
+/// The longest a preview or its result can get, leaving room for a list's last line.
+const MAX_LENGTH: usize = 1800;
+
+/// Lists the operations recorded by a dry run executor in response to a command's `preview`
+/// option, along with the targets the command refused.
+pub(super) fn preview_response(executor: &ActionExecutor, errors: &[String]) -> Response {
+    Response::ephemeral().content(describe_operations(executor, errors))
+}
+
+/// Like `preview_response`, but with buttons to run the previewed actions or discard them.
+/// Actions that failed during the preview are left out. The actions are kept in Redis until
+/// either button is used, or the preview expires.
+pub(super) async fn confirmable_preview(
+    executor: &ActionExecutor,
+    actions: Vec<Action>,
+    required_permissions: Permissions,
+    errors: &[String],
+) -> Result<Response> {
+    let response = preview_response(executor, errors);
+    if actions.is_empty() {
+        return Ok(response);
+    }
+
+    let id = rand::random::<u64>();
+    let mut set = ActionSet::new();
+    set.set_action(actions.into());
+    executor
+        .storage()
+        .redis()
+        .stored_actions()
+        .store(id, set)
+        .await?;
+
+    let confirm = create_button(
+        id,
+        required_permissions,
+        false,
+        "Confirm",
+        ButtonStyle::Danger,
+    )?;
+    let cancel = create_button(
+        id,
+        required_permissions,
+        true,
+        "Cancel",
+        ButtonStyle::Secondary,
+    )?;
+    Ok(response.components(&[Component::ActionRow(ActionRow {
+        components: vec![confirm, cancel],
+    })]))
+}
+
+/// Describes the operations recorded by a dry run executor, one per line, followed by the
+/// errors that kept the rest from being recorded.
+pub(super) fn describe_operations(executor: &ActionExecutor, errors: &[String]) -> String {
+    let operations = executor.take_operations();
+    let mut content = if operations.is_empty() {
+        "Preview: this would do nothing.".to_owned()
+    } else {
+        String::from("Preview: this would do the following:")
+    };
+    let lines = operations
+        .iter()
+        .enumerate()
+        .map(|(idx, operation)| format!("{}. {}", idx + 1, operation));
+    push_lines(&mut content, lines);
+    if !errors.is_empty() {
+        content.push_str("\nThis would fail for:");
+        push_lines(
+            &mut content,
+            errors.iter().map(|error| format!("- {}", error)),
+        );
+    }
+    content
+}
+
+/// Appends lines to a message, summarizing the ones that do not fit.
+pub(super) fn push_lines(content: &mut String, lines: impl ExactSizeIterator<Item = String>) {
+    let total = lines.len();
+    for (idx, line) in lines.enumerate() {
+        if content.len() + line.len() + 1 > MAX_LENGTH {
+            content.push_str(&format!("\n...and {} more.", total - idx));
+            break;
+        }
+        content.push('\n');
+        content.push_str(&line);
+    }
+}
+
+fn create_button(
+    id: u64,
+    required_permissions: Permissions,
+    cancel: bool,
+    label: &str,
+    style: ButtonStyle,
+) -> Result<Component> {
+    let mut button = ActionButton::new();
+    button.set_required_permissions(required_permissions.bits());
+    button.set_stored_actions_id(id);
+    button.set_cancel(cancel);
+
+    let mut proto = MessageComponentProto::new();
+    proto.set_action_button(button);
+
+    Ok(Component::Button(Button {
+        custom_id: Some(hourai::interactions::proto_to_custom_id(&proto)?),
+        disabled: false,
+        emoji: None,
+        label: Some(label.to_string()),
+        sku_id: None,
+        style,
+        url: None,
+    }))
+}
+
+/// Handles the confirm and cancel buttons of a preview. Previewed actions can only be run once.
+pub(super) async fn handle_component(
+    ctx: ComponentContext,
+    executor: &ActionExecutor,
+) -> Result<()> {
+    let metadata = ctx.metadata()?;
+    let button = metadata.get_action_button();
+    ctx.defer_update().await?;
+
+    let required = Permissions::from_bits_truncate(button.get_required_permissions());
+    if !button.get_cancel() && !ctx.has_user_permission(required) {
+        ctx.reply(
+            Response::ephemeral().content(":x: Error: You do not have the permissions to do this."),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let actions = if button.has_stored_actions_id() {
+        executor
+            .storage()
+            .redis()
+            .stored_actions()
+            .take(button.get_stored_actions_id())
+            .await?
+    } else {
+        Some(button.get_actions().clone())
+    };
+    let response = Response::ephemeral().components(&[]);
+    let actions = match actions {
+        Some(_) if button.get_cancel() => {
+            return ctx.reply(response.content("Cancelled.")).await;
+        }
+        Some(actions) => actions,
+        None => {
+            return ctx
+                .reply(response.content(":x: Error: This preview has expired or was already used."))
+                .await;
+        }
+    };
+
+    let total = actions.get_action().len();
+    let mut errors = Vec::new();
+    for action in actions.get_action() {
+        if let Err(err) = executor.execute_action(action).await {
+            tracing::error!("Error while running a previewed action: {}", err);
+            errors.push(format!("- <@{}>: {}", action.get_user_id(), err));
+        }
+    }
+
+    let mut content = format!(
+        "Done. {} of {} actions succeeded.",
+        total - errors.len(),
+        total
+    );
+    push_lines(&mut content, errors.into_iter());
+    ctx.reply(response.content(content)).await
+}
@@ -0,0 +1,152 @@
+use super::prelude::*;
+use crate::pending_events::CLAIM_LEASE;
+use hourai::proto::action::{Action, Action_oneof_details};
+use hourai_sql::{Executor, PendingAction};
+use hourai_storage::cases;
+use twilight_util::builder::embed::*;
+
+/// The most scheduled actions shown by `/scheduled list`.
+const MAX_LISTED_ACTIONS: i64 = 25;
+
+/// The longest error shown for each failed action in `/scheduled list`.
+const MAX_ERROR_LENGTH: usize = 100;
+
+/// The error shown to users who are not moderators.
+const MODERATORS_ONLY: &str = "Only moderators can view or change scheduled actions.";
+
+fn describe_action(action: &Action) -> String {
+    let name = cases::case_action(action).unwrap_or("Action");
+    match action.details.as_ref() {
+        Some(Action_oneof_details::change_role(info)) => {
+            let roles: Vec<String> = info
+                .get_role_ids()
+                .iter()
+                .map(|id| format!("<@&{}>", id))
+                .collect();
+            format!("{} {}", name, roles.join(", "))
+        }
+        _ => name.to_owned(),
+    }
+}
+
+fn describe_pending(pending: &PendingAction) -> String {
+    let mut description = format!(
+        "**#{}** {}",
+        pending.id(),
+        describe_action(pending.action())
+    );
+    if let Some(user_id) = pending.user_id() {
+        description.push_str(&format!(" for <@{}>", user_id));
+    }
+    match pending.failed_at() {
+        Some(failed_at) => {
+            description.push_str(&format!(" failed <t:{}:R>", failed_at.timestamp()));
+            if let Some(error) = pending.last_error() {
+                let mut excerpt: String = error.chars().take(MAX_ERROR_LENGTH).collect();
+                if excerpt.len() < error.len() {
+                    excerpt.push('…');
+                }
+                description.push_str(&format!(": {}", excerpt));
+            }
+        }
+        None => {
+            description.push_str(&format!(" <t:{}:R>", pending.timestamp().timestamp()));
+        }
+    }
+    match (pending.case_number(), pending.moderator_id()) {
+        (Some(case_number), _) => description.push_str(&format!(" (case #{})", case_number)),
+        (None, Some(moderator_id)) => description.push_str(&format!(" (by <@{}>)", moderator_id)),
+        (None, None) => {}
+    }
+    description
+}
+
+fn not_found(id: i32) -> InteractionError {
+    InteractionError::InvalidArgument(format!(
+        "Scheduled action #{} does not exist or is already running.",
+        id
+    ))
+}
+
+/// Gets the ID of the scheduled action a command names.
+fn get_id(ctx: &CommandContext) -> Result<i32> {
+    let id = ctx.get_int("id")?;
+    Ok(i32::try_from(id).map_err(|_| {
+        InteractionError::InvalidArgument(format!("#{} is not a valid scheduled action.", id))
+    })?)
+}
+
+pub(super) async fn scheduled_list(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let user_id = ctx.get_user("user").ok();
+    let scheduled = PendingAction::fetch_guild(guild_id, user_id, MAX_LISTED_ACTIONS)
+        .fetch_all(storage.sql())
+        .await?;
+    if scheduled.is_empty() {
+        return Ok(Response::ephemeral().content(match user_id {
+            Some(user_id) => format!("<@{}> has no scheduled actions.", user_id),
+            None => "There are no scheduled actions.".to_owned(),
+        }));
+    }
+
+    let mut desc = String::new();
+    for pending in scheduled.iter() {
+        desc.push_str(&describe_pending(pending));
+        desc.push('\n');
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(match user_id {
+            Some(user_id) => match ctx.resolve_user(user_id) {
+                Some(user) => format!("Scheduled actions for {}", user.name),
+                None => format!("Scheduled actions for user {}", user_id),
+            },
+            None => "Scheduled actions".to_owned(),
+        })
+        .description(desc)
+        .footer(EmbedFooterBuilder::new(format!(
+            "Showing up to the {} soonest actions.",
+            MAX_LISTED_ACTIONS
+        )))
+        .build();
+    Ok(Response::direct().embed(embed))
+}
+
+pub(super) async fn scheduled_cancel(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    require_moderator(ctx, storage, MODERATORS_ONLY).await?;
+
+    let id = get_id(ctx)?;
+    let pending = PendingAction::cancel(guild_id, id)
+        .fetch_optional(storage.sql())
+        .await?
+        .ok_or_else(|| not_found(id))?;
+    Ok(Response::direct().content(format!("Cancelled {}", describe_pending(&pending))))
+}
+
+pub(super) async fn scheduled_run(
+    ctx: &CommandContext,
+    executor: &ActionExecutor,
+) -> Result<Response> {
+    let guild_id = ctx.guild_id()?;
+    let sql = executor.storage().sql();
+    require_moderator(ctx, executor.storage(), MODERATORS_ONLY).await?;
+
+    let id = get_id(ctx)?;
+    let pending = PendingAction::claim(guild_id, id, CLAIM_LEASE)
+        .fetch_optional(sql)
+        .await?
+        .ok_or_else(|| not_found(id))?;
+    if let Err(err) = executor
+        .without_cases()
+        .execute_action(pending.action())
+        .await
+    {
+        sql.execute(pending.release()).await?;
+        return Err(err);
+    }
+    sql.execute(pending.finish()).await?;
+    Ok(Response::direct().content(format!("Ran {}", describe_pending(&pending))))
+}
@@ -151,7 +151,14 @@ pub(super) async fn ping_event(ctx: &CommandContext) -> Result<Response> {
 }
 
 pub(super) async fn info_user(ctx: &CommandContext, executor: &ActionExecutor) -> Result<Response> {
-    ctx.defer().await?;
+    // Notes are only for moderators, so only they can see the response when it has them. This
+    // has to be known before deferring, which decides who can see the response.
+    let show_notes = ctx.guild_id().is_ok() && is_moderator(ctx, executor.storage()).await?;
+    if show_notes {
+        ctx.defer_ephemeral().await?;
+    } else {
+        ctx.defer().await?;
+    }
     let user_id = ctx.get_user("user")?;
     if let Ok(guild_id) = ctx.guild_id() {
         let member = executor
@@ -160,7 +167,12 @@ pub(super) async fn info_user(ctx: &CommandContext, executor: &ActionExecutor) -
             .await?
             .model()
             .await?;
-        let embed = hourai_sql::whois::member(executor.storage().sql(), &member).await?;
+        let mut embed = hourai_sql::whois::member(executor.storage().sql(), &member).await?;
+        if show_notes {
+            embed = hourai_sql::whois::notes(executor.storage().sql(), embed, guild_id, user_id)
+                .await?;
+            return Ok(Response::ephemeral().embed(embed.build()));
+        }
         return Ok(Response::direct().embed(embed.build()));
     }
     let user = executor.http().user(user_id).await?.model().await?;
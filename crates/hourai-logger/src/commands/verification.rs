@@ -23,14 +23,12 @@ pub async fn setup(ctx: &CommandContext, storage: &Storage) -> Result<Response>
     } else {
         config.clear_role_id();
     }
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
 
-    Ok(Response::direct().content("Verification configuration enabled."))
+    Ok(Response::direct().content(with_warnings(
+        "Verification configuration enabled.",
+        &warnings,
+    )))
 }
 
 pub async fn disable(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
@@ -41,14 +39,9 @@ pub async fn disable(ctx: &CommandContext, storage: &Storage) -> Result<Response
     }
     let mut config: VerificationConfig = storage.redis().guild(guild_id).configs().get().await?;
     config.set_enabled(false);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
 
-    Ok(Response::direct().content("Verification disabled."))
+    Ok(Response::direct().content(with_warnings("Verification disabled.", &warnings)))
 }
 
 pub async fn verify(ctx: &CommandContext, actions: &ActionExecutor) -> Result<Response> {
@@ -160,14 +153,12 @@ pub async fn lockdown(ctx: &CommandContext, storage: &Storage) -> Result<Respons
     let expiration = Utc::now() + Duration::hours(duration_hours);
     let mut config: VerificationConfig = storage.redis().guild(guild_id).configs().get().await?;
     config.set_lockdown_expiration(expiration.timestamp() as u64);
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
 
-    Ok(Response::direct().content(format!("Lockdown enabled for {} hour(s).", duration_hours)))
+    Ok(Response::direct().content(with_warnings(
+        format!("Lockdown enabled for {} hour(s).", duration_hours),
+        &warnings,
+    )))
 }
 
 pub async fn lockdown_lift(ctx: &CommandContext, storage: &Storage) -> Result<Response> {
@@ -178,14 +169,9 @@ pub async fn lockdown_lift(ctx: &CommandContext, storage: &Storage) -> Result<Re
     }
     let mut config: VerificationConfig = storage.redis().guild(guild_id).configs().get().await?;
     config.clear_lockdown_expiration();
-    storage
-        .redis()
-        .guild(guild_id)
-        .configs()
-        .set(config)
-        .await?;
+    let warnings = hourai_storage::set_config(guild_id, storage, config).await?;
 
-    Ok(Response::direct().content("Lockdown lifted."))
+    Ok(Response::direct().content(with_warnings("Lockdown lifted.", &warnings)))
 }
 
 pub async fn propagate(ctx: &CommandContext, actions: &ActionExecutor) -> Result<Response> {
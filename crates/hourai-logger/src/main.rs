@@ -13,7 +13,7 @@ mod web;
 use anyhow::Result;
 use core::time::Duration;
 use hourai::{
-    cache::{InMemoryCache, ResourceType},
+    cache::{InMemoryCache, ResourceType, is_timed_out},
     config::{self, HouraiConfig},
     gateway::{Event, EventType, EventTypeFlags, Intents, MessageSender, StreamExt as _},
     init,
@@ -21,7 +21,7 @@ use hourai::{
         MessageLike,
         application::interaction::{Interaction, InteractionType},
         channel::{Channel, ChannelType, Message},
-        gateway::payload::incoming::*,
+        gateway::{GatewayReaction, payload::incoming::*},
         guild::{Member, MemberFlags, Permissions, Role},
         http::interaction::*,
         id::{Id, marker::*},
@@ -41,6 +41,7 @@ const BOT_INTENTS: Intents = Intents::from_bits_truncate(
         | Intents::GUILD_VOICE_STATES.bits()
         | Intents::GUILD_MEMBERS.bits()
         | Intents::GUILD_MESSAGES.bits()
+        | Intents::GUILD_MESSAGE_REACTIONS.bits()
         | Intents::GUILD_PRESENCES.bits()
         | Intents::MESSAGE_CONTENT.bits(),
 );
@@ -109,6 +110,7 @@ async fn async_main(config: HouraiConfig) -> Result<()> {
     // Setup background tasks
     tokio::spawn(web::run_server(
         config.clone(),
+        actions.http().clone(),
         storage.sql().clone(),
         storage.redis().clone(),
     ));
@@ -116,6 +118,11 @@ async fn async_main(config: HouraiConfig) -> Result<()> {
     tokio::spawn(flush_online(cache.clone(), storage.redis().clone()));
     tokio::spawn(pending_events::run_pending_actions(actions.clone()));
     tokio::spawn(pending_events::run_pending_deescalations(actions.clone()));
+    tokio::spawn(pending_events::run_scheduled_events(actions.clone()));
+    tokio::spawn(pending_events::sync_scheduled_events(
+        cache.clone(),
+        storage.clone(),
+    ));
 
     for mut shard in shards {
         let client = client.clone();
@@ -237,6 +244,13 @@ impl Client {
         let kind = event.kind();
         let result = match event {
             Event::MemberUpdate(evt) => {
+                // The cache has not seen this update yet, so it still holds the previous
+                // timeout state of the member.
+                if !self.0.cache.is_timed_out(evt.guild_id, evt.user.id)
+                    && is_timed_out(evt.communication_disabled_until)
+                {
+                    tokio::spawn(self.clone().on_member_timeout((**evt).clone()));
+                }
                 if self.0.cache.is_pending(evt.guild_id, evt.user.id) && !evt.pending {
                     let member = Member {
                         avatar: None,
@@ -292,6 +306,7 @@ impl Client {
             Event::MessageUpdate(evt) => self.on_message_update(*evt).await,
             Event::MessageDelete(evt) => self.on_message_delete(evt).await,
             Event::MessageDeleteBulk(evt) => self.on_message_bulk_delete(evt).await,
+            Event::ReactionAdd(evt) => self.on_reaction_add(evt.0).await,
             Event::RoleCreate(evt) => self.on_role_create(evt).await,
             Event::RoleUpdate(evt) => self.on_role_update(evt).await,
             Event::RoleDelete(evt) => self.on_role_delete(evt).await,
@@ -299,6 +314,8 @@ impl Client {
             Event::ChannelUpdate(evt) => self.on_channel_update(*evt).await,
             Event::ChannelDelete(evt) => self.on_channel_delete(*evt).await,
             Event::ThreadCreate(evt) => self.on_thread_create(*evt).await,
+            Event::ThreadUpdate(evt) => self.on_thread_update(*evt).await,
+            Event::ThreadDelete(evt) => self.on_thread_delete(evt).await,
             Event::ThreadListSync(evt) => self.on_thread_list_sync(evt).await,
             Event::VoiceStateUpdate(evt) => self.on_voice_state_update(*evt).await,
             _ => {
@@ -361,14 +378,7 @@ impl Client {
         res2?;
         res3?;
 
-        if let Ok(config) = self
-            .storage()
-            .redis()
-            .guild(evt.guild_id)
-            .configs()
-            .get::<hourai::proto::auto_config::AutoConfig>()
-            .await
-        {
+        if let Ok(config) = auto::compiled_config(self.storage(), evt.guild_id).await {
             let _ =
                 auto::AutoEngine::on_member_ban(&self.0.actions, &config, evt.guild_id, &evt.user)
                     .await;
@@ -413,14 +423,7 @@ impl Client {
             self.log_member(guild_id, &member).await?;
             res?;
         }
-        if let Ok(config) = self
-            .storage()
-            .redis()
-            .guild(guild_id)
-            .configs()
-            .get::<hourai::proto::auto_config::AutoConfig>()
-            .await
-        {
+        if let Ok(config) = auto::compiled_config(self.storage(), guild_id).await {
             let _ =
                 auto::AutoEngine::on_member_join(&self.0.actions, &config, guild_id, &member).await;
         }
@@ -445,6 +448,17 @@ impl Client {
             return Ok(());
         }
 
+        if let Ok(config) = auto::compiled_config(self.storage(), evt.guild_id).await {
+            let before = hourai_sql::Member::fetch(evt.guild_id, evt.user.id)
+                .fetch_optional(self.storage().sql())
+                .await
+                .ok()
+                .flatten();
+            let _ =
+                auto::AutoEngine::on_member_update(&self.0.actions, &config, before.as_ref(), &evt)
+                    .await;
+        }
+
         let mut txn = self.storage().sql().begin().await?;
         txn.execute(hourai_sql::Member::from(&evt).insert()).await?;
         txn.execute(Username::new(&evt.user).insert()).await?;
@@ -452,6 +466,11 @@ impl Client {
         Ok(())
     }
 
+    async fn on_member_timeout(self, evt: MemberUpdate) -> Result<()> {
+        let config = auto::compiled_config(self.storage(), evt.guild_id).await?;
+        auto::AutoEngine::on_member_timeout(&self.0.actions, &config, &evt).await
+    }
+
     async fn on_member_remove(&self, evt: MemberRemove) -> Result<()> {
         let (res1, res2, res3) = futures::join!(
             self.storage().execute(hourai_sql::Member::set_present(
@@ -463,14 +482,7 @@ impl Client {
             announcements::on_member_leave(self, evt.clone())
         );
 
-        if let Ok(config) = self
-            .storage()
-            .redis()
-            .guild(evt.guild_id)
-            .configs()
-            .get::<hourai::proto::auto_config::AutoConfig>()
-            .await
-        {
+        if let Ok(config) = auto::compiled_config(self.storage(), evt.guild_id).await {
             let _ = auto::AutoEngine::on_member_remove(
                 &self.0.actions,
                 &config,
@@ -520,6 +532,13 @@ impl Client {
     }
 
     async fn on_thread_create(&mut self, evt: ThreadCreate) -> Result<()> {
+        if let Some(guild_id) = evt.0.guild_id {
+            self.storage()
+                .redis()
+                .guild(guild_id)
+                .save_resource(evt.0.id, &evt.0)
+                .await?;
+        }
         if evt.0.kind == ChannelType::PublicThread {
             self.http().join_thread(evt.0.id).await?;
             info!("Joined thread {}", evt.0.id);
@@ -527,8 +546,35 @@ impl Client {
         Ok(())
     }
 
+    async fn on_thread_update(&self, evt: ThreadUpdate) -> Result<()> {
+        if let Some(guild_id) = evt.0.guild_id {
+            self.storage()
+                .redis()
+                .guild(guild_id)
+                .save_resource(evt.0.id, &evt.0)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn on_thread_delete(&self, evt: ThreadDelete) -> Result<()> {
+        self.storage()
+            .redis()
+            .guild(evt.guild_id)
+            .delete_resource::<Channel>(evt.id)
+            .await
+    }
+
     async fn on_thread_list_sync(&mut self, evt: ThreadListSync) -> Result<()> {
-        for thread in evt.threads {
+        let mut redis = self.storage().redis().guild(evt.guild_id);
+        for mut thread in evt.threads {
+            thread.guild_id = Some(evt.guild_id);
+            if let Err(err) = redis.save_resource(thread.id, &thread).await {
+                error!(
+                    "Error while caching thread {} in guild {}: {} ({:?})",
+                    thread.id, evt.guild_id, err, err
+                );
+            }
             if let Err(err) = self.http().join_thread(thread.id).await {
                 error!(
                     "Error while joining new thread in guild {}: {} ({:?})",
@@ -543,15 +589,16 @@ impl Client {
 
     async fn on_message_create(self, evt: Message) -> Result<()> {
         if let Some(guild_id) = evt.guild_id
-            && let Ok(config) = self
-                .storage()
-                .redis()
-                .guild(guild_id)
-                .configs()
-                .get::<hourai::proto::auto_config::AutoConfig>()
-                .await
+            && let Ok(config) = auto::compiled_config(self.storage(), guild_id).await
         {
-            let _ = auto::AutoEngine::on_message(&self.0.actions, &config, &evt, false).await;
+            let _ = auto::AutoEngine::on_message(
+                &self.0.actions,
+                &config,
+                &evt,
+                evt.member.as_ref(),
+                false,
+            )
+            .await;
         }
         match message_filter::check_message(&self.0.actions, &evt).await {
             Ok(deleted) => {
@@ -580,15 +627,10 @@ impl Client {
             let before = msg.clone();
             msg.set_content(evt.content.clone());
             if let Some(guild_id) = msg.guild_id()
-                && let Ok(config) = self
-                    .storage()
-                    .redis()
-                    .guild(guild_id)
-                    .configs()
-                    .get::<hourai::proto::auto_config::AutoConfig>()
-                    .await
+                && let Ok(config) = auto::compiled_config(self.storage(), guild_id).await
             {
-                let _ = auto::AutoEngine::on_message(&self.0.actions, &config, &msg, true).await;
+                let _ =
+                    auto::AutoEngine::on_message(&self.0.actions, &config, &msg, None, true).await;
             }
             tokio::spawn(message_logging::on_message_update(
                 self.clone(),
@@ -622,7 +664,15 @@ impl Client {
             }
             InteractionType::MessageComponent => {
                 let ctx = hourai::interactions::ComponentContext::new(self.http().clone(), evt);
-                verification::handle_component_interaction(ctx, &self).await?;
+                if ctx.metadata()?.has_verification_button() {
+                    verification::handle_component_interaction(ctx, &self).await?;
+                } else {
+                    commands::handle_component(ctx, &self.0.actions).await?;
+                }
+            }
+            InteractionType::ModalSubmit => {
+                let ctx = hourai::interactions::ModalContext::new(self.http().clone(), evt);
+                commands::handle_modal(ctx, &self.0.actions).await?;
             }
             interaction => {
                 warn!("Unknown incoming interaction: {:?}", interaction);
@@ -632,8 +682,34 @@ impl Client {
         Ok(())
     }
 
-    async fn on_message_delete(mut self, evt: MessageDelete) -> Result<()> {
-        message_logging::on_message_delete(&mut self, &evt).await?;
+    async fn on_message_delete(self, evt: MessageDelete) -> Result<()> {
+        let cached = self
+            .storage()
+            .redis()
+            .messages()
+            .fetch(evt.channel_id, evt.id)
+            .await;
+        match cached {
+            Ok(Some(msg)) => {
+                if let Some(guild_id) = evt.guild_id {
+                    let result = match auto::compiled_config(self.storage(), guild_id).await {
+                        Ok(config) => {
+                            auto::AutoEngine::on_message_delete(&self.0.actions, &config, &msg)
+                                .await
+                        }
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = result {
+                        error!("Error while running message delete events: {}", err);
+                    }
+                }
+                if let Err(err) = message_logging::on_message_delete(&self, &evt, &msg).await {
+                    error!("Error while logging deleted message: {}", err);
+                }
+            }
+            Ok(None) => {}
+            Err(err) => error!("Error while fetching deleted message {}: {}", evt.id, err),
+        }
         self.storage()
             .redis()
             .messages()
@@ -655,6 +731,15 @@ impl Client {
         Ok(())
     }
 
+    async fn on_reaction_add(self, evt: GatewayReaction) -> Result<()> {
+        if let Some(guild_id) = evt.guild_id
+            && let Ok(config) = auto::compiled_config(self.storage(), guild_id).await
+        {
+            let _ = auto::AutoEngine::on_reaction_add(&self.0.actions, &config, &evt).await;
+        }
+        Ok(())
+    }
+
     async fn on_guild_create(self, evt: GuildCreate) -> Result<()> {
         match evt {
             GuildCreate::Available(guild) => {
@@ -663,6 +748,8 @@ impl Client {
                 let mut redis_guild = self.storage().redis().guild(guild.id);
                 redis_guild.voice_states().update_guild(&guild).await?;
                 redis_guild.save(&guild).await?;
+                // Compiling the config syncs its scheduled events.
+                auto::compiled_config(self.storage(), guild.id).await?;
             }
             GuildCreate::Unavailable(guild) => {
                 info!("Joined Guild: {}", guild.id);
@@ -715,6 +802,20 @@ impl Client {
         } else {
             None
         };
+        if let Some(guild_id) = evt.0.guild_id
+            && let Some(member) = evt.0.member.as_ref()
+            && let Ok(config) = auto::compiled_config(self.storage(), guild_id).await
+        {
+            let _ = auto::AutoEngine::on_voice_update(
+                &self.0.actions,
+                &config,
+                guild_id,
+                member,
+                before,
+                evt.0.channel_id,
+            )
+            .await;
+        }
         announcements::on_voice_update(&self, evt.0.clone(), before).await?;
         if let Some(guild_id) = evt.0.guild_id {
             let redis = self.storage().redis().guild(guild_id);
@@ -1,13 +1,17 @@
 use crate::message_logging;
 use anyhow::Result;
-use hourai::proto::guild_configs::*;
+use hourai::proto::{
+    event::{DiscordMessage, EventSource, MessageFilterMatch},
+    guild_configs::*,
+};
 use hourai::{
     models::{Snowflake, id::Id, message::MessageLike, user::UserLike},
-    util::mentions,
+    util::{mentions, stable_hash, template::TemplateContext},
 };
 
 use hourai_sql::Member;
 use hourai_storage::actions::ActionExecutor;
+use protobuf::Message;
 use regex::{Regex, RegexSet};
 use std::collections::HashSet;
 
@@ -47,10 +51,25 @@ pub async fn check_message(executor: &ActionExecutor, message: &impl MessageLike
         false
     };
 
-    for rule in config.get_message_filter().get_rules() {
+    for (idx, rule) in config.get_message_filter().get_rules().iter().enumerate() {
+        let key = rule_key(rule);
+        metrics::increment_counter!(
+            "hourai_message_filter_evaluations_total",
+            "guild" => guild_id.to_string(),
+            "rule" => key.clone(),
+        );
         let reasons = get_filter_reasons(moderator, message, rule.get_criteria()).await?;
         if !reasons.is_empty() {
-            apply_rule(message, rule, reasons, executor).await?;
+            metrics::increment_counter!(
+                "hourai_message_filter_hits_total",
+                "guild" => guild_id.to_string(),
+                "rule" => key.clone(),
+            );
+            if let Err(err) = redis.guild(guild_id).filter_stats().record_hit(&key).await {
+                tracing::warn!("Failed to record message filter hit: {}", err);
+            }
+            let label = rule_label(idx, rule);
+            apply_rule(message, rule, &label, &key, reasons, executor).await?;
             return Ok(rule.get_delete_message());
         }
     }
@@ -58,6 +77,24 @@ pub async fn check_message(executor: &ActionExecutor, message: &impl MessageLike
     Ok(false)
 }
 
+/// Gets a human readable label for a message filter rule. Unnamed rules are labeled by their
+/// 1-indexed position in the rule list.
+pub fn rule_label(idx: usize, rule: &MessageFilterRule) -> String {
+    if rule.get_name().is_empty() {
+        format!("Rule #{}", idx + 1)
+    } else {
+        rule.get_name().to_owned()
+    }
+}
+
+/// Gets the stable identity of a message filter rule, which its statistics and metrics are
+/// recorded under. Derived from the rule's criteria, so renaming or reordering rules keeps their
+/// history.
+pub fn rule_key(rule: &MessageFilterRule) -> String {
+    let criteria = rule.get_criteria().write_to_bytes().unwrap_or_default();
+    format!("{:016x}", stable_hash(&criteria))
+}
+
 fn generalize_filters(filters: &[&str]) -> Result<RegexSet, regex::Error> {
     let generalized = filters
         .iter()
@@ -82,6 +119,8 @@ fn generalize_filter(filter: &str) -> String {
 async fn apply_rule(
     message: &impl MessageLike,
     rule: &MessageFilterRule,
+    label: &str,
+    key: &str,
     reasons: Vec<String>,
     executor: &ActionExecutor,
 ) -> Result<()> {
@@ -121,6 +160,7 @@ async fn apply_rule(
             .await?;
 
         let http = executor.http().clone();
+        let rule_key = key.to_owned();
         tokio::spawn(async move {
             // TODO(james7132): DM the user that their message was deleted.
             let result = http.delete_message(channel_id, message_id).await;
@@ -131,6 +171,12 @@ async fn apply_rule(
                     channel_id,
                     err
                 );
+            } else {
+                metrics::increment_counter!(
+                    "hourai_message_filter_deletions_total",
+                    "guild" => guild_id.to_string(),
+                    "rule" => rule_key,
+                );
             }
         });
 
@@ -143,17 +189,47 @@ async fn apply_rule(
 
     if !rule.additional_actions.is_empty() {
         let rule = rule.clone();
+        let rule_key = key.to_owned();
         let exec = executor.clone();
+        let mut details = MessageFilterMatch::new();
+        details.set_rule(label.to_owned());
+        details.set_reasons(reasons.clone().into());
+        details.set_message(DiscordMessage::from_message(message));
+        let mut source = EventSource::new();
+        source.set_channel_id(channel_id.get());
+        source.set_message_filter(details);
+        let ctx = TemplateContext {
+            user_id: Some(author_id),
+            user_name: Some(message.author().name().to_owned()),
+            channel_id: Some(channel_id),
+            message: Some(message.content().to_owned()),
+            ..Default::default()
+        };
         tokio::spawn(async move {
-            for action_template in rule.additional_actions.iter() {
-                let mut action = action_template.clone();
-                action.set_guild_id(guild_id.get());
-                action.set_user_id(author_id.get());
-                action.set_reason(format!("Triggered message filter: {}", rule.get_name()));
-                if let Err(err) = exec.execute_action(&action).await {
-                    tracing::error!("Error while running actions for message filter: {}", err);
-                    break;
-                }
+            let actions = rule
+                .additional_actions
+                .iter()
+                .map(|action_template| {
+                    let mut action = action_template.clone();
+                    action.set_guild_id(guild_id.get());
+                    action.set_user_id(author_id.get());
+                    action.set_reason(format!("Triggered message filter: {}", rule.get_name()));
+                    action
+                })
+                .collect();
+            let event = exec.execute_event(guild_id, source, actions, &ctx).await;
+            let failures = event
+                .get_results()
+                .iter()
+                .filter(|result| !result.get_success())
+                .count();
+            if failures > 0 {
+                metrics::counter!(
+                    "hourai_message_filter_action_failures_total",
+                    failures as u64,
+                    "guild" => guild_id.to_string(),
+                    "rule" => rule_key,
+                );
             }
         });
     }
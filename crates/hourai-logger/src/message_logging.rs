@@ -8,6 +8,7 @@ use hourai::models::{
     id::{Id, marker::*},
     util::Timestamp,
 };
+use hourai::proto::cache::CachedMessageProto;
 use hourai::proto::guild_configs::*;
 use hourai::proto::util::IdFilter;
 use twilight_util::builder::embed::*;
@@ -99,8 +100,16 @@ pub(super) async fn on_message_update(
     Ok(())
 }
 
-pub(super) async fn on_message_delete(client: &mut Client, evt: &MessageDelete) -> Result<()> {
+/// Logs a deleted message. `msg` is the deleted message as it was cached.
+pub(super) async fn on_message_delete(
+    client: &Client,
+    evt: &MessageDelete,
+    msg: &CachedMessageProto,
+) -> Result<()> {
     let guild_id = evt.guild_id.ok_or_else(|| anyhow!("Not in guild."))?;
+    if msg.author().bot() {
+        return Ok(());
+    }
     let redis = client.storage().redis();
     let config: LoggingConfig = redis.guild(guild_id).configs().get().await?;
     let type_config = config.get_deleted_messages();
@@ -108,24 +117,18 @@ pub(super) async fn on_message_delete(client: &mut Client, evt: &MessageDelete)
     if let Some(channel) = output_channel
         && should_log(type_config, evt.channel_id)
     {
-        let cached = redis.messages().fetch(evt.channel_id, evt.id).await?;
-        if let Some(msg) = cached {
-            if msg.author().bot() {
-                return Ok(());
-            }
-            client
-                .http()
-                .create_message(channel)
-                .content(&format!(
-                    "Message by <@{}> deleted from <#{}>",
-                    msg.author().get_id(),
-                    msg.get_channel_id()
-                ))
-                .embeds(&[message_to_embed(&msg)?
-                    .color(0x992d22) // Dark red
-                    .build()])
-                .await?;
-        }
+        client
+            .http()
+            .create_message(channel)
+            .content(&format!(
+                "Message by <@{}> deleted from <#{}>",
+                msg.author().get_id(),
+                msg.get_channel_id()
+            ))
+            .embeds(&[message_to_embed(msg)?
+                .color(0x992d22) // Dark red
+                .build()])
+            .await?;
     }
     Ok(())
 }
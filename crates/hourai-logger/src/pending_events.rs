@@ -1,36 +1,166 @@
-use crate::utils;
+use crate::{auto, utils};
 use anyhow::Result;
-use futures::stream::StreamExt;
-use hourai_sql::{Executor, PendingAction, PendingDeescalation};
-use hourai_storage::{actions::ActionExecutor, escalation::EscalationManager};
+use chrono::{DateTime, Utc};
+use hourai::{
+    cache::InMemoryCache,
+    models::id::{Id, marker::*},
+    proto::{
+        auto_config::{ScheduledEvent, ScheduledEvent_CatchUp},
+        event::{AutoConfigTrigger, EventSource},
+    },
+    util::{cron::Schedule, template::TemplateContext},
+};
+use hourai_sql::{
+    Executor, Member, PendingAction, PendingDeescalation, ScheduledRule, SqlPool,
+    postgres::PgListener,
+};
+use hourai_storage::{
+    Storage,
+    actions::{ActionError, ActionExecutor},
+    escalation::EscalationManager,
+};
 use tokio::time::{Duration, Instant};
 
-const CYCLE_DURATION: Duration = Duration::from_secs(1);
+/// How late a scheduled run can start before it is considered missed.
+const MISSED_RUN_GRACE_SECONDS: i64 = 60;
+
+/// How often the schedules of cached guilds are synced with their AutoConfigs.
+const SCHEDULE_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The most runs of a scheduled event that are caught up on at once.
+const MAX_CATCH_UP_RUNS: usize = 24;
+
+/// The longest the schedulers sleep without checking for due work, in case a wakeup is missed.
+const MAX_IDLE_DURATION: Duration = Duration::from_secs(60);
+
+/// How long a claimed pending action, deescalation or scheduled event is reserved for the
+/// instance that claimed it. Claims that outlive their lease, such as those of a crashed instance, are claimed again.
+pub(crate) const CLAIM_LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// How long before its lease runs out a pending action is given up on, so it is not claimed
+/// again while it is still running.
+const LEASE_MARGIN: Duration = Duration::from_secs(30);
+
+/// The most pending actions, deescalations or scheduled events claimed at once.
+const CLAIM_BATCH_SIZE: i64 = 50;
+
+/// How many times a pending action or deescalation is attempted before it is marked as failed.
+const MAX_ATTEMPTS: i32 = 8;
+
+/// The delay before the first retry. Doubles with every following attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(10);
+
+/// The longest delay between retries.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Sleeps until scheduled work is due, waking up early when new work is scheduled.
+struct Wakeup {
+    listener: Option<PgListener>,
+}
+
+impl Wakeup {
+    /// Listens for work being scheduled on `channel`. Falls back to polling if the listener
+    /// cannot be set up.
+    async fn listen(sql: &SqlPool, channel: &str) -> Self {
+        let listener = match PgListener::connect_with(sql).await {
+            Ok(mut listener) => match listener.listen(channel).await {
+                Ok(()) => Some(listener),
+                Err(err) => {
+                    tracing::warn!("Failed to listen on {}, polling instead: {}", channel, err);
+                    None
+                }
+            },
+            Err(err) => {
+                tracing::warn!("Failed to listen on {}, polling instead: {}", channel, err);
+                None
+            }
+        };
+        Self { listener }
+    }
+
+    /// Waits until the next scheduled work is due, `seconds_until_due` seconds from now, or
+    /// until new work is scheduled.
+    async fn wait(&mut self, seconds_until_due: Option<f64>) {
+        let delay = seconds_until_due
+            .map(|seconds| Duration::from_secs_f64(seconds.max(0.0)))
+            .unwrap_or(MAX_IDLE_DURATION)
+            .min(MAX_IDLE_DURATION);
+        let deadline = Instant::now() + delay;
+        match self.listener.as_mut() {
+            Some(listener) => tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => {}
+                result = listener.recv() => {
+                    if let Err(err) = result {
+                        tracing::error!("Error while waiting for scheduled work: {}", err);
+                        tokio::time::sleep_until(deadline).await;
+                    }
+                }
+            },
+            None => tokio::time::sleep_until(deadline).await,
+        }
+    }
+}
+
+/// Records the targets of actions scheduled before they had their own columns, so moderators
+/// can list and cancel them.
+async fn backfill_targets(sql: &SqlPool) -> Result<()> {
+    let mut after = 0;
+    loop {
+        let untargeted = PendingAction::fetch_untargeted(after, CLAIM_BATCH_SIZE)
+            .fetch_all(sql)
+            .await?;
+        let Some(last) = untargeted.last() else {
+            return Ok(());
+        };
+        after = last.id();
+        for pending in untargeted.iter() {
+            sql.execute(pending.set_targets()).await?;
+        }
+    }
+}
 
 pub async fn run_pending_actions(executor: ActionExecutor) {
+    let sql = executor.storage().sql().clone();
+    if let Err(err) = backfill_targets(&sql).await {
+        tracing::error!(
+            "Error while recording the targets of pending actions: {}",
+            err
+        );
+    }
+    let mut wakeup = Wakeup::listen(&sql, PendingAction::CHANNEL).await;
     loop {
-        let next = Instant::now() + CYCLE_DURATION;
-        let mut pending = PendingAction::fetch_expired().fetch(executor.storage().sql());
-        while let Some(item) = pending.next().await {
-            match item {
-                Ok(action) => {
+        match PendingAction::claim_due(CLAIM_LEASE, CLAIM_BATCH_SIZE)
+            .fetch_all(&sql)
+            .await
+        {
+            Ok(claimed) => {
+                for action in claimed {
                     tokio::spawn(utils::log_error(
                         "running pending action",
                         run_action(executor.clone(), action),
                     ));
                 }
-                Err(err) => {
-                    tracing::error!("Error while fetching pending actions: {}", err);
-                    break;
-                }
             }
+            Err(err) => tracing::error!("Error while claiming pending actions: {}", err),
         }
-        tokio::time::sleep_until(next).await;
+
+        let seconds_until_due = match PendingAction::seconds_until_due().fetch_one(&sql).await {
+            Ok((seconds,)) => seconds,
+            Err(err) => {
+                tracing::error!("Error while fetching the next pending action: {}", err);
+                None
+            }
+        };
+        wakeup.wait(seconds_until_due).await;
     }
 }
 
 fn is_client_error(err: &anyhow::Error) -> bool {
     use hourai::http::error::*;
+    // Actions refused by the executor will not succeed if retried.
+    if err.is::<ActionError>() {
+        return true;
+    }
     if let Some(err) = err.downcast_ref::<Error>()
         && let ErrorType::Response { status, .. } = err.kind()
     {
@@ -41,14 +171,61 @@ fn is_client_error(err: &anyhow::Error) -> bool {
 
 async fn run_action(executor: ActionExecutor, pending: PendingAction) -> Result<()> {
     tracing::debug!("Running pending action: {:?}", pending.action());
-    if let Err(err) = executor.execute_action(pending.action()).await {
-        if !is_client_error(&err) {
-            return Err(err);
+    let sql = executor.storage().sql();
+    // The case for the action being undone already covers its undo. Actions that outlive their
+    // lease could be claimed and run again, so they are retried instead.
+    let executor = executor.without_cases();
+    let result = tokio::time::timeout(
+        CLAIM_LEASE - LEASE_MARGIN,
+        executor.execute_action(pending.action()),
+    )
+    .await
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("Timed out before the lease ran out")));
+    if let Err(err) = result {
+        if is_client_error(&err) {
+            tracing::error!(
+                "Client error while running pending action {} in guild {:?}: {}",
+                pending.id(),
+                pending.guild_id(),
+                err
+            );
+            sql.execute(pending.fail(&err.to_string())).await?;
+            return Ok(());
+        }
+        let (failed,) = pending
+            .retry(
+                &err.to_string(),
+                MAX_ATTEMPTS,
+                RETRY_BASE_DELAY,
+                RETRY_MAX_DELAY,
+            )
+            .fetch_optional(sql)
+            .await?
+            .unwrap_or((false,));
+        if failed {
+            tracing::error!(
+                "Giving up on pending action {} in guild {:?} after {} attempts: {}",
+                pending.id(),
+                pending.guild_id(),
+                pending.attempts(),
+                err
+            );
         } else {
-            tracing::error!("Client Error while running pending actions: {}", err);
+            tracing::warn!(
+                "Retrying pending action {} after attempt {}: {}",
+                pending.id(),
+                pending.attempts(),
+                err
+            );
         }
+        return Ok(());
+    }
+    if sql.execute(pending.finish()).await?.rows_affected() == 0 {
+        tracing::warn!(
+            "Pending action {} was claimed again while it ran, and may run twice",
+            pending.id()
+        );
     }
-    executor.storage().sql().execute(pending.delete()).await?;
     tracing::info!("Ran pending action: {:?}", pending.action());
     Ok(())
 }
@@ -56,49 +233,279 @@ async fn run_action(executor: ActionExecutor, pending: PendingAction) -> Result<
 pub async fn run_pending_deescalations(executor: ActionExecutor) {
     let storage = executor.storage().clone();
     let escalation_manager = EscalationManager::new(executor);
+    let mut wakeup = Wakeup::listen(storage.sql(), PendingDeescalation::CHANNEL).await;
     loop {
-        let next = Instant::now() + CYCLE_DURATION;
-        let mut pending = PendingDeescalation::fetch_expired().fetch(&storage);
-        while let Some(item) = pending.next().await {
-            match item {
-                Ok(deescalation) => {
+        match PendingDeescalation::claim_due(CLAIM_LEASE, CLAIM_BATCH_SIZE)
+            .fetch_all(storage.sql())
+            .await
+        {
+            Ok(claimed) => {
+                for deescalation in claimed {
                     tokio::spawn(utils::log_error(
                         "running automatic deescalation",
                         run_deescalation(escalation_manager.clone(), deescalation),
                     ));
                 }
-                Err(err) => {
-                    tracing::error!("Error while fetching pending deescalations: {}", err);
-                    break;
-                }
             }
+            Err(err) => tracing::error!("Error while claiming pending deescalations: {}", err),
         }
-        tokio::time::sleep_until(next).await;
+
+        let seconds_until_due = match PendingDeescalation::seconds_until_due()
+            .fetch_one(storage.sql())
+            .await
+        {
+            Ok((seconds,)) => seconds,
+            Err(err) => {
+                tracing::error!(
+                    "Error while fetching the next pending deescalation: {}",
+                    err
+                );
+                None
+            }
+        };
+        wakeup.wait(seconds_until_due).await;
     }
 }
 
 async fn run_deescalation(
     escalation_manager: EscalationManager,
     pending: PendingDeescalation,
+) -> Result<()> {
+    let sql = escalation_manager.executor().storage().sql();
+    if let Err(err) = deescalate(&escalation_manager, &pending).await {
+        let (failed,) = pending
+            .retry(
+                &err.to_string(),
+                MAX_ATTEMPTS,
+                RETRY_BASE_DELAY,
+                RETRY_MAX_DELAY,
+            )
+            .fetch_optional(sql)
+            .await?
+            .unwrap_or((false,));
+        if failed {
+            tracing::error!(
+                "Giving up on automatic deescalation for user {} in guild {}: {}",
+                pending.user_id(),
+                pending.guild_id(),
+                err
+            );
+        } else {
+            tracing::warn!(
+                "Retrying automatic deescalation for user {} in guild {}: {}",
+                pending.user_id(),
+                pending.guild_id(),
+                err
+            );
+        }
+        return Ok(());
+    }
+    if sql.execute(pending.finish()).await?.rows_affected() == 0 {
+        tracing::warn!(
+            "Automatic deescalation for user {} in guild {} was claimed again while it ran, and \
+             may run twice",
+            pending.user_id(),
+            pending.guild_id(),
+        );
+    }
+
+    tracing::info!(
+        "Ran automatic deescalation for user {} in guild {}",
+        pending.user_id(),
+        pending.guild_id(),
+    );
+
+    Ok(())
+}
+
+async fn deescalate(
+    escalation_manager: &EscalationManager,
+    pending: &PendingDeescalation,
 ) -> Result<()> {
     let user = escalation_manager.executor().current_user();
     let guild = escalation_manager.guild(pending.guild_id()).await?;
     let history = guild.fetch_history(pending.user_id()).await?;
 
-    history
-        .apply_delta(
-            /*authorizer=*/ user,
-            /*reason=*/ "Automatic Deescalation",
-            /*diff=*/ pending.amount,
-            /*execute=*/ pending.amount >= 0,
-        )
-        .await?;
+    if history.uses_points() {
+        history.expire_points(user, pending.amount).await?;
+    } else {
+        history
+            .apply_delta(
+                /*authorizer=*/ user,
+                /*reason=*/ "Automatic Deescalation",
+                /*diff=*/ pending.amount,
+                /*execute=*/ pending.amount >= 0,
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn run_scheduled_events(executor: ActionExecutor) {
+    let sql = executor.storage().sql().clone();
+    let mut wakeup = Wakeup::listen(&sql, ScheduledRule::CHANNEL).await;
+    loop {
+        match ScheduledRule::claim_due(CLAIM_LEASE, CLAIM_BATCH_SIZE)
+            .fetch_all(&sql)
+            .await
+        {
+            Ok(claimed) => {
+                for rule in claimed {
+                    if let Err(err) = start_scheduled_event(&executor, rule).await {
+                        tracing::error!("Error while starting scheduled event: {}", err);
+                    }
+                }
+            }
+            Err(err) => tracing::error!("Error while claiming scheduled events: {}", err),
+        }
+
+        let seconds_until_due = match ScheduledRule::seconds_until_due().fetch_one(&sql).await {
+            Ok((seconds,)) => seconds,
+            Err(err) => {
+                tracing::error!("Error while fetching the next scheduled event: {}", err);
+                None
+            }
+        };
+        wakeup.wait(seconds_until_due).await;
+    }
+}
+
+/// Periodically recompiles the AutoConfigs of cached guilds so that schedules added or removed
+/// by other processes are stored even if nothing happens in the guild.
+pub async fn sync_scheduled_events(cache: InMemoryCache, storage: Storage) {
+    loop {
+        let next = Instant::now() + SCHEDULE_SYNC_INTERVAL;
+        for guild_id in cache.guilds() {
+            if let Err(err) = auto::compiled_config(&storage, guild_id).await {
+                tracing::error!(
+                    "Error while syncing scheduled events for guild {}: {}",
+                    guild_id,
+                    err
+                );
+            }
+        }
+        tokio::time::sleep_until(next).await;
+    }
+}
+
+/// Works out how many times a due scheduled event should run and moves its next run forward
+/// before running it, so that slow runs are not picked up again by the next cycle.
+async fn start_scheduled_event(executor: &ActionExecutor, rule: ScheduledRule) -> Result<()> {
+    let sql = executor.storage().sql();
+    let schedule: Schedule = match rule.event().get_schedule().parse() {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            tracing::error!("Removing scheduled event with an invalid schedule: {}", err);
+            sql.execute(rule.delete()).await?;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now();
+    let mut runs: Vec<DateTime<Utc>> = Vec::new();
+    let mut run = Some(rule.next_run());
+    while let Some(time) = run {
+        if time > now || runs.len() >= MAX_CATCH_UP_RUNS {
+            break;
+        }
+        runs.push(time);
+        run = schedule.next_after(time);
+    }
+    match schedule.next_after(now) {
+        Some(next_run) => sql.execute(rule.set_next_run(next_run)).await?,
+        None => sql.execute(rule.delete()).await?,
+    };
+
+    let missed = runs
+        .iter()
+        .filter(|time| (now - **time).num_seconds() > MISSED_RUN_GRACE_SECONDS)
+        .count();
+    let count = match rule.event().get_catch_up() {
+        ScheduledEvent_CatchUp::SKIP => runs.len() - missed,
+        ScheduledEvent_CatchUp::RUN_ONCE => runs.len().min(1),
+        ScheduledEvent_CatchUp::RUN_ALL => runs.len(),
+    };
+    if missed > 0 {
+        tracing::info!(
+            "Scheduled event {:x} in guild {} missed {} runs, running {} times",
+            rule.rule_id(),
+            rule.guild_id(),
+            missed,
+            count
+        );
+    }
+
+    if count > 0 {
+        tokio::spawn(utils::log_error(
+            "running scheduled event",
+            run_scheduled_event(
+                executor.clone(),
+                rule.guild_id(),
+                rule.rule_id(),
+                rule.event().clone(),
+                count,
+            ),
+        ));
+    }
+    Ok(())
+}
+
+async fn run_scheduled_event(
+    executor: ActionExecutor,
+    guild_id: Id<GuildMarker>,
+    rule_id: u64,
+    event: ScheduledEvent,
+    count: usize,
+) -> Result<()> {
+    let targets: Vec<Option<Id<UserMarker>>> = if event.get_target_role_ids().is_empty() {
+        vec![None]
+    } else {
+        let role_ids = event
+            .get_target_role_ids()
+            .iter()
+            .filter_map(|id| Id::new_checked(*id));
+        Member::find_with_roles(guild_id, role_ids)
+            .fetch_all(executor.storage().sql())
+            .await?
+            .iter()
+            .map(|member| Some(member.user_id()))
+            .collect()
+    };
+
+    for _ in 0..count {
+        for user_id in targets.iter().copied() {
+            let actions = event
+                .get_action()
+                .iter()
+                .map(|action| {
+                    let mut action = action.clone();
+                    action.set_guild_id(guild_id.get());
+                    if let Some(user_id) = user_id {
+                        action.set_user_id(user_id.get());
+                    }
+                    action
+                })
+                .collect();
+            let mut trigger = AutoConfigTrigger::new();
+            trigger.set_rule_id(rule_id);
+            trigger.set_trigger("scheduled_events".to_owned());
+            let mut source = EventSource::new();
+            source.set_auto_config(trigger);
+            let ctx = TemplateContext {
+                user_id,
+                ..Default::default()
+            };
+            executor
+                .execute_event(guild_id, source, actions, &ctx)
+                .await;
+        }
+    }
 
     tracing::info!(
-        "Ran automatic deescalation for user {} in guild {}",
-        pending.guild_id(),
-        pending.user_id(),
+        "Ran scheduled event {:x} in guild {} for {} targets",
+        rule_id,
+        guild_id,
+        targets.len()
     );
-
     Ok(())
 }
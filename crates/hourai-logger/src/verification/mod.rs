@@ -105,7 +105,10 @@ pub async fn on_member_join(
                 .title("⚠️ User Verification Required")
                 .description(desc)
                 .color(0xED4245)
-                .footer(EmbedFooterBuilder::new(format!("{:x}", user.id.get())))
+                .footer(EmbedFooterBuilder::new(format!("{:x}", user.id.get())));
+            // Moderators decide from this message, so show them the user's notes too.
+            let embed = hourai_sql::whois::notes(client.storage().sql(), embed, guild_id, user.id)
+                .await?
                 .build();
 
             let embeds = [embed];
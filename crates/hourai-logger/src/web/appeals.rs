@@ -0,0 +1,89 @@
+use super::{AppState, prelude::*};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::get,
+};
+use hourai_sql::{Appeal, AppealStatus};
+use hourai_storage::{
+    Storage,
+    appeals::{self, AppealError},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Serialize)]
+struct AppealInfo {
+    guild_id: String,
+    status: &'static str,
+    created_at: i64,
+    submitted_at: Option<i64>,
+    resolved_at: Option<i64>,
+    content: Option<String>,
+}
+
+impl From<Appeal> for AppealInfo {
+    fn from(appeal: Appeal) -> Self {
+        Self {
+            guild_id: appeal.guild_id.to_string(),
+            // Appeals being accepted are still under review as far as the user is concerned.
+            status: match appeal.status {
+                AppealStatus::Open => "open",
+                AppealStatus::Submitted | AppealStatus::Accepting => "submitted",
+                AppealStatus::Accepted => "accepted",
+                AppealStatus::Denied => "denied",
+            },
+            created_at: appeal.created_at.timestamp(),
+            submitted_at: appeal.submitted_at.map(|time| time.timestamp()),
+            resolved_at: appeal
+                .resolved_at
+                .filter(|_| appeal.status != AppealStatus::Accepting)
+                .map(|time| time.timestamp()),
+            content: appeal.content,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AppealSubmission {
+    content: String,
+}
+
+async fn fetch_appeal(data: &AppState, token: &str) -> Result<Appeal> {
+    Appeal::fetch_by_token(token)
+        .fetch_optional(&data.sql)
+        .await
+        .http_internal_error("Failed to fetch the appeal")?
+        .http_error(StatusCode::NOT_FOUND, AppealError::NotFound)
+}
+
+async fn get_appeal(
+    State(data): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Result<Json<AppealInfo>> {
+    Ok(Json(fetch_appeal(&data, &token).await?.into()))
+}
+
+async fn submit_appeal(
+    State(data): State<Arc<AppState>>,
+    Path(token): Path<String>,
+    Json(submission): Json<AppealSubmission>,
+) -> Result<Json<AppealInfo>> {
+    let appeal = fetch_appeal(&data, &token).await?;
+    let storage = Storage::new(data.sql.clone(), data.redis.clone());
+    match appeals::submit(&data.discord, &storage, appeal.id, &submission.content).await {
+        Ok(appeal) => Ok(Json(appeal.into())),
+        Err(err) => match err.downcast_ref::<AppealError>() {
+            Some(err @ (AppealError::AlreadySubmitted | AppealError::NotEnabled)) => {
+                http_error(StatusCode::CONFLICT, err)
+            }
+            Some(err) => http_error(StatusCode::BAD_REQUEST, err),
+            None => Err(err).http_internal_error("Failed to submit the appeal"),
+        },
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/:token", get(get_appeal).post(submit_appeal))
+}
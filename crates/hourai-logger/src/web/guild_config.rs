@@ -3,12 +3,24 @@ use axum::{
     Json, Router,
     extract::{Path, State},
     http::StatusCode,
-    routing::get,
+    routing::{get, post},
+};
+use hourai::{
+    models::id::Id,
+    proto::auto_config::*,
+    proto::guild_configs::*,
+    util::validation::{self, InvalidConfig, Validate},
 };
-use hourai::{models::id::Id, proto::auto_config::*, proto::guild_configs::*};
 use hourai_redis::CachedGuildConfig;
+use serde::Serialize;
 use std::sync::Arc;
 
+#[derive(Serialize)]
+struct FieldError {
+    path: String,
+    message: String,
+}
+
 async fn get_config<T>(
     State(data): State<Arc<AppState>>,
     Path(guild_id): Path<u64>,
@@ -26,6 +38,32 @@ where
     Ok(Json(proto))
 }
 
+/// Checks a config without saving it. Responds with every problem found, keyed by the path of
+/// the field it was found in. Valid configs get an empty list.
+async fn validate_config<T>(
+    State(data): State<Arc<AppState>>,
+    Path(guild_id): Path<u64>,
+    Json(config): Json<T>,
+) -> Result<Json<Vec<FieldError>>>
+where
+    T: Validate + serde::de::DeserializeOwned,
+{
+    let resources = hourai_storage::guild_resources(Id::new(guild_id), &data.redis)
+        .await
+        .http_internal_error("Failed to fetch the guild's channels and roles")?;
+    let errors = match validation::validate(&config, resources.as_ref()) {
+        Ok(()) => Vec::new(),
+        Err(InvalidConfig(errors)) => errors
+            .into_iter()
+            .map(|err| FieldError {
+                path: err.path,
+                message: err.message,
+            })
+            .collect(),
+    };
+    Ok(Json(errors))
+}
+
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/:guild_id/announce", get(get_config::<AnnouncementConfig>))
@@ -38,4 +76,32 @@ pub fn router() -> Router<Arc<AppState>> {
             "/:guild_id/validation",
             get(get_config::<VerificationConfig>),
         )
+        .route(
+            "/:guild_id/announce/validate",
+            post(validate_config::<AnnouncementConfig>),
+        )
+        .route(
+            "/:guild_id/auto/validate",
+            post(validate_config::<AutoConfig>),
+        )
+        .route(
+            "/:guild_id/moderation/validate",
+            post(validate_config::<ModerationConfig>),
+        )
+        .route(
+            "/:guild_id/music/validate",
+            post(validate_config::<MusicConfig>),
+        )
+        .route(
+            "/:guild_id/logging/validate",
+            post(validate_config::<LoggingConfig>),
+        )
+        .route(
+            "/:guild_id/roles/validate",
+            post(validate_config::<RoleConfig>),
+        )
+        .route(
+            "/:guild_id/validation/validate",
+            post(validate_config::<VerificationConfig>),
+        )
 }
@@ -1,3 +1,4 @@
+mod appeals;
 mod guild_config;
 mod oauth;
 pub mod prelude;
@@ -15,6 +16,7 @@ use tower_http::trace::TraceLayer;
 pub(crate) struct AppState {
     pub config: HouraiConfig,
     pub http: reqwest::Client,
+    pub discord: Arc<hourai::http::Client>,
     pub sql: SqlPool,
     pub redis: RedisClient,
 }
@@ -25,6 +27,7 @@ pub fn app(state: Arc<AppState>) -> Router {
             "/v1",
             Router::new()
                 .route("/bot/status", get(status::bot_status))
+                .nest("/appeals", appeals::router())
                 .nest("/guilds", guild_config::router()),
         )
         .nest("/oauth", oauth::router())
@@ -36,13 +39,19 @@ pub fn app(state: Arc<AppState>) -> Router {
         .layer(TraceLayer::new_for_http())
 }
 
-pub async fn run_server(config: HouraiConfig, sql: SqlPool, redis: RedisClient) -> Result<()> {
+pub async fn run_server(
+    config: HouraiConfig,
+    discord: Arc<hourai::http::Client>,
+    sql: SqlPool,
+    redis: RedisClient,
+) -> Result<()> {
     let port = config.web.port;
     tracing::info!("Starting Axum web server on port {}", port);
 
     let state = Arc::new(AppState {
         config,
         http: reqwest::Client::new(),
+        discord,
         sql,
         redis,
     });
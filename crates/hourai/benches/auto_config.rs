@@ -0,0 +1,104 @@
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use hourai::{
+    models::id::Id,
+    proto::{auto_config::*, util::FilterSettings},
+    util::{auto_config::CompiledAutoConfig, condition::ConditionContext},
+};
+use regex::Regex;
+
+const PATTERNS_PER_EVENT: usize = 10;
+const CHANNEL_GROUPS: u64 = 200;
+
+const MESSAGE: &str = "Hey everyone, check out this cool new server I found: discord.gg/abcdef. \
+                       It has a lot of channels about games, art, and music!";
+
+fn message_event(seed: usize) -> MessageEvent {
+    let mut filter = FilterSettings::new();
+    for idx in 0..PATTERNS_PER_EVENT {
+        filter
+            .mut_blacklist()
+            .push(format!(r"(?i)\bbanned{}_{}\w*\b", seed, idx));
+    }
+    filter.mut_whitelist().push(format!(r"(?i)allowed{}", seed));
+    let mut event = MessageEvent::new();
+    event.set_field_type(MessageEvent_Type::ALL_MESSAGES);
+    event.set_content_filter(filter);
+    event
+}
+
+fn event_group(seed: usize, events: usize) -> EventGroup {
+    let mut group = EventGroup::new();
+    for idx in 0..events {
+        group
+            .mut_on_message()
+            .push(message_event(seed * 1000 + idx));
+    }
+    group
+}
+
+/// Builds a config resembling a large guild: many guild-wide events and per-channel groups.
+fn large_config(events: usize) -> AutoConfig {
+    let mut config = AutoConfig::new();
+    config.set_guild_events(event_group(0, events));
+    for channel in 1..=CHANNEL_GROUPS {
+        config
+            .mut_channel_events()
+            .insert(channel.to_string(), event_group(channel as usize, 5));
+    }
+    config
+}
+
+/// The per-event cost before configs were compiled: every pattern is built for every message.
+fn uncompiled_message_events(config: &AutoConfig, channel: u64, content: &str) -> usize {
+    let meets = |patterns: &[String]| {
+        patterns
+            .iter()
+            .any(|p| Regex::new(p).map(|r| r.is_match(content)).unwrap_or(false))
+    };
+    let channel_group = config.get_channel_events().get(&channel.to_string());
+    channel_group
+        .into_iter()
+        .chain(config.guild_events.as_ref())
+        .flat_map(|group| group.get_on_message())
+        .filter(|evt| {
+            let filter = evt.get_content_filter();
+            !meets(filter.get_blacklist()) || meets(filter.get_whitelist())
+        })
+        .count()
+}
+
+fn bench_message_events(c: &mut Criterion) {
+    let mut group = c.benchmark_group("message_events");
+    for events in [10, 100] {
+        let config = large_config(events);
+        let compiled = CompiledAutoConfig::new(&config);
+        let ancestry = [Id::new(CHANNEL_GROUPS / 2)];
+        let ctx = ConditionContext {
+            ancestry: &ancestry,
+            content: Some(MESSAGE),
+            ..Default::default()
+        };
+
+        group.bench_with_input(BenchmarkId::new("compiled", events), &events, |b, _| {
+            b.iter(|| {
+                compiled
+                    .message_events(black_box(&ancestry), black_box(MESSAGE), false, &ctx)
+                    .len()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("uncompiled", events), &events, |b, _| {
+            b.iter(|| uncompiled_message_events(&config, CHANNEL_GROUPS / 2, black_box(MESSAGE)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_compile(c: &mut Criterion) {
+    let config = large_config(100);
+    c.bench_function("compile_large_config", |b| {
+        b.iter(|| CompiledAutoConfig::new(black_box(&config)))
+    });
+}
+
+criterion_group!(benches, bench_message_events, bench_compile);
+criterion_main!(benches);
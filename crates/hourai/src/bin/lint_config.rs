@@ -0,0 +1,51 @@
+//! Checks guild configs for problems without connecting to Discord or any database.
+//!
+//! Usage: `lint_config <config.json>...`
+//!
+//! Each file must contain a JSON encoded `GuildConfig`, in the same format served by the web
+//! API. References to channels and roles cannot be checked offline and are skipped.
+use hourai::{
+    proto::guild_configs::GuildConfig,
+    util::validation::{self, InvalidConfig},
+};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: lint_config <config.json>...");
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = false;
+    for path in paths.iter() {
+        let mut contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                eprintln!("{}: failed to read file: {}", path, err);
+                failed = true;
+                continue;
+            }
+        };
+        let config: GuildConfig = match simd_json::from_slice(&mut contents) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("{}: failed to parse config: {}", path, err);
+                failed = true;
+                continue;
+            }
+        };
+        if let Err(InvalidConfig(errors)) = validation::validate(&config, None) {
+            for error in errors {
+                eprintln!("{}: {}", path, error);
+            }
+            failed = true;
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -9,7 +9,11 @@ pub use self::{
 };
 
 use dashmap::{DashMap, DashSet};
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use twilight_model::{
     gateway::presence::{Presence, Status, UserOrId},
     guild::Member,
@@ -17,6 +21,7 @@ use twilight_model::{
         Id,
         marker::{GuildMarker, UserMarker},
     },
+    util::Timestamp,
 };
 
 // When adding a field here, be sure to add it to `InMemoryCache::clear` if
@@ -28,6 +33,7 @@ struct InMemoryCacheRef {
     guild_presences: DashMap<Id<GuildMarker>, HashSet<Id<UserMarker>>>,
     unavailable_guilds: DashSet<Id<GuildMarker>>,
     pending_members: DashSet<(Id<GuildMarker>, Id<UserMarker>)>,
+    timed_out_members: DashMap<(Id<GuildMarker>, Id<UserMarker>), Timestamp>,
 }
 
 /// A thread-safe, in-memory-process cache of Discord data. It can be cloned and
@@ -105,6 +111,16 @@ impl InMemoryCache {
         self.0.pending_members.contains(&(guild_id, user_id))
     }
 
+    /// Checks if a member is currently timed out in a specific guild, going by the timeout seen
+    /// in the last update for them. Timeouts that have since expired are not counted.
+    /// This runs O(1) time.
+    pub fn is_timed_out(&self, guild_id: Id<GuildMarker>, user_id: Id<UserMarker>) -> bool {
+        self.0
+            .timed_out_members
+            .get(&(guild_id, user_id))
+            .is_some_and(|until| is_timed_out(Some(*until)))
+    }
+
     /// Gets all of the IDs of the guilds in the cache.
     ///
     /// This is an O(n) operation. This requires the [`GUILDS`] intent.
@@ -157,6 +173,7 @@ impl InMemoryCache {
         self.0.guild_presences.clear();
         self.0.unavailable_guilds.clear();
         self.0.pending_members.clear();
+        self.0.timed_out_members.clear();
     }
 
     pub fn cache_guild_create(
@@ -188,6 +205,19 @@ impl InMemoryCache {
         } else {
             self.0.pending_members.remove(&id);
         }
+        self.cache_timeout(id, member.communication_disabled_until);
+    }
+
+    pub(crate) fn cache_timeout(
+        &self,
+        id: (Id<GuildMarker>, Id<UserMarker>),
+        until: Option<Timestamp>,
+    ) {
+        if let Some(until) = until.filter(|until| is_timed_out(Some(*until))) {
+            self.0.timed_out_members.insert(id, until);
+        } else {
+            self.0.timed_out_members.remove(&id);
+        }
     }
 
     pub(crate) fn cache_members<'a>(
@@ -246,6 +276,15 @@ impl InMemoryCache {
     }
 }
 
+/// Checks if a `communication_disabled_until` timestamp represents an active timeout.
+pub fn is_timed_out(until: Option<Timestamp>) -> bool {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as i64)
+        .unwrap_or(0);
+    until.map(|ts| ts.as_micros() > now).unwrap_or(false)
+}
+
 pub fn presence_user_id(presence: &Presence) -> Id<UserMarker> {
     match presence.user {
         UserOrId::User(ref u) => u.id,
@@ -44,6 +44,7 @@ impl UpdateCache for GuildDelete {
 
         if cache.wants(ResourceType::MEMBER) {
             cache.0.pending_members.retain(|kv| kv.0 != id);
+            cache.0.timed_out_members.retain(|kv, _| kv.0 != id);
         }
 
         if cache.wants(ResourceType::PRESENCE) {
@@ -82,10 +83,9 @@ impl UpdateCache for MemberRemove {
             return;
         }
 
-        cache
-            .0
-            .pending_members
-            .remove(&(self.guild_id, self.user.id));
+        let id = (self.guild_id, self.user.id);
+        cache.0.pending_members.remove(&id);
+        cache.0.timed_out_members.remove(&id);
     }
 }
 
@@ -101,6 +101,7 @@ impl UpdateCache for MemberUpdate {
         } else {
             cache.0.pending_members.remove(&id);
         }
+        cache.cache_timeout(id, self.communication_disabled_until);
     }
 }
 
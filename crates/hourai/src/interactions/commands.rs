@@ -47,7 +47,7 @@ impl CommandContext {
         }
     }
 
-    #[expect(clippy::expect_used)]
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
     fn data(&self) -> &CommandData {
         match &self.command.data {
             Some(InteractionData::ApplicationCommand(data)) => data,
@@ -241,13 +241,13 @@ impl<'a> Iterator for FlattenedOptions<'a> {
 
             let opt = &slice[self.indices[self.depth]];
             match &opt.value {
-                CommandOptionValue::SubCommand(sub) | CommandOptionValue::SubCommandGroup(sub) => {
-                    if self.depth + 1 < self.stack.len() {
-                        self.depth += 1;
-                        self.stack[self.depth] = Some(sub.as_slice());
-                        self.indices[self.depth] = 0;
-                        continue;
-                    }
+                CommandOptionValue::SubCommand(sub) | CommandOptionValue::SubCommandGroup(sub)
+                    if self.depth + 1 < self.stack.len() =>
+                {
+                    self.depth += 1;
+                    self.stack[self.depth] = Some(sub.as_slice());
+                    self.indices[self.depth] = 0;
+                    continue;
                 }
                 _ => {}
             }
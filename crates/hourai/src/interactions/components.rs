@@ -58,7 +58,7 @@ impl ComponentContext {
         }
     }
 
-    #[expect(clippy::expect_used)]
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
     fn data(&self) -> &MessageComponentInteractionData {
         match &self.component.data {
             Some(InteractionData::MessageComponent(data)) => data,
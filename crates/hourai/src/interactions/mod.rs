@@ -1,8 +1,10 @@
 mod commands;
 mod components;
+mod modals;
 
 pub use commands::*;
 pub use components::*;
+pub use modals::*;
 
 use crate::{
     http,
@@ -64,6 +66,15 @@ impl Response {
         Self::direct().flag(MessageFlags::EPHEMERAL)
     }
 
+    /// Creates a modal form. The inputs are added as components, and the submission is sent
+    /// with the given custom ID.
+    pub fn modal(custom_id: impl Into<String>, title: impl Into<String>) -> Self {
+        let mut response = Self::direct();
+        response.0.custom_id = Some(custom_id.into());
+        response.0.title = Some(title.into());
+        response
+    }
+
     pub fn content(mut self, content: impl Into<String>) -> Self {
         self.0.content = Some(content.into());
         self
@@ -0,0 +1,108 @@
+use crate::{
+    http,
+    interactions::{InteractionContext, InteractionError, InteractionResult, parse_custom_id},
+    models::{
+        application::interaction::{Interaction, InteractionData, modal::ModalInteractionData},
+        guild::PartialMember,
+        id::{
+            Id,
+            marker::{ApplicationMarker, ChannelMarker, GuildMarker, InteractionMarker},
+        },
+        user::User,
+    },
+    proto::message_components::MessageComponentProto,
+};
+use anyhow::Result;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ModalContext {
+    pub http: Arc<http::Client>,
+    pub modal: Interaction,
+    marker_: PhantomData<()>,
+}
+
+impl ModalContext {
+    pub fn new(client: Arc<http::Client>, interaction: Interaction) -> Self {
+        assert!(matches!(
+            interaction.data,
+            Some(InteractionData::ModalSubmit(_))
+        ));
+        Self {
+            http: client,
+            modal: interaction,
+            marker_: PhantomData,
+        }
+    }
+
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
+    fn data(&self) -> &ModalInteractionData {
+        match &self.modal.data {
+            Some(InteractionData::ModalSubmit(data)) => data,
+            _ => Option::<&ModalInteractionData>::None
+                .expect("Provided interaction data is not a modal submission"),
+        }
+    }
+
+    pub fn metadata(&self) -> Result<MessageComponentProto> {
+        parse_custom_id(&self.data().custom_id)
+    }
+
+    /// Gets the value submitted for a text input in the modal.
+    pub fn text_value(&self, custom_id: &str) -> Option<&str> {
+        self.data()
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find(|component| component.custom_id == custom_id)
+            .and_then(|component| component.value.as_deref())
+    }
+}
+
+impl InteractionContext for ModalContext {
+    fn http(&self) -> &Arc<http::Client> {
+        &self.http
+    }
+
+    fn id(&self) -> Id<InteractionMarker> {
+        self.modal.id
+    }
+
+    fn application_id(&self) -> Id<ApplicationMarker> {
+        self.modal.application_id
+    }
+
+    fn token(&self) -> &str {
+        &self.modal.token
+    }
+
+    fn guild_id(&self) -> InteractionResult<Id<GuildMarker>> {
+        self.modal.guild_id.ok_or(InteractionError::NotInGuild)
+    }
+
+    #[expect(clippy::expect_used)]
+    fn channel_id(&self) -> Id<ChannelMarker> {
+        self.modal
+            .channel
+            .as_ref()
+            .map(|c| c.id)
+            .expect("Modal interaction is missing channel")
+    }
+
+    fn member(&self) -> Option<&PartialMember> {
+        self.modal.member.as_ref()
+    }
+
+    #[expect(clippy::expect_used)]
+    fn user(&self) -> &User {
+        let member = self
+            .modal
+            .member
+            .as_ref()
+            .and_then(|member| member.user.as_ref());
+        let user = self.modal.user.as_ref();
+        user.or(member)
+            .expect("Interaction has neither user nor member")
+    }
+}
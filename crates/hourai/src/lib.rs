@@ -6,12 +6,30 @@ pub mod models;
 pub mod prelude;
 pub mod util;
 
-// Include the auto-generated protos as a module
-#[allow(unused_parens, elided_lifetimes_in_paths, renamed_and_removed_lints)]
 pub mod proto {
-    include!(concat!(env!("OUT_DIR"), "/proto/mod.rs"));
-    use self::cache::{CachedRoleProto, CachedUserProto};
-    use crate::models::user::{User, UserLike};
+    // Include the auto-generated protos as a module. The allowed lints only apply to them.
+    #[allow(
+        unused_parens,
+        elided_lifetimes_in_paths,
+        mismatched_lifetime_syntaxes,
+        renamed_and_removed_lints,
+        clippy::panic,
+        clippy::unwrap_used,
+        clippy::expect_used
+    )]
+    mod generated {
+        include!(concat!(env!("OUT_DIR"), "/proto/mod.rs"));
+    }
+
+    pub use self::generated::*;
+    use self::{
+        cache::{CachedRoleProto, CachedUserProto},
+        event::DiscordMessage,
+    };
+    use crate::models::{
+        MessageLike, Snowflake,
+        user::{User, UserLike},
+    };
     use std::cmp::{Ord, Ordering, PartialOrd};
 
     impl Eq for CachedRoleProto {}
@@ -44,6 +62,17 @@ pub mod proto {
             proto
         }
     }
+
+    impl DiscordMessage {
+        pub fn from_message(msg: &impl MessageLike) -> Self {
+            let mut proto = Self::new();
+            proto.set_channel_id(msg.channel_id().get());
+            proto.set_user_id(msg.author().id().get());
+            proto.set_content(msg.content().to_owned());
+            proto.set_timestamp(msg.created_at().timestamp_millis() as u64);
+            proto
+        }
+    }
 }
 
 pub use twilight_gateway as gateway;
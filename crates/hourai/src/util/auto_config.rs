@@ -0,0 +1,611 @@
+use crate::{
+    models::id::{
+        Id,
+        marker::{ChannelMarker, RoleMarker},
+    },
+    proto::auto_config::*,
+    util::{
+        condition::{CompiledCondition, ConditionContext, meets_condition},
+        cron::{InvalidSchedule, Schedule},
+        filter::{CompiledFilter, InvalidPattern, meets_filter},
+        stable_hash,
+    },
+};
+use std::collections::{HashMap, HashSet};
+
+/// The parts of an event shared by every trigger.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    /// Identifies the rule. Derived from the position and contents of the event, so identical
+    /// events in different places get different IDs, and the ID changes whenever the event is
+    /// edited or moved.
+    pub id: u64,
+    /// The cooldown of the rule, if it has a non-zero one.
+    pub cooldown: Option<Cooldown>,
+}
+
+impl Rule {
+    /// Creates the rule for an event. `path` is the event's position in the config, such as
+    /// `guild.on_join[0]`.
+    fn new(path: &str, event: &impl protobuf::Message, cooldown: Option<&Cooldown>) -> Self {
+        let mut bytes = path.as_bytes().to_vec();
+        bytes.push(0);
+        bytes.extend(event.write_to_bytes().unwrap_or_default());
+        Self {
+            id: stable_hash(&bytes),
+            cooldown: cooldown
+                .filter(|cooldown| cooldown.get_seconds() > 0)
+                .cloned(),
+        }
+    }
+}
+
+fn compile_filter(
+    filter: Option<&crate::proto::util::FilterSettings>,
+    errors: &mut Vec<InvalidPattern>,
+) -> Option<CompiledFilter> {
+    filter.map(|filter| CompiledFilter::new(filter, errors))
+}
+
+fn compile_condition(
+    condition: Option<&Condition>,
+    errors: &mut Vec<InvalidPattern>,
+) -> Option<CompiledCondition> {
+    condition.map(|condition| CompiledCondition::new(condition, errors))
+}
+
+/// A `MessageEvent` with its filters pre-compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledMessageEvent {
+    pub event: MessageEvent,
+    pub rule: Rule,
+    content_filter: Option<CompiledFilter>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledMessageEvent {
+    fn new(event: &MessageEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            content_filter: compile_filter(event.content_filter.as_ref(), errors),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    pub fn matches(&self, content: &str, is_edit: bool, ctx: &ConditionContext) -> bool {
+        let event_mask = if is_edit {
+            MessageEvent_Type::MESSAGE_EDITS
+        } else {
+            MessageEvent_Type::MESSAGE_CREATES
+        };
+        let evt_type = self.event.get_field_type();
+        (evt_type == MessageEvent_Type::ALL_MESSAGES || evt_type == event_mask)
+            && meets_filter(Some(content), self.content_filter.as_ref())
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `UserChangeEvent` with its filters pre-compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledUserChangeEvent {
+    pub event: UserChangeEvent,
+    pub rule: Rule,
+    username_filter: Option<CompiledFilter>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledUserChangeEvent {
+    fn new(event: &UserChangeEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            username_filter: compile_filter(event.username_filter.as_ref(), errors),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    pub fn matches(&self, username: &str, ctx: &ConditionContext) -> bool {
+        meets_filter(Some(username), self.username_filter.as_ref())
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `MessageDeleteEvent` with its filters pre-compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledMessageDeleteEvent {
+    pub event: MessageDeleteEvent,
+    pub rule: Rule,
+    content_filter: Option<CompiledFilter>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledMessageDeleteEvent {
+    fn new(event: &MessageDeleteEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            content_filter: compile_filter(event.content_filter.as_ref(), errors),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    pub fn matches(&self, content: &str, ctx: &ConditionContext) -> bool {
+        meets_filter(Some(content), self.content_filter.as_ref())
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `ReactionEvent` with its filters pre-compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledReactionEvent {
+    pub event: ReactionEvent,
+    pub rule: Rule,
+    emoji_filter: Option<CompiledFilter>,
+    username_filter: Option<CompiledFilter>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledReactionEvent {
+    fn new(event: &ReactionEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            emoji_filter: compile_filter(event.emoji_filter.as_ref(), errors),
+            username_filter: compile_filter(event.username_filter.as_ref(), errors),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    /// Checks if a reaction matches the event. `username` is `None` if the reacting user is
+    /// not known, which only passes if there is no username filter.
+    pub fn matches(&self, emoji: &str, username: Option<&str>, ctx: &ConditionContext) -> bool {
+        meets_filter(Some(emoji), self.emoji_filter.as_ref())
+            && (self.username_filter.is_none()
+                || meets_filter(username, self.username_filter.as_ref()))
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `VoiceEvent` with its filters pre-compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledVoiceEvent {
+    pub event: VoiceEvent,
+    pub rule: Rule,
+    username_filter: Option<CompiledFilter>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledVoiceEvent {
+    fn new(event: &VoiceEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            username_filter: compile_filter(event.username_filter.as_ref(), errors),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    pub fn matches(&self, username: &str, ctx: &ConditionContext) -> bool {
+        meets_filter(Some(username), self.username_filter.as_ref())
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `NicknameChangeEvent` with its filters pre-compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledNicknameChangeEvent {
+    pub event: NicknameChangeEvent,
+    pub rule: Rule,
+    nickname_filter: Option<CompiledFilter>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledNicknameChangeEvent {
+    fn new(event: &NicknameChangeEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            nickname_filter: compile_filter(event.nickname_filter.as_ref(), errors),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    /// Checks if a nickname change matches the event. Cleared nicknames are matched as an empty
+    /// string.
+    pub fn matches(&self, nickname: Option<&str>, ctx: &ConditionContext) -> bool {
+        meets_filter(Some(nickname.unwrap_or("")), self.nickname_filter.as_ref())
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `RoleChangeEvent` with its role IDs pre-parsed.
+#[derive(Clone, Debug)]
+pub struct CompiledRoleChangeEvent {
+    pub event: RoleChangeEvent,
+    pub rule: Rule,
+    role_ids: HashSet<Id<RoleMarker>>,
+    condition: Option<CompiledCondition>,
+}
+
+impl CompiledRoleChangeEvent {
+    fn new(event: &RoleChangeEvent, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, event.cooldown.as_ref()),
+            role_ids: event
+                .get_role_ids()
+                .iter()
+                .filter_map(|id| Id::new_checked(*id))
+                .collect(),
+            condition: compile_condition(event.condition.as_ref(), errors),
+        }
+    }
+
+    /// Checks if any of the changed roles match the event.
+    pub fn matches(&self, changed: &[Id<RoleMarker>], ctx: &ConditionContext) -> bool {
+        !changed.is_empty()
+            && (self.role_ids.is_empty() || changed.iter().any(|id| self.role_ids.contains(id)))
+            && meets_condition(self.condition.as_ref(), ctx)
+    }
+}
+
+/// A `ScheduledEvent` with its schedule pre-parsed.
+#[derive(Clone, Debug)]
+pub struct CompiledScheduledEvent {
+    pub event: ScheduledEvent,
+    pub rule: Rule,
+    pub schedule: Result<Schedule, InvalidSchedule>,
+}
+
+impl CompiledScheduledEvent {
+    fn new(event: &ScheduledEvent, path: &str) -> Self {
+        Self {
+            event: event.clone(),
+            rule: Rule::new(path, event, None),
+            schedule: event.get_schedule().parse(),
+        }
+    }
+}
+
+/// An `EventGroup` with all of its events pre-compiled.
+#[derive(Clone, Debug, Default)]
+pub struct CompiledEventGroup {
+    pub on_message: Vec<CompiledMessageEvent>,
+    pub on_join: Vec<CompiledUserChangeEvent>,
+    pub on_leave: Vec<CompiledUserChangeEvent>,
+    pub on_ban: Vec<CompiledUserChangeEvent>,
+    pub on_verify: Vec<CompiledUserChangeEvent>,
+    pub on_message_delete: Vec<CompiledMessageDeleteEvent>,
+    pub on_reaction_add: Vec<CompiledReactionEvent>,
+    pub on_voice_join: Vec<CompiledVoiceEvent>,
+    pub on_voice_leave: Vec<CompiledVoiceEvent>,
+    pub on_nickname_change: Vec<CompiledNicknameChangeEvent>,
+    pub on_role_gained: Vec<CompiledRoleChangeEvent>,
+    pub on_role_lost: Vec<CompiledRoleChangeEvent>,
+    pub on_timeout: Vec<CompiledUserChangeEvent>,
+    pub exclusive: bool,
+}
+
+impl CompiledEventGroup {
+    /// Compiles an event group. `path` is the group's position in the config, which prefixes
+    /// the paths of its events.
+    fn new(group: &EventGroup, path: &str, errors: &mut Vec<InvalidPattern>) -> Self {
+        fn compile<E, C>(
+            events: &[E],
+            path: &str,
+            field: &str,
+            errors: &mut Vec<InvalidPattern>,
+            new: fn(&E, &str, &mut Vec<InvalidPattern>) -> C,
+        ) -> Vec<C> {
+            events
+                .iter()
+                .enumerate()
+                .map(|(index, evt)| new(evt, &format!("{}.{}[{}]", path, field, index), errors))
+                .collect()
+        }
+        Self {
+            on_message: compile(
+                group.get_on_message(),
+                path,
+                "on_message",
+                errors,
+                CompiledMessageEvent::new,
+            ),
+            on_join: compile(
+                group.get_on_join(),
+                path,
+                "on_join",
+                errors,
+                CompiledUserChangeEvent::new,
+            ),
+            on_leave: compile(
+                group.get_on_leave(),
+                path,
+                "on_leave",
+                errors,
+                CompiledUserChangeEvent::new,
+            ),
+            on_ban: compile(
+                group.get_on_ban(),
+                path,
+                "on_ban",
+                errors,
+                CompiledUserChangeEvent::new,
+            ),
+            on_verify: compile(
+                group.get_on_verify(),
+                path,
+                "on_verify",
+                errors,
+                CompiledUserChangeEvent::new,
+            ),
+            on_message_delete: compile(
+                group.get_on_message_delete(),
+                path,
+                "on_message_delete",
+                errors,
+                CompiledMessageDeleteEvent::new,
+            ),
+            on_reaction_add: compile(
+                group.get_on_reaction_add(),
+                path,
+                "on_reaction_add",
+                errors,
+                CompiledReactionEvent::new,
+            ),
+            on_voice_join: compile(
+                group.get_on_voice_join(),
+                path,
+                "on_voice_join",
+                errors,
+                CompiledVoiceEvent::new,
+            ),
+            on_voice_leave: compile(
+                group.get_on_voice_leave(),
+                path,
+                "on_voice_leave",
+                errors,
+                CompiledVoiceEvent::new,
+            ),
+            on_nickname_change: compile(
+                group.get_on_nickname_change(),
+                path,
+                "on_nickname_change",
+                errors,
+                CompiledNicknameChangeEvent::new,
+            ),
+            on_role_gained: compile(
+                group.get_on_role_gained(),
+                path,
+                "on_role_gained",
+                errors,
+                CompiledRoleChangeEvent::new,
+            ),
+            on_role_lost: compile(
+                group.get_on_role_lost(),
+                path,
+                "on_role_lost",
+                errors,
+                CompiledRoleChangeEvent::new,
+            ),
+            on_timeout: compile(
+                group.get_on_timeout(),
+                path,
+                "on_timeout",
+                errors,
+                CompiledUserChangeEvent::new,
+            ),
+            exclusive: group.get_exclusive(),
+        }
+    }
+
+    /// Gets all of the conditions used by the events in the group.
+    fn conditions(&self) -> impl Iterator<Item = &CompiledCondition> {
+        let on_message = self.on_message.iter().filter_map(|e| e.condition.as_ref());
+        let user_events = [
+            &self.on_join,
+            &self.on_leave,
+            &self.on_ban,
+            &self.on_verify,
+            &self.on_timeout,
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.condition.as_ref());
+        let voice_events = [&self.on_voice_join, &self.on_voice_leave]
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.condition.as_ref());
+        let role_events = [&self.on_role_gained, &self.on_role_lost]
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.condition.as_ref());
+        on_message
+            .chain(user_events)
+            .chain(voice_events)
+            .chain(role_events)
+            .chain(
+                self.on_message_delete
+                    .iter()
+                    .filter_map(|e| e.condition.as_ref()),
+            )
+            .chain(
+                self.on_reaction_add
+                    .iter()
+                    .filter_map(|e| e.condition.as_ref()),
+            )
+            .chain(
+                self.on_nickname_change
+                    .iter()
+                    .filter_map(|e| e.condition.as_ref()),
+            )
+    }
+}
+
+/// A pre-compiled `AutoConfig`. Compiling a config builds all of the regexes used by it ahead
+/// of time, so evaluating an event does not need to build any.
+#[derive(Clone, Debug, Default)]
+pub struct CompiledAutoConfig {
+    guild_events: Option<CompiledEventGroup>,
+    channel_events: HashMap<Id<ChannelMarker>, CompiledEventGroup>,
+    errors: Vec<InvalidPattern>,
+    needs_member: bool,
+    needs_ancestry: bool,
+    max_firings_per_minute: u32,
+    scheduled_events: Vec<CompiledScheduledEvent>,
+}
+
+impl CompiledAutoConfig {
+    pub fn new(config: &AutoConfig) -> Self {
+        let mut errors = Vec::new();
+        let guild_events = config
+            .guild_events
+            .as_ref()
+            .map(|group| CompiledEventGroup::new(group, "guild", &mut errors));
+        let channel_events = config
+            .get_channel_events()
+            .iter()
+            .filter_map(|(id, group)| Some((id.parse().ok()?, group)))
+            .map(|(id, group)| {
+                let path = format!("channel.{}", id);
+                (id, CompiledEventGroup::new(group, &path, &mut errors))
+            })
+            .collect();
+        let mut config = Self {
+            guild_events,
+            channel_events,
+            errors,
+            needs_member: false,
+            needs_ancestry: false,
+            max_firings_per_minute: config.get_max_firings_per_minute(),
+            scheduled_events: config
+                .get_scheduled_events()
+                .iter()
+                .enumerate()
+                .map(|(index, evt)| {
+                    CompiledScheduledEvent::new(evt, &format!("scheduled_events[{}]", index))
+                })
+                .collect(),
+        };
+        let conditions: Vec<&CompiledCondition> = config
+            .guild_events
+            .iter()
+            .chain(config.channel_events.values())
+            .flat_map(CompiledEventGroup::conditions)
+            .collect();
+        config.needs_member = conditions.iter().any(|c| c.needs_member());
+        config.needs_ancestry = conditions.iter().any(|c| c.needs_ancestry());
+        config
+    }
+
+    /// The invalid patterns found while compiling the config. These patterns never match.
+    pub fn errors(&self) -> &[InvalidPattern] {
+        &self.errors
+    }
+
+    pub fn guild_events(&self) -> Option<&CompiledEventGroup> {
+        self.guild_events.as_ref()
+    }
+
+    pub fn has_channel_events(&self) -> bool {
+        !self.channel_events.is_empty()
+    }
+
+    /// Checks if any condition in the config depends on the roles of the user or when they
+    /// joined. The member need only be looked up for events that do not include it if this is
+    /// true.
+    pub fn needs_member(&self) -> bool {
+        self.needs_member
+    }
+
+    /// Checks if any condition in the config depends on the channel an event happened in or its
+    /// parents.
+    pub fn needs_ancestry(&self) -> bool {
+        self.needs_ancestry
+    }
+
+    /// The maximum number of times rules can fire per minute in the guild. Zero if there is no
+    /// limit.
+    pub fn max_firings_per_minute(&self) -> u32 {
+        self.max_firings_per_minute
+    }
+
+    pub fn scheduled_events(&self) -> &[CompiledScheduledEvent] {
+        &self.scheduled_events
+    }
+
+    /// Gets the event groups applicable to a channel in order of precedence. `ancestry` is the
+    /// channel followed by its parents, from most to least specific. The guild-wide group is
+    /// last. Less specific groups are skipped after the first exclusive group.
+    pub fn event_groups(&self, ancestry: &[Id<ChannelMarker>]) -> Vec<&CompiledEventGroup> {
+        let mut groups = Vec::new();
+        for id in ancestry {
+            if let Some(group) = self.channel_events.get(id) {
+                groups.push(group);
+                if group.exclusive {
+                    return groups;
+                }
+            }
+        }
+        groups.extend(self.guild_events.as_ref());
+        groups
+    }
+
+    /// Gets all of the events of one kind applicable to a channel in order of precedence. See
+    /// `event_groups` for how `ancestry` is used.
+    pub fn channel_events<'a, T>(
+        &'a self,
+        ancestry: &[Id<ChannelMarker>],
+        select: impl Fn(&'a CompiledEventGroup) -> &'a [T],
+    ) -> Vec<&'a T> {
+        self.event_groups(ancestry)
+            .into_iter()
+            .flat_map(select)
+            .collect()
+    }
+
+    /// Gets all of the message events that match a message in order of precedence.
+    pub fn message_events<'a>(
+        &'a self,
+        ancestry: &[Id<ChannelMarker>],
+        content: &str,
+        is_edit: bool,
+        ctx: &ConditionContext,
+    ) -> Vec<&'a CompiledMessageEvent> {
+        self.channel_events(ancestry, |group| group.on_message.as_slice())
+            .into_iter()
+            .filter(|evt| evt.matches(content, is_edit, ctx))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_rules_have_distinct_stable_ids() {
+        let mut group = EventGroup::new();
+        group.mut_on_join().push(UserChangeEvent::new());
+        group.mut_on_join().push(UserChangeEvent::new());
+        group.mut_on_leave().push(UserChangeEvent::new());
+        let mut config = AutoConfig::new();
+        config.set_guild_events(group);
+
+        let compiled = CompiledAutoConfig::new(&config);
+        let guild = compiled.guild_events().unwrap();
+        let first = guild.on_join[0].rule.id;
+        assert_ne!(first, guild.on_join[1].rule.id);
+        assert_ne!(first, guild.on_leave[0].rule.id);
+        assert_eq!(
+            first,
+            CompiledAutoConfig::new(&config)
+                .guild_events()
+                .unwrap()
+                .on_join[0]
+                .rule
+                .id
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use crate::{
+    models::{
+        id::{Id, marker::*},
+        util::Timestamp,
+    },
+    proto::auto_config::{
+        AgeRange, Condition, Condition_oneof_condition, CountRange, RegexCondition_Field,
+    },
+    util::{
+        filter::{InvalidPattern, compile_patterns},
+        mentions,
+    },
+};
+use chrono::Utc;
+use regex::RegexSet;
+use std::collections::HashSet;
+
+/// Milliseconds between the Unix epoch and the Discord epoch, the first second of 2015.
+const DISCORD_EPOCH_MS: i64 = 1_420_070_400_000;
+
+/// The information about an event that conditions are evaluated against. Conditions on
+/// information that is not available are unknown, and never hold, even when negated.
+#[derive(Clone, Debug, Default)]
+pub struct ConditionContext<'a> {
+    pub user_id: Option<Id<UserMarker>>,
+    pub joined_at: Option<Timestamp>,
+    pub roles: Option<&'a [Id<RoleMarker>]>,
+    /// The channel the event happened in followed by its parents.
+    pub ancestry: &'a [Id<ChannelMarker>],
+    pub content: Option<&'a str>,
+    pub attachment_count: Option<usize>,
+    pub username: Option<&'a str>,
+    pub nickname: Option<&'a str>,
+}
+
+impl ConditionContext<'_> {
+    fn account_age(&self, now_ms: i64) -> Option<u64> {
+        let created_ms = (self.user_id?.get() >> 22) as i64 + DISCORD_EPOCH_MS;
+        u64::try_from((now_ms - created_ms) / 1000).ok()
+    }
+
+    fn member_age(&self, now_ms: i64) -> Option<u64> {
+        let joined_ms = self.joined_at?.as_micros() / 1000;
+        u64::try_from((now_ms - joined_ms) / 1000).ok()
+    }
+
+    fn mention_count(&self) -> Option<usize> {
+        let content = self.content?;
+        Some(
+            mentions::get_user_mention_ids(content).count()
+                + mentions::get_role_mention_ids(content).count(),
+        )
+    }
+}
+
+/// An inclusive range of values. Unset bounds are unbounded.
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    min: Option<u64>,
+    max: Option<u64>,
+}
+
+impl Bounds {
+    fn contains(&self, value: u64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+impl From<&AgeRange> for Bounds {
+    fn from(range: &AgeRange) -> Self {
+        Self {
+            min: range.has_min_seconds().then(|| range.get_min_seconds()),
+            max: range.has_max_seconds().then(|| range.get_max_seconds()),
+        }
+    }
+}
+
+impl From<&CountRange> for Bounds {
+    fn from(range: &CountRange) -> Self {
+        Self {
+            min: range.has_min().then(|| u64::from(range.get_min())),
+            max: range.has_max().then(|| u64::from(range.get_max())),
+        }
+    }
+}
+
+/// A pre-compiled `Condition`.
+#[derive(Clone, Debug)]
+pub enum CompiledCondition {
+    All(Vec<CompiledCondition>),
+    Any(Vec<CompiledCondition>),
+    NoneOf(Vec<CompiledCondition>),
+    AccountAge(Bounds),
+    MemberAge(Bounds),
+    HasRole(HashSet<Id<RoleMarker>>),
+    Channel(HashSet<Id<ChannelMarker>>),
+    AttachmentCount(Bounds),
+    MentionCount(Bounds),
+    Regex(RegexCondition_Field, RegexSet),
+}
+
+impl CompiledCondition {
+    /// Compiles a condition. Invalid patterns are added to `errors` and never match.
+    pub fn new(condition: &Condition, errors: &mut Vec<InvalidPattern>) -> Self {
+        let list = |conditions: &[Condition], errors: &mut Vec<InvalidPattern>| {
+            conditions
+                .iter()
+                .map(|condition| Self::new(condition, errors))
+                .collect()
+        };
+        match &condition.condition {
+            Some(Condition_oneof_condition::all(all)) => {
+                Self::All(list(all.get_condition(), errors))
+            }
+            Some(Condition_oneof_condition::any(any)) => {
+                Self::Any(list(any.get_condition(), errors))
+            }
+            Some(Condition_oneof_condition::none(none)) => {
+                Self::NoneOf(list(none.get_condition(), errors))
+            }
+            Some(Condition_oneof_condition::account_age(range)) => Self::AccountAge(range.into()),
+            Some(Condition_oneof_condition::member_age(range)) => Self::MemberAge(range.into()),
+            Some(Condition_oneof_condition::has_role(roles)) => Self::HasRole(
+                roles
+                    .get_role_ids()
+                    .iter()
+                    .filter_map(|id| Id::new_checked(*id))
+                    .collect(),
+            ),
+            Some(Condition_oneof_condition::channel(channels)) => Self::Channel(
+                channels
+                    .get_channel_ids()
+                    .iter()
+                    .filter_map(|id| Id::new_checked(*id))
+                    .collect(),
+            ),
+            Some(Condition_oneof_condition::attachment_count(range)) => {
+                Self::AttachmentCount(range.into())
+            }
+            Some(Condition_oneof_condition::mention_count(range)) => {
+                Self::MentionCount(range.into())
+            }
+            Some(Condition_oneof_condition::regex(regex)) => Self::Regex(
+                regex.get_field(),
+                compile_patterns(regex.get_patterns(), errors),
+            ),
+            None => Self::All(Vec::new()),
+        }
+    }
+
+    /// Checks if the condition holds for an event.
+    pub fn evaluate(&self, ctx: &ConditionContext) -> bool {
+        let now_ms = Utc::now().timestamp_millis();
+        self.evaluate_at(ctx, now_ms)
+    }
+
+    fn evaluate_at(&self, ctx: &ConditionContext, now_ms: i64) -> bool {
+        self.truth(ctx, now_ms).unwrap_or(false)
+    }
+
+    /// Evaluates the condition with three-valued logic. Returns None if whether the condition
+    /// holds is unknown, because it depends on information that is not available.
+    fn truth(&self, ctx: &ConditionContext, now_ms: i64) -> Option<bool> {
+        let count =
+            |value: Option<usize>, range: &Bounds| value.map(|value| range.contains(value as u64));
+        match self {
+            Self::All(conditions) => all(conditions.iter().map(|c| c.truth(ctx, now_ms))),
+            Self::Any(conditions) => any(conditions.iter().map(|c| c.truth(ctx, now_ms))),
+            Self::NoneOf(conditions) => {
+                any(conditions.iter().map(|c| c.truth(ctx, now_ms))).map(|any| !any)
+            }
+            Self::AccountAge(range) => ctx.account_age(now_ms).map(|age| range.contains(age)),
+            Self::MemberAge(range) => ctx.member_age(now_ms).map(|age| range.contains(age)),
+            Self::HasRole(roles) => ctx
+                .roles
+                .map(|held| held.iter().any(|id| roles.contains(id))),
+            Self::Channel(channels) => Some(ctx.ancestry.iter().any(|id| channels.contains(id))),
+            Self::AttachmentCount(range) => count(ctx.attachment_count, range),
+            Self::MentionCount(range) => count(ctx.mention_count(), range),
+            Self::Regex(field, patterns) => {
+                let value = match field {
+                    RegexCondition_Field::CONTENT => ctx.content,
+                    RegexCondition_Field::USERNAME => ctx.username,
+                    RegexCondition_Field::NICKNAME => ctx.nickname,
+                };
+                value.map(|value| patterns.is_match(value))
+            }
+        }
+    }
+
+    /// Checks if evaluating the condition needs the member information of the user: their
+    /// roles or when they joined.
+    pub fn needs_member(&self) -> bool {
+        match self {
+            Self::All(conditions) | Self::Any(conditions) | Self::NoneOf(conditions) => {
+                conditions.iter().any(|c| c.needs_member())
+            }
+            Self::HasRole(_) | Self::MemberAge(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Checks if evaluating the condition needs the ancestry of the channel.
+    pub fn needs_ancestry(&self) -> bool {
+        match self {
+            Self::All(conditions) | Self::Any(conditions) | Self::NoneOf(conditions) => {
+                conditions.iter().any(|c| c.needs_ancestry())
+            }
+            Self::Channel(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Combines conditions that must all hold. False if any is false, even if others are unknown.
+fn all(values: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    let mut result = Some(true);
+    for value in values {
+        match value {
+            Some(false) => return Some(false),
+            Some(true) => {}
+            None => result = None,
+        }
+    }
+    result
+}
+
+/// Combines conditions of which any must hold. True if any is true, even if others are unknown.
+fn any(values: impl Iterator<Item = Option<bool>>) -> Option<bool> {
+    let mut result = Some(false);
+    for value in values {
+        match value {
+            Some(true) => return Some(true),
+            Some(false) => {}
+            None => result = None,
+        }
+    }
+    result
+}
+
+/// Checks if an optional condition holds. Missing conditions always hold.
+pub fn meets_condition(condition: Option<&CompiledCondition>, ctx: &ConditionContext) -> bool {
+    condition.is_none_or(|condition| condition.evaluate(ctx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::auto_config::{
+        ChannelCondition, ConditionList, RegexCondition, RoleCondition,
+    };
+
+    fn has_role(id: u64) -> Condition {
+        let mut roles = RoleCondition::new();
+        roles.mut_role_ids().push(id);
+        let mut condition = Condition::new();
+        condition.set_has_role(roles);
+        condition
+    }
+
+    fn content_regex(pattern: &str) -> Condition {
+        let mut regex = RegexCondition::new();
+        regex.set_field(RegexCondition_Field::CONTENT);
+        regex.mut_patterns().push(pattern.to_owned());
+        let mut condition = Condition::new();
+        condition.set_regex(regex);
+        condition
+    }
+
+    fn new_member(max_seconds: u64) -> Condition {
+        let mut range = AgeRange::new();
+        range.set_max_seconds(max_seconds);
+        let mut condition = Condition::new();
+        condition.set_member_age(range);
+        condition
+    }
+
+    fn list(conditions: Vec<Condition>) -> ConditionList {
+        let mut list = ConditionList::new();
+        list.set_condition(conditions.into());
+        list
+    }
+
+    #[test]
+    fn new_members_without_role_posting_links() {
+        let mut none = Condition::new();
+        none.set_none(list(vec![has_role(1)]));
+        let mut all = Condition::new();
+        all.set_all(list(vec![
+            new_member(3600),
+            none,
+            content_regex("https?://"),
+        ]));
+
+        let mut errors = Vec::new();
+        let condition = CompiledCondition::new(&all, &mut errors);
+        assert!(errors.is_empty());
+
+        let now_ms = 1_700_000_000_000;
+        let joined_at = Timestamp::from_micros((now_ms - 60_000) * 1000).unwrap();
+        let no_roles = [];
+        let roles = [Id::new(1)];
+        let mut ctx = ConditionContext {
+            joined_at: Some(joined_at),
+            roles: Some(&no_roles),
+            content: Some("check out https://example.com"),
+            ..Default::default()
+        };
+        assert!(condition.evaluate_at(&ctx, now_ms));
+
+        ctx.roles = Some(&roles);
+        assert!(!condition.evaluate_at(&ctx, now_ms));
+
+        ctx.roles = Some(&no_roles);
+        ctx.content = Some("hello");
+        assert!(!condition.evaluate_at(&ctx, now_ms));
+
+        ctx.content = Some("https://example.com");
+        assert!(!condition.evaluate_at(&ctx, now_ms + 7_200_000));
+    }
+
+    #[test]
+    fn missing_information_never_holds() {
+        let mut errors = Vec::new();
+        let condition = CompiledCondition::new(&has_role(1), &mut errors);
+        assert!(!condition.evaluate(&ConditionContext::default()));
+        assert!(condition.needs_member());
+        assert!(!condition.needs_ancestry());
+    }
+
+    #[test]
+    fn negated_missing_information_never_holds() {
+        let mut none = Condition::new();
+        none.set_none(list(vec![has_role(1)]));
+        let mut errors = Vec::new();
+        let condition = CompiledCondition::new(&none, &mut errors);
+
+        // A message without member information, such as an edit of an uncached member's message.
+        let mut ctx = ConditionContext {
+            content: Some("https://example.com"),
+            ..Default::default()
+        };
+        assert!(!condition.evaluate(&ctx));
+        let no_roles = [];
+        ctx.roles = Some(&no_roles);
+        assert!(condition.evaluate(&ctx));
+
+        // Known results still decide combinations that include unknown ones.
+        ctx.roles = None;
+        let mut any = Condition::new();
+        any.set_any(list(vec![has_role(1), content_regex("https?://")]));
+        assert!(CompiledCondition::new(&any, &mut errors).evaluate(&ctx));
+        let mut all = Condition::new();
+        all.set_all(list(vec![none, content_regex("hello")]));
+        let mut negated = Condition::new();
+        negated.set_none(list(vec![all]));
+        assert!(CompiledCondition::new(&negated, &mut errors).evaluate(&ctx));
+    }
+
+    #[test]
+    fn nested_conditions_need_member_and_ancestry() {
+        let mut channels = ChannelCondition::new();
+        channels.mut_channel_ids().push(1);
+        let mut channel = Condition::new();
+        channel.set_channel(channels);
+        let mut any = Condition::new();
+        any.set_any(list(vec![new_member(3600), channel]));
+
+        let mut errors = Vec::new();
+        let condition = CompiledCondition::new(&any, &mut errors);
+        assert!(condition.needs_member());
+        assert!(condition.needs_ancestry());
+
+        let condition = CompiledCondition::new(&content_regex("a"), &mut errors);
+        assert!(!condition.needs_member());
+        assert!(!condition.needs_ancestry());
+    }
+
+    #[test]
+    fn empty_condition_always_holds() {
+        let mut errors = Vec::new();
+        let condition = CompiledCondition::new(&Condition::new(), &mut errors);
+        assert!(condition.evaluate(&ConditionContext::default()));
+    }
+}
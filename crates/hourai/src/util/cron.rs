@@ -0,0 +1,211 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// How far ahead to search for the next run of a schedule. Schedules that never match, like
+/// the 30th of February, give up after this many years.
+const MAX_SEARCH_YEARS: i64 = 5;
+
+/// The name and inclusive range of values of each field, in order.
+const FIELDS: [(&str, u32, u32); 5] = [
+    ("minute", 0, 59),
+    ("hour", 0, 23),
+    ("day of month", 1, 31),
+    ("month", 1, 12),
+    ("day of week", 0, 7),
+];
+
+#[derive(Clone, Debug, Error)]
+#[error("Invalid schedule `{schedule}`: {reason}")]
+pub struct InvalidSchedule {
+    pub schedule: String,
+    pub reason: String,
+}
+
+/// A parsed cron expression. Schedules are always evaluated in UTC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// If both day fields are restricted, a day matches if either of them does.
+    either_day: bool,
+}
+
+fn contains(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parses a single field into a bitmask of the values it matches. Fields are comma separated
+/// lists of `*`, values or ranges, each optionally followed by a step.
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut mask = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>().ok().filter(|s| *s > 0)?),
+            None => (item, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let start = range.parse().ok()?;
+            // A single value with a step, like `5/15`, runs from the value to the maximum.
+            (start, if item.contains('/') { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step) {
+            mask |= 1 << value;
+        }
+    }
+    Some(mask)
+}
+
+impl FromStr for Schedule {
+    type Err = InvalidSchedule;
+
+    fn from_str(schedule: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| InvalidSchedule {
+            schedule: schedule.to_owned(),
+            reason,
+        };
+        let fields: Vec<&str> = schedule.split_whitespace().collect();
+        if fields.len() != FIELDS.len() {
+            return Err(error(format!(
+                "expected {} fields, found {}",
+                FIELDS.len(),
+                fields.len()
+            )));
+        }
+
+        let mut masks = [0; FIELDS.len()];
+        for (mask, (field, (name, min, max))) in masks.iter_mut().zip(fields.iter().zip(FIELDS)) {
+            *mask =
+                parse_field(field, min, max).ok_or_else(|| error(format!("invalid {}", name)))?;
+        }
+
+        // Sunday can be written as either 0 or 7.
+        let mut days_of_week = masks[4];
+        if contains(days_of_week, 7) {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: masks[0],
+            hours: masks[1],
+            days_of_month: masks[2],
+            months: masks[3],
+            days_of_week,
+            either_day: fields[2] != "*" && fields[4] != "*",
+        })
+    }
+}
+
+fn start_of_day(date: NaiveDate) -> Option<DateTime<Utc>> {
+    Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?))
+}
+
+impl Schedule {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day_of_month = contains(self.days_of_month, date.day());
+        let day_of_week = contains(self.days_of_week, date.weekday().num_days_from_sunday());
+        if self.either_day {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+
+    /// Gets the first time the schedule runs strictly after the given time. Returns `None` if
+    /// the schedule never runs.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(366 * MAX_SEARCH_YEARS);
+        while time < limit {
+            let date = time.date_naive();
+            if !contains(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = start_of_day(NaiveDate::from_ymd_opt(year, month, 1)?)?;
+            } else if !self.matches_day(date) {
+                time = start_of_day(date.succ_opt()?)?;
+            } else if !contains(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !contains(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn weekly_schedule() {
+        let schedule: Schedule = "0 9 * * 1".parse().unwrap();
+        // 2026-10-18 is a Sunday.
+        let sunday = time(2026, 10, 18, 12, 30);
+        let monday = time(2026, 10, 19, 9, 0);
+        assert_eq!(schedule.next_after(sunday), Some(monday));
+        assert_eq!(schedule.next_after(monday), Some(time(2026, 10, 26, 9, 0)));
+    }
+
+    #[test]
+    fn steps_ranges_and_lists() {
+        let schedule: Schedule = "*/15 8-10,22 * * *".parse().unwrap();
+        let start = time(2026, 10, 19, 10, 50);
+        assert_eq!(schedule.next_after(start), Some(time(2026, 10, 19, 22, 0)));
+        assert_eq!(
+            schedule.next_after(time(2026, 10, 19, 8, 0)),
+            Some(time(2026, 10, 19, 8, 15))
+        );
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_set() {
+        // The 1st of the month or any Friday.
+        let schedule: Schedule = "0 0 1 * 5".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(time(2026, 10, 19, 0, 0)),
+            Some(time(2026, 10, 23, 0, 0))
+        );
+        assert_eq!(
+            schedule.next_after(time(2026, 10, 30, 0, 0)),
+            Some(time(2026, 11, 1, 0, 0))
+        );
+        // Sunday can be written as 7.
+        assert_eq!(
+            "0 0 * * 7".parse::<Schedule>().unwrap(),
+            "0 0 * * 0".parse::<Schedule>().unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_and_impossible_schedules() {
+        assert!("0 9 * *".parse::<Schedule>().is_err());
+        assert!("60 * * * *".parse::<Schedule>().is_err());
+        assert!("*/0 * * * *".parse::<Schedule>().is_err());
+        assert!("5-1 * * * *".parse::<Schedule>().is_err());
+
+        let february_30th: Schedule = "0 0 30 2 *".parse().unwrap();
+        assert_eq!(february_30th.next_after(time(2026, 1, 1, 0, 0)), None);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::proto::util::FilterSettings;
+use regex::{Regex, RegexSet};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error)]
+#[error("Invalid pattern `{pattern}`: {error}")]
+pub struct InvalidPattern {
+    pub pattern: String,
+    pub error: regex::Error,
+}
+
+/// Builds a `RegexSet` from a set of patterns. Invalid patterns are excluded from the set and
+/// returned separately.
+pub fn compile_patterns<'a>(
+    patterns: impl IntoIterator<Item = &'a String>,
+    errors: &mut Vec<InvalidPattern>,
+) -> RegexSet {
+    let valid = patterns
+        .into_iter()
+        .filter(|pattern| match Regex::new(pattern) {
+            Ok(_) => true,
+            Err(error) => {
+                errors.push(InvalidPattern {
+                    pattern: (*pattern).clone(),
+                    error,
+                });
+                false
+            }
+        });
+    // Every pattern has been validated individually, so this should never fail. Fall back to
+    // matching nothing if it does.
+    RegexSet::new(valid).unwrap_or_else(|_| RegexSet::empty())
+}
+
+/// A pre-compiled form of `FilterSettings`.
+#[derive(Clone, Debug)]
+pub struct CompiledFilter {
+    blacklist: Option<RegexSet>,
+    whitelist: Option<RegexSet>,
+}
+
+impl CompiledFilter {
+    /// Compiles the provided filter settings. Invalid patterns are added to `errors` and never
+    /// match.
+    pub fn new(settings: &FilterSettings, errors: &mut Vec<InvalidPattern>) -> Self {
+        let compile = |patterns: &[String], errors: &mut Vec<InvalidPattern>| {
+            (!patterns.is_empty()).then(|| compile_patterns(patterns, errors))
+        };
+        Self {
+            blacklist: compile(settings.get_blacklist(), errors),
+            whitelist: compile(settings.get_whitelist(), errors),
+        }
+    }
+
+    /// Checks if a value passes the filter.
+    ///
+    /// If a blacklist is present, values pass if they do not match the blacklist or are
+    /// explicitly whitelisted. If only a whitelist is present, only values that match the
+    /// whitelist pass. Empty filters let every value pass.
+    pub fn is_match(&self, value: &str) -> bool {
+        let whitelisted = || {
+            self.whitelist
+                .as_ref()
+                .map(|set| set.is_match(value))
+                .unwrap_or(false)
+        };
+        match (&self.blacklist, &self.whitelist) {
+            (Some(blacklist), _) => !blacklist.is_match(value) || whitelisted(),
+            (None, Some(_)) => whitelisted(),
+            (None, None) => true,
+        }
+    }
+}
+
+/// Checks if an optional value passes an optional filter. Missing values never pass, and a
+/// missing filter lets every value pass.
+pub fn meets_filter(value: Option<&str>, filter: Option<&CompiledFilter>) -> bool {
+    match (value, filter) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(value), Some(filter)) => filter.is_match(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(blacklist: &[&str], whitelist: &[&str]) -> FilterSettings {
+        let mut settings = FilterSettings::new();
+        settings
+            .mut_blacklist()
+            .extend(blacklist.iter().map(|p| p.to_string()));
+        settings
+            .mut_whitelist()
+            .extend(whitelist.iter().map(|p| p.to_string()));
+        settings
+    }
+
+    #[test]
+    fn blacklist_with_whitelist_exceptions() {
+        let mut errors = Vec::new();
+        let filter = CompiledFilter::new(&filter(&["bad"], &["not bad"]), &mut errors);
+        assert!(errors.is_empty());
+        assert!(filter.is_match("fine"));
+        assert!(!filter.is_match("bad"));
+        assert!(filter.is_match("not bad"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported_and_never_match() {
+        let mut errors = Vec::new();
+        let filter = CompiledFilter::new(&filter(&[], &["(", "good"]), &mut errors);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].pattern, "(");
+        assert!(filter.is_match("good"));
+        assert!(!filter.is_match("("));
+    }
+}
@@ -1,2 +1,30 @@
+pub mod auto_config;
+pub mod condition;
+pub mod cron;
+pub mod filter;
 pub mod mentions;
+pub mod template;
+pub mod validation;
 pub mod whois;
+
+/// Hashes bytes with 64-bit FNV-1a. Unlike the standard library's hashers, the result is stable
+/// across Rust releases and platforms, so it can be persisted.
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash_matches_fnv1a() {
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
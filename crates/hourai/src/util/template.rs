@@ -0,0 +1,230 @@
+use crate::models::id::{Id, marker::*};
+use thiserror::Error;
+
+/// The placeholders supported in templated messages.
+pub const PLACEHOLDERS: &[&str] = &[
+    "user",
+    "user.name",
+    "guild",
+    "guild.member_count",
+    "channel",
+    "reason",
+    "message",
+    "escalation.rung",
+    "escalation.next_rung",
+    "escalation.expiration",
+];
+
+/// The maximum number of characters of the triggering message rendered by `{message}`.
+const MESSAGE_EXCERPT_LENGTH: usize = 100;
+
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum TemplateError {
+    #[error("Unknown placeholder `{{{0}}}`. Supported placeholders: {1}")]
+    UnknownPlaceholder(String, String),
+    #[error("Unclosed placeholder: `{0}`. Use `{{{{` to include a literal `{{`.")]
+    Unclosed(String),
+}
+
+/// The values available to placeholders when rendering a template. Placeholders without an
+/// available value are rendered as empty strings.
+#[derive(Clone, Debug, Default)]
+pub struct TemplateContext {
+    pub user_id: Option<Id<UserMarker>>,
+    pub user_name: Option<String>,
+    pub guild_name: Option<String>,
+    pub member_count: Option<u64>,
+    pub channel_id: Option<Id<ChannelMarker>>,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    /// The display name of the escalation rung the user was escalated to.
+    pub escalation_rung: Option<String>,
+    /// The display name of the rung after it, or "None" at the top of the ladder.
+    pub escalation_next_rung: Option<String>,
+    /// When the escalation expires, already formatted for display.
+    pub escalation_expiration: Option<String>,
+}
+
+impl TemplateContext {
+    fn value(&self, placeholder: &str) -> Option<String> {
+        match placeholder {
+            "user" => self.user_id.map(|id| format!("<@{}>", id)),
+            "user.name" => self.user_name.as_deref().map(escape_mentions),
+            "guild" => self.guild_name.as_deref().map(escape_mentions),
+            "guild.member_count" => self.member_count.map(|count| count.to_string()),
+            "channel" => self.channel_id.map(|id| format!("<#{}>", id)),
+            "reason" => self.reason.as_deref().map(escape_mentions),
+            "message" => self.message.as_deref().map(|message| {
+                let mut excerpt: String = message.chars().take(MESSAGE_EXCERPT_LENGTH).collect();
+                if excerpt.len() < message.len() {
+                    excerpt.push('…');
+                }
+                escape_mentions(&excerpt)
+            }),
+            "escalation.rung" => self.escalation_rung.clone(),
+            "escalation.next_rung" => self.escalation_next_rung.clone(),
+            "escalation.expiration" => self.escalation_expiration.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// User provided text may contain @everyone or @here. Break them up to avoid pinging everyone.
+fn escape_mentions(text: &str) -> String {
+    text.replace('@', "@\u{200B}")
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+    Unclosed(&'a str),
+}
+
+struct Tokens<'a>(&'a str);
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.0;
+        if rest.is_empty() {
+            return None;
+        }
+
+        let (token, len) = if rest.starts_with("{{") {
+            (Token::Text("{"), 2)
+        } else if rest.starts_with("}}") {
+            (Token::Text("}"), 2)
+        } else if rest.starts_with('}') {
+            (Token::Text("}"), 1)
+        } else if let Some(inner) = rest.strip_prefix('{') {
+            match inner.find('}') {
+                Some(end) => (Token::Placeholder(&inner[..end]), end + 2),
+                None => (Token::Unclosed(rest), rest.len()),
+            }
+        } else {
+            let end = rest.find(['{', '}']).unwrap_or(rest.len());
+            (Token::Text(&rest[..end]), end)
+        };
+
+        self.0 = &rest[len..];
+        Some(token)
+    }
+}
+
+/// Gets all of the placeholders used in a template.
+pub fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    Tokens(template).filter_map(|token| match token {
+        Token::Placeholder(placeholder) => Some(placeholder),
+        _ => None,
+    })
+}
+
+/// Checks that a template only uses supported placeholders.
+pub fn validate(template: &str) -> Result<(), TemplateError> {
+    for token in Tokens(template) {
+        match token {
+            Token::Placeholder(placeholder) if !PLACEHOLDERS.contains(&placeholder) => {
+                return Err(TemplateError::UnknownPlaceholder(
+                    placeholder.to_owned(),
+                    PLACEHOLDERS
+                        .iter()
+                        .map(|p| format!("`{{{}}}`", p))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ));
+            }
+            Token::Unclosed(text) => return Err(TemplateError::Unclosed(text.to_owned())),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Renders a template with the provided context. Invalid placeholders are left as is.
+pub fn render(template: &str, ctx: &TemplateContext) -> String {
+    let mut output = String::with_capacity(template.len());
+    for token in Tokens(template) {
+        match token {
+            Token::Text(text) | Token::Unclosed(text) => output.push_str(text),
+            Token::Placeholder(placeholder) if PLACEHOLDERS.contains(&placeholder) => {
+                if let Some(value) = ctx.value(placeholder) {
+                    output.push_str(&value);
+                }
+            }
+            Token::Placeholder(placeholder) => {
+                output.push('{');
+                output.push_str(placeholder);
+                output.push('}');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            user_id: Some(Id::new(1234)),
+            user_name: Some("Reimu".into()),
+            guild_name: Some("Hakurei Shrine".into()),
+            member_count: Some(42),
+            channel_id: Some(Id::new(5678)),
+            reason: None,
+            message: Some("@everyone hello".into()),
+            escalation_rung: Some("Warning".into()),
+            escalation_next_rung: Some("None".into()),
+            escalation_expiration: Some("Never".into()),
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        assert_eq!(
+            render("Welcome {user} ({user.name}) to {guild}!", &context()),
+            "Welcome <@1234> (Reimu) to Hakurei Shrine!"
+        );
+        assert_eq!(
+            render("Member #{guild.member_count} in {channel}", &context()),
+            "Member #42 in <#5678>"
+        );
+        assert_eq!(render("Reason: {reason}", &context()), "Reason: ");
+        assert_eq!(render("{message}", &context()), "@\u{200B}everyone hello");
+        let ctx = TemplateContext {
+            guild_name: Some("@here".into()),
+            reason: Some("Pinged @everyone".into()),
+            ..context()
+        };
+        assert_eq!(
+            render("{guild}: {reason}", &ctx),
+            "@\u{200B}here: Pinged @\u{200B}everyone"
+        );
+        assert_eq!(
+            render(
+                "{escalation.rung} ({escalation.next_rung}) until {escalation.expiration}",
+                &context()
+            ),
+            "Warning (None) until Never"
+        );
+        assert_eq!(
+            render("{{user}} {unknown} {", &context()),
+            "{user} {unknown} {"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("{user} joined {guild}. {{literal}}"), Ok(()));
+        assert!(matches!(
+            validate("Hello {usr}"),
+            Err(TemplateError::UnknownPlaceholder(placeholder, _)) if placeholder == "usr"
+        ));
+        assert_eq!(
+            validate("Hello {user"),
+            Err(TemplateError::Unclosed("{user".into()))
+        );
+    }
+}
@@ -0,0 +1,948 @@
+use crate::{
+    proto::{
+        action::*,
+        auto_config::*,
+        escalation::*,
+        guild_configs::*,
+        util::{FilterSettings, IdFilter},
+    },
+    util::{cron::Schedule, template},
+};
+use regex::Regex;
+use std::{collections::HashSet, fmt};
+
+/// The longest Discord allows a user to be timed out for, in seconds.
+const MAX_TIMEOUT_SECONDS: u64 = 28 * 24 * 60 * 60;
+
+/// The longest slowmode Discord allows, in seconds.
+const MAX_SLOWMODE_SECONDS: u32 = 6 * 60 * 60;
+
+/// The most days of messages Discord can delete when banning a user.
+const MAX_BAN_DELETE_MESSAGE_DAYS: u32 = 7;
+
+/// The shortest time unvalidated users can be given before being kicked, in seconds.
+const MIN_KICK_UNVALIDATED_SECONDS: u64 = 60 * 60;
+
+/// A single problem found in a config, along with the path of the field it was found in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "`{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Every problem found while validating a config.
+#[derive(Clone, Debug)]
+pub struct InvalidConfig(pub Vec<ValidationError>);
+
+impl std::error::Error for InvalidConfig {}
+
+impl fmt::Display for InvalidConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid config:")?;
+        for error in self.0.iter() {
+            write!(f, "\n- {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// The channels and roles of a guild that configs are allowed to reference.
+#[derive(Clone, Debug, Default)]
+pub struct GuildResources {
+    pub channel_ids: HashSet<u64>,
+    pub role_ids: HashSet<u64>,
+}
+
+/// Walks a config, collecting errors under the path of the field currently being checked.
+pub struct Validator<'a> {
+    path: Vec<String>,
+    resources: Option<&'a GuildResources>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Validator<'a> {
+    /// Creates a validator. If no resources are provided, references to channels and roles are
+    /// not checked.
+    pub fn new(resources: Option<&'a GuildResources>) -> Self {
+        Self {
+            path: Vec::new(),
+            resources,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    /// Records an error for the field currently being checked.
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.errors.push(ValidationError {
+            path: self.path.join("."),
+            message: message.into(),
+        });
+    }
+
+    /// Runs a check on a field of the current value.
+    pub fn field(&mut self, name: impl Into<String>, check: impl FnOnce(&mut Self)) {
+        self.path.push(name.into());
+        check(self);
+        self.path.pop();
+    }
+
+    /// Validates a nested value.
+    pub fn child(&mut self, name: impl Into<String>, value: &impl Validate) {
+        self.field(name, |validator| value.validate(validator));
+    }
+
+    /// Validates each element of a repeated field.
+    pub fn each<T: Validate>(&mut self, name: &str, values: &[T]) {
+        for (idx, value) in values.iter().enumerate() {
+            self.child(format!("{}[{}]", name, idx), value);
+        }
+    }
+
+    pub fn channel(&mut self, name: &str, channel_id: u64) {
+        let resources = self.resources;
+        self.field(name, |validator| {
+            if channel_id == 0 {
+                validator.error("A channel must be provided.");
+            } else if resources.is_some_and(|res| !res.channel_ids.contains(&channel_id)) {
+                validator.error(format!("Channel {} does not exist.", channel_id));
+            }
+        });
+    }
+
+    pub fn channels(&mut self, name: &str, channel_ids: &[u64]) {
+        for (idx, channel_id) in channel_ids.iter().enumerate() {
+            self.channel(&format!("{}[{}]", name, idx), *channel_id);
+        }
+    }
+
+    pub fn role(&mut self, name: &str, role_id: u64) {
+        let resources = self.resources;
+        self.field(name, |validator| {
+            if role_id == 0 {
+                validator.error("A role must be provided.");
+            } else if resources.is_some_and(|res| !res.role_ids.contains(&role_id)) {
+                validator.error(format!("Role {} does not exist.", role_id));
+            }
+        });
+    }
+
+    pub fn roles(&mut self, name: &str, role_ids: &[u64]) {
+        for (idx, role_id) in role_ids.iter().enumerate() {
+            self.role(&format!("{}[{}]", name, idx), *role_id);
+        }
+    }
+
+    pub fn patterns(&mut self, name: &str, patterns: &[String]) {
+        for (idx, pattern) in patterns.iter().enumerate() {
+            if let Err(err) = Regex::new(pattern) {
+                self.field(format!("{}[{}]", name, idx), |validator| {
+                    validator.error(format!("Invalid pattern `{}`: {}", pattern, err))
+                });
+            }
+        }
+    }
+
+    pub fn template(&mut self, name: &str, content: &str) {
+        self.field(name, |validator| {
+            if content.is_empty() {
+                validator.error("Message content must not be empty.");
+            } else if let Err(err) = template::validate(content) {
+                validator.error(err.to_string());
+            }
+        });
+    }
+}
+
+/// A config, or part of a config, that can be checked for problems.
+pub trait Validate {
+    fn validate(&self, validator: &mut Validator);
+}
+
+/// Finds all of the problems in a config. If resources are provided, references to channels and
+/// roles that are not in them are reported.
+pub fn errors(config: &impl Validate, resources: Option<&GuildResources>) -> Vec<ValidationError> {
+    let mut validator = Validator::new(resources);
+    config.validate(&mut validator);
+    validator.into_errors()
+}
+
+/// Checks a config for problems. If resources are provided, references to channels and roles
+/// that are not in them are reported.
+pub fn validate(
+    config: &impl Validate,
+    resources: Option<&GuildResources>,
+) -> Result<(), InvalidConfig> {
+    let errors = errors(config, resources);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(InvalidConfig(errors))
+    }
+}
+
+/// Checks a change to a config for problems. Only problems the change introduces are errors.
+/// Problems `previous` already had are returned as warnings, so that they do not block unrelated
+/// changes.
+pub fn validate_change<T: Validate>(
+    previous: &T,
+    config: &T,
+    resources: Option<&GuildResources>,
+) -> Result<Vec<ValidationError>, InvalidConfig> {
+    let existing = errors(previous, resources);
+    let (warnings, errors): (Vec<_>, Vec<_>) = errors(config, resources)
+        .into_iter()
+        .partition(|err| existing.contains(err));
+    if errors.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(InvalidConfig(errors))
+    }
+}
+
+/// Checks if an action can be undone, which is required for it to have a duration.
+pub fn can_undo(action: &Action) -> bool {
+    match &action.details {
+        Some(Action_oneof_details::ban(info)) => info.get_field_type() != BanMember_Type::SOFTBAN,
+        Some(
+            Action_oneof_details::escalate(_)
+            | Action_oneof_details::mute(_)
+            | Action_oneof_details::deafen(_)
+            | Action_oneof_details::change_role(_)
+            | Action_oneof_details::timeout(_)
+            | Action_oneof_details::set_nickname(_)
+            | Action_oneof_details::set_slowmode(_)
+            | Action_oneof_details::lock_channel(_),
+        ) => true,
+        _ => false,
+    }
+}
+
+impl Validate for GuildConfig {
+    fn validate(&self, validator: &mut Validator) {
+        if let Some(logging) = self.logging.as_ref() {
+            validator.child("logging", logging);
+        }
+        if let Some(verification) = self.verification.as_ref() {
+            validator.child("verification", verification);
+        }
+        if let Some(auto) = self.auto.as_ref() {
+            validator.child("auto", auto);
+        }
+        if let Some(moderation) = self.moderation.as_ref() {
+            validator.child("moderation", moderation);
+        }
+        if let Some(music) = self.music.as_ref() {
+            validator.child("music", music);
+        }
+        if let Some(announce) = self.announce.as_ref() {
+            validator.child("announce", announce);
+        }
+        if let Some(role) = self.role.as_ref() {
+            validator.child("role", role);
+        }
+    }
+}
+
+impl Validate for Action {
+    fn validate(&self, validator: &mut Validator) {
+        let details = match self.details.as_ref() {
+            Some(details) => details,
+            None => return validator.error("An action type must be provided."),
+        };
+        if self.has_duration() && !can_undo(self) {
+            validator.field("duration", |validator| {
+                validator
+                    .error("This type of action cannot be undone, so it cannot have a duration.")
+            });
+        }
+        match details {
+            Action_oneof_details::ban(info)
+                if info.get_delete_message_days() > MAX_BAN_DELETE_MESSAGE_DAYS =>
+            {
+                validator.field("ban.delete_message_days", |validator| {
+                    validator.error(format!(
+                        "At most {} days of messages can be deleted.",
+                        MAX_BAN_DELETE_MESSAGE_DAYS
+                    ))
+                });
+            }
+            Action_oneof_details::escalate(info) if info.get_amount() == 0 => {
+                validator.field("escalate.amount", |validator| {
+                    validator.error("The amount must not be zero.")
+                });
+            }
+            Action_oneof_details::change_role(info) => {
+                if info.get_role_ids().is_empty() {
+                    validator.field("change_role.role_ids", |validator| {
+                        validator.error("At least one role must be provided.")
+                    });
+                }
+                validator.roles("change_role.role_ids", info.get_role_ids());
+            }
+            Action_oneof_details::timeout(info) => {
+                let duration = self.get_duration();
+                if info.get_field_type() != StatusType::UNAPPLY
+                    && (duration == 0 || duration > MAX_TIMEOUT_SECONDS)
+                {
+                    validator.field("duration", |validator| {
+                        validator
+                            .error("Timeouts must have a duration between 1 second and 28 days.")
+                    });
+                }
+            }
+            Action_oneof_details::set_slowmode(info) => {
+                validator.channel("set_slowmode.channel_id", info.get_channel_id());
+                if info.get_seconds() > MAX_SLOWMODE_SECONDS {
+                    validator.field("set_slowmode.seconds", |validator| {
+                        validator.error(format!(
+                            "Slowmode can be at most {} seconds.",
+                            MAX_SLOWMODE_SECONDS
+                        ))
+                    });
+                }
+            }
+            Action_oneof_details::lock_channel(info) => {
+                validator.channel("lock_channel.channel_id", info.get_channel_id());
+            }
+            Action_oneof_details::direct_message(info) => {
+                validator.template("direct_message.content", info.get_content());
+            }
+            Action_oneof_details::send_message(info) => {
+                validator.channel("send_message.channel_id", info.get_channel_id());
+                validator.template("send_message.content", info.get_content());
+            }
+            Action_oneof_details::delete_messages(info) => {
+                validator.channel("delete_messages.channel_id", info.get_channel_id());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Validate for FilterSettings {
+    fn validate(&self, validator: &mut Validator) {
+        validator.patterns("blacklist", self.get_blacklist());
+        validator.patterns("whitelist", self.get_whitelist());
+    }
+}
+
+impl Validate for IdFilter {
+    fn validate(&self, validator: &mut Validator) {
+        validator.channels("allowlist", self.get_allowlist());
+        validator.channels("denylist", self.get_denylist());
+    }
+}
+
+// ------------------------------------------------------------------------------
+// Logging Configs
+// ------------------------------------------------------------------------------
+
+impl Validate for LoggingConfig {
+    fn validate(&self, validator: &mut Validator) {
+        if self.has_modlog_channel_id() {
+            validator.channel("modlog_channel_id", self.get_modlog_channel_id());
+        }
+        if let Some(deleted) = self.deleted_messages.as_ref() {
+            validator.child("deleted_messages", deleted);
+        }
+        if let Some(edited) = self.edited_messages.as_ref() {
+            validator.child("edited_messages", edited);
+        }
+    }
+}
+
+impl Validate for MessageLoggingConfig {
+    fn validate(&self, validator: &mut Validator) {
+        if self.get_enabled() || self.has_output_channel_id() {
+            validator.channel("output_channel_id", self.get_output_channel_id());
+        }
+        if let Some(filter) = self.channel_filter.as_ref() {
+            validator.child("channel_filter", filter);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+// Moderation Configs
+// ------------------------------------------------------------------------------
+
+impl Validate for ModerationConfig {
+    fn validate(&self, validator: &mut Validator) {
+        if let Some(ladder) = self.escalation_ladder.as_ref() {
+            validator.child("escalation_ladder", ladder);
+        }
+        if let Some(filter) = self.message_filter.as_ref() {
+            validator.each("message_filter.rules", filter.get_rules());
+        }
+        if let Some(appeals) = self.appeals.as_ref() {
+            validator.child("appeals", appeals);
+        }
+    }
+}
+
+impl Validate for AppealsConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.channel("channel_id", self.get_channel_id());
+    }
+}
+
+impl Validate for EscalationLadder {
+    fn validate(&self, validator: &mut Validator) {
+        if self.get_rung().is_empty() {
+            validator.field("rung", |validator| {
+                validator.error("An escalation ladder must have at least one rung.")
+            });
+        }
+        validator.each("rung", self.get_rung());
+        if self.has_default_notification() {
+            validator.template("default_notification", self.get_default_notification());
+        }
+        if self.get_mode() == EscalationMode::ESCALATION_MODE_POINTS {
+            let mut previous = 0;
+            for (idx, rung) in self.get_rung().iter().enumerate() {
+                if rung.get_threshold() <= previous {
+                    validator.field(format!("rung[{}].threshold", idx), |validator| {
+                        validator.error(
+                            "In points mode, every rung needs a threshold higher than the \
+                             previous rung's.",
+                        )
+                    });
+                }
+                previous = previous.max(rung.get_threshold());
+                if rung.has_deescalation_period() {
+                    validator.field(format!("rung[{}].deescalation_period", idx), |validator| {
+                        validator.error(
+                            "Rungs do not deescalate in points mode. Set the ladder's \
+                             point_lifetime instead.",
+                        )
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Validate for EscalationLadderRung {
+    fn validate(&self, validator: &mut Validator) {
+        if self.get_display_name().is_empty() {
+            validator.field("display_name", |validator| {
+                validator.error("Every rung must have a display name.")
+            });
+        }
+        if self.get_action().is_empty() {
+            validator.field("action", |validator| {
+                validator.error("Every rung must have at least one action.")
+            });
+        }
+        for (idx, action) in self.get_action().iter().enumerate() {
+            if action.has_escalate() {
+                validator.field(format!("action[{}]", idx), |validator| {
+                    validator.error("Escalation ladders cannot escalate or deescalate.")
+                });
+            }
+        }
+        validator.each("action", self.get_action());
+        if self.has_notification() {
+            validator.template("notification", self.get_notification());
+        }
+    }
+}
+
+impl Validate for MessageFilterRule {
+    fn validate(&self, validator: &mut Validator) {
+        match self.criteria.as_ref() {
+            Some(criteria) => validator.child("criteria", criteria),
+            None => validator.field("criteria", |validator| {
+                validator.error("Every rule must have criteria.")
+            }),
+        }
+        validator.each("additional_actions", self.get_additional_actions());
+    }
+}
+
+impl Validate for MessageFilterRule_Criteria {
+    fn validate(&self, validator: &mut Validator) {
+        validator.patterns("matches", self.get_matches());
+        validator.channels("excluded_channels", self.get_excluded_channels());
+    }
+}
+
+// ------------------------------------------------------------------------------
+// Auto Configs
+// ------------------------------------------------------------------------------
+
+impl Validate for AutoConfig {
+    fn validate(&self, validator: &mut Validator) {
+        if let Some(group) = self.guild_events.as_ref() {
+            validator.child("guild_events", group);
+        }
+        let mut channel_events: Vec<_> = self.get_channel_events().iter().collect();
+        channel_events.sort_by_key(|(id, _)| *id);
+        for (id, group) in channel_events {
+            let name = format!("channel_events[{}]", id);
+            match id.parse::<u64>() {
+                Ok(channel_id) => {
+                    validator.channel(&name, channel_id);
+                    validator.child(name, group);
+                }
+                Err(_) => validator.field(name, |validator| {
+                    validator.error(format!("`{}` is not a valid channel ID.", id))
+                }),
+            }
+        }
+        validator.each("scheduled_events", self.get_scheduled_events());
+    }
+}
+
+impl Validate for ScheduledEvent {
+    fn validate(&self, validator: &mut Validator) {
+        if let Err(err) = self.get_schedule().parse::<Schedule>() {
+            validator.field("schedule", |validator| validator.error(err.to_string()));
+        }
+        validator.each("action", self.get_action());
+        validator.roles("target_role_ids", self.get_target_role_ids());
+    }
+}
+
+impl Validate for EventGroup {
+    fn validate(&self, validator: &mut Validator) {
+        validator.each("on_message", self.get_on_message());
+        validator.each("on_join", self.get_on_join());
+        validator.each("on_leave", self.get_on_leave());
+        validator.each("on_ban", self.get_on_ban());
+        validator.each("on_verify", self.get_on_verify());
+        validator.each("on_message_delete", self.get_on_message_delete());
+        validator.each("on_reaction_add", self.get_on_reaction_add());
+        validator.each("on_voice_join", self.get_on_voice_join());
+        validator.each("on_voice_leave", self.get_on_voice_leave());
+        validator.each("on_nickname_change", self.get_on_nickname_change());
+        validator.each("on_role_gained", self.get_on_role_gained());
+        validator.each("on_role_lost", self.get_on_role_lost());
+        validator.each("on_timeout", self.get_on_timeout());
+    }
+}
+
+/// Validates the fields shared by every type of event.
+fn validate_event(
+    validator: &mut Validator,
+    actions: &[Action],
+    condition: Option<&Condition>,
+    cooldown: Option<&Cooldown>,
+) {
+    validator.each("action", actions);
+    if let Some(condition) = condition {
+        validator.child("condition", condition);
+    }
+    if let Some(cooldown) = cooldown {
+        validator.child("cooldown", cooldown);
+    }
+}
+
+impl Validate for MessageEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        if let Some(filter) = self.content_filter.as_ref() {
+            validator.child("content_filter", filter);
+        }
+    }
+}
+
+impl Validate for UserChangeEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        if let Some(filter) = self.username_filter.as_ref() {
+            validator.child("username_filter", filter);
+        }
+    }
+}
+
+impl Validate for MessageDeleteEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        if let Some(filter) = self.content_filter.as_ref() {
+            validator.child("content_filter", filter);
+        }
+    }
+}
+
+impl Validate for ReactionEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        if let Some(filter) = self.emoji_filter.as_ref() {
+            validator.child("emoji_filter", filter);
+        }
+        if let Some(filter) = self.username_filter.as_ref() {
+            validator.child("username_filter", filter);
+        }
+    }
+}
+
+impl Validate for VoiceEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        if let Some(filter) = self.username_filter.as_ref() {
+            validator.child("username_filter", filter);
+        }
+    }
+}
+
+impl Validate for NicknameChangeEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        if let Some(filter) = self.nickname_filter.as_ref() {
+            validator.child("nickname_filter", filter);
+        }
+    }
+}
+
+impl Validate for RoleChangeEvent {
+    fn validate(&self, validator: &mut Validator) {
+        validate_event(
+            validator,
+            self.get_action(),
+            self.condition.as_ref(),
+            self.cooldown.as_ref(),
+        );
+        validator.roles("role_ids", self.get_role_ids());
+    }
+}
+
+impl Validate for Cooldown {
+    fn validate(&self, validator: &mut Validator) {
+        if self.get_seconds() == 0 {
+            validator.field("seconds", |validator| {
+                validator.error("Cooldowns must last at least one second.")
+            });
+        }
+    }
+}
+
+fn validate_range<T: PartialOrd>(
+    validator: &mut Validator,
+    name: &str,
+    min: Option<T>,
+    max: Option<T>,
+) {
+    if let (Some(min), Some(max)) = (min, max)
+        && min > max
+    {
+        validator.field(name, |validator| {
+            validator.error("The minimum must not be greater than the maximum.")
+        });
+    }
+}
+
+impl Validate for Condition {
+    fn validate(&self, validator: &mut Validator) {
+        match self.condition.as_ref() {
+            Some(Condition_oneof_condition::all(list)) => {
+                validator.each("all.condition", list.get_condition())
+            }
+            Some(Condition_oneof_condition::any(list)) => {
+                validator.each("any.condition", list.get_condition())
+            }
+            Some(Condition_oneof_condition::none(list)) => {
+                validator.each("none.condition", list.get_condition())
+            }
+            Some(Condition_oneof_condition::account_age(range)) => validate_range(
+                validator,
+                "account_age",
+                range.has_min_seconds().then_some(range.get_min_seconds()),
+                range.has_max_seconds().then_some(range.get_max_seconds()),
+            ),
+            Some(Condition_oneof_condition::member_age(range)) => validate_range(
+                validator,
+                "member_age",
+                range.has_min_seconds().then_some(range.get_min_seconds()),
+                range.has_max_seconds().then_some(range.get_max_seconds()),
+            ),
+            Some(Condition_oneof_condition::has_role(roles)) => {
+                validator.roles("has_role.role_ids", roles.get_role_ids())
+            }
+            Some(Condition_oneof_condition::channel(channels)) => {
+                validator.channels("channel.channel_ids", channels.get_channel_ids())
+            }
+            Some(Condition_oneof_condition::attachment_count(range)) => validate_range(
+                validator,
+                "attachment_count",
+                range.has_min().then_some(range.get_min()),
+                range.has_max().then_some(range.get_max()),
+            ),
+            Some(Condition_oneof_condition::mention_count(range)) => validate_range(
+                validator,
+                "mention_count",
+                range.has_min().then_some(range.get_min()),
+                range.has_max().then_some(range.get_max()),
+            ),
+            Some(Condition_oneof_condition::regex(regex)) => {
+                validator.patterns("regex.patterns", regex.get_patterns())
+            }
+            None => {}
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------
+// Other Configs
+// ------------------------------------------------------------------------------
+
+impl Validate for MusicConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.roles("dj_role_id", self.get_dj_role_id());
+        if self.has_voice_channel_id() {
+            validator.channel("voice_channel_id", self.get_voice_channel_id());
+        }
+        validator.channels("text_channel_id", self.get_text_channel_id());
+    }
+}
+
+impl Validate for AnnouncementConfig {
+    fn validate(&self, validator: &mut Validator) {
+        let types = [
+            ("joins", self.joins.as_ref()),
+            ("leaves", self.leaves.as_ref()),
+            ("bans", self.bans.as_ref()),
+            ("streams", self.streams.as_ref()),
+            ("voice", self.voice.as_ref()),
+        ];
+        for (name, config) in types {
+            if let Some(config) = config {
+                validator.child(name, config);
+            }
+        }
+    }
+}
+
+impl Validate for AnnouncementTypeConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.channels("channel_ids", self.get_channel_ids());
+        for (idx, message) in self.get_messages().iter().enumerate() {
+            validator.template(&format!("messages[{}]", idx), message);
+        }
+    }
+}
+
+impl Validate for VerificationConfig {
+    fn validate(&self, validator: &mut Validator) {
+        if self.has_role_id() {
+            validator.role("role_id", self.get_role_id());
+        }
+        if self.has_kick_unvalidated_users_after()
+            && self.get_kick_unvalidated_users_after() < MIN_KICK_UNVALIDATED_SECONDS
+        {
+            validator.field("kick_unvalidated_users_after", |validator| {
+                validator.error(format!(
+                    "Must be at least {} seconds.",
+                    MIN_KICK_UNVALIDATED_SECONDS
+                ))
+            });
+        }
+        if let Some(filter) = self
+            .username
+            .as_ref()
+            .and_then(|username| username.username_filter.as_ref())
+        {
+            validator.child("username.username_filter", filter);
+        }
+    }
+}
+
+impl Validate for RoleConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.roles("self_serve_role_ids", self.get_self_serve_role_ids());
+        let mut role_ids: Vec<u64> = self.get_settings().keys().copied().collect();
+        role_ids.sort_unstable();
+        for role_id in role_ids {
+            validator.role(&format!("settings[{}]", role_id), role_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(config: &impl Validate, resources: Option<&GuildResources>) -> Vec<String> {
+        match validate(config, resources) {
+            Ok(()) => Vec::new(),
+            Err(InvalidConfig(errors)) => errors.into_iter().map(|err| err.path).collect(),
+        }
+    }
+
+    #[test]
+    fn changes_only_reject_new_problems() {
+        let resources = GuildResources {
+            channel_ids: HashSet::from([1]),
+            role_ids: HashSet::new(),
+        };
+        let mut previous = LoggingConfig::new();
+        previous.set_modlog_channel_id(2);
+        let mut config = previous.clone();
+        config.mut_edited_messages().set_output_channel_id(1);
+
+        let warnings = validate_change(&previous, &config, Some(&resources)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, "modlog_channel_id");
+
+        config.mut_deleted_messages().set_output_channel_id(3);
+        let err = validate_change(&previous, &config, Some(&resources)).unwrap_err();
+        assert_eq!(err.0.len(), 1);
+        assert_eq!(err.0[0].path, "deleted_messages.output_channel_id");
+    }
+
+    #[test]
+    fn durations_on_actions_that_cannot_be_undone() {
+        let mut rung = EscalationLadderRung::new();
+        rung.set_display_name("Kick".to_owned());
+        let mut action = Action::new();
+        action.mut_kick();
+        action.set_duration(60);
+        rung.mut_action().push(action);
+        let mut ladder = EscalationLadder::new();
+        ladder.mut_rung().push(rung);
+
+        assert_eq!(errors(&ladder, None), vec!["rung[0].action[0].duration"]);
+    }
+
+    #[test]
+    fn empty_ladders_and_rungs() {
+        let mut ladder = EscalationLadder::new();
+        assert_eq!(errors(&ladder, None), vec!["rung"]);
+
+        ladder.mut_rung().push(EscalationLadderRung::new());
+        assert_eq!(
+            errors(&ladder, None),
+            vec!["rung[0].display_name", "rung[0].action"]
+        );
+    }
+
+    #[test]
+    fn points_mode_thresholds() {
+        let mut ladder = EscalationLadder::new();
+        ladder.set_mode(EscalationMode::ESCALATION_MODE_POINTS);
+        for (name, threshold) in [("Warning", 1), ("Kick", 5), ("Ban", 5)] {
+            let mut rung = EscalationLadderRung::new();
+            rung.set_display_name(name.to_owned());
+            let mut action = Action::new();
+            action.mut_kick();
+            rung.mut_action().push(action);
+            rung.set_threshold(threshold);
+            ladder.mut_rung().push(rung);
+        }
+        ladder.mut_rung()[0].set_deescalation_period(60);
+
+        assert_eq!(
+            errors(&ladder, None),
+            vec!["rung[0].deescalation_period", "rung[2].threshold"]
+        );
+
+        ladder.mut_rung()[0].clear_deescalation_period();
+        ladder.mut_rung()[2].set_threshold(10);
+        assert!(errors(&ladder, None).is_empty());
+    }
+
+    #[test]
+    fn invalid_escalation_notifications() {
+        let mut rung = EscalationLadderRung::new();
+        rung.set_display_name("Kick".to_owned());
+        let mut action = Action::new();
+        action.mut_kick();
+        rung.mut_action().push(action);
+        rung.set_notification("You are now at {escalation.rung} until {expiry}".to_owned());
+        let mut ladder = EscalationLadder::new();
+        ladder.mut_rung().push(rung);
+        ladder.set_default_notification(String::new());
+
+        assert_eq!(
+            errors(&ladder, None),
+            vec!["rung[0].notification", "default_notification"]
+        );
+    }
+
+    #[test]
+    fn invalid_patterns_and_templates() {
+        let mut event = MessageEvent::new();
+        event
+            .mut_content_filter()
+            .mut_blacklist()
+            .push("(unclosed".to_owned());
+        let mut action = Action::new();
+        action
+            .mut_direct_message()
+            .set_content("Hi {usr}".to_owned());
+        event.mut_action().push(action);
+        let mut config = AutoConfig::new();
+        config.mut_guild_events().mut_on_message().push(event);
+
+        assert_eq!(
+            errors(&config, None),
+            vec![
+                "guild_events.on_message[0].action[0].direct_message.content",
+                "guild_events.on_message[0].content_filter.blacklist[0]",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_channels_and_roles() {
+        let resources = GuildResources {
+            channel_ids: [1].into_iter().collect(),
+            role_ids: [2].into_iter().collect(),
+        };
+        let mut config = VerificationConfig::new();
+        config.set_role_id(3);
+        assert_eq!(errors(&config, Some(&resources)), vec!["role_id"]);
+        // Existence is not checked without resources.
+        assert!(errors(&config, None).is_empty());
+
+        let mut config = LoggingConfig::new();
+        config.set_modlog_channel_id(1);
+        assert!(errors(&config, Some(&resources)).is_empty());
+        config.set_modlog_channel_id(4);
+        assert_eq!(errors(&config, Some(&resources)), vec!["modlog_channel_id"]);
+    }
+}
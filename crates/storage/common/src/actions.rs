@@ -1,25 +1,283 @@
-use crate::{Storage, escalation::EscalationManager};
+use crate::{
+    Storage, appeals,
+    cases::{self, CaseTarget},
+    escalation::EscalationManager,
+};
 use anyhow::Result;
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use futures::future::{BoxFuture, FutureExt};
 use hourai::{
     http::{self, request::AuditLogReason},
     models::{
+        guild::{Guild, Permissions},
+        http::permission_overwrite::{PermissionOverwrite, PermissionOverwriteType},
         id::{Id, marker::*},
         user::User,
+        util::Timestamp,
+    },
+    proto::{
+        action::*,
+        event::{ActionResult, Event, EventSource},
     },
-    proto::action::*,
+    util::{
+        template::{self, TemplateContext},
+        validation,
+    },
+};
+use hourai_sql::{EventLog, Member, PendingAction};
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration as StdDuration,
 };
-use hourai_sql::{Member, PendingAction};
-use std::{collections::HashSet, sync::Arc};
+use thiserror::Error;
 
 const SECONDS_IN_DAY: u32 = 24 * 60 * 60;
 
+/// The longest Discord allows a user to be timed out for.
+const MAX_TIMEOUT_SECONDS: u64 = 28 * SECONDS_IN_DAY as u64;
+
+/// The longest slowmode Discord allows.
+const MAX_SLOWMODE_SECONDS: u32 = 6 * 60 * 60;
+
+/// The permissions denied to @everyone in locked channels.
+const LOCKED_PERMISSIONS: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::CREATE_PRIVATE_THREADS)
+    .union(Permissions::ADD_REACTIONS);
+
+/// Reasons an action is refused before it is sent to Discord.
+#[derive(Debug, Error)]
+pub enum ActionError {
+    #[error("Bot is missing permission: `{0:?}`")]
+    BotMissingPermission(Permissions),
+    #[error("Authorizer is missing permission: `{0:?}`")]
+    AuthorizerMissingPermission(Permissions),
+    #[error("Has higher or equal roles than the bot, cannot be moderated by it.")]
+    OutranksBot,
+    #[error("Has higher or equal roles, not authorized to moderate them.")]
+    OutranksAuthorizer,
+    #[error("The server owner cannot be moderated.")]
+    TargetIsOwner,
+    #[error("Role {0} is higher than or equal to the bot's highest role.")]
+    RoleAboveBot(Id<RoleMarker>),
+}
+
+/// A change an `ActionExecutor` makes to Discord or the database while running actions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Kick {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+    },
+    Ban {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        delete_message_days: u32,
+    },
+    Unban {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+    },
+    SetMute {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        mute: bool,
+    },
+    SetDeafen {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        deafen: bool,
+    },
+    SetRoles {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        role_ids: Vec<Id<RoleMarker>>,
+    },
+    SetTimeout {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        until: Option<Timestamp>,
+    },
+    SetNickname {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        nickname: Option<String>,
+    },
+    SetSlowmode {
+        channel_id: Id<ChannelMarker>,
+        seconds: u16,
+    },
+    SetChannelPermissions {
+        channel_id: Id<ChannelMarker>,
+        allow: Permissions,
+        deny: Permissions,
+    },
+    SendDirectMessage {
+        user_id: Id<UserMarker>,
+        content: String,
+    },
+    SendMessage {
+        channel_id: Id<ChannelMarker>,
+        content: String,
+    },
+    DeleteMessages {
+        channel_id: Id<ChannelMarker>,
+        message_ids: Vec<Id<MessageMarker>>,
+    },
+    RecordCase {
+        guild_id: Id<GuildMarker>,
+        target: CaseTarget,
+        action: &'static str,
+    },
+    ScheduleUndo {
+        action: Action,
+        timestamp: DateTime<Utc>,
+    },
+    RecordEscalation {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        level: i64,
+        display_name: String,
+    },
+    PardonEscalation {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        entry_number: usize,
+    },
+    OfferAppeal {
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+    },
+    RecordEvent {
+        guild_id: Id<GuildMarker>,
+    },
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kick { user_id, .. } => write!(f, "Kick <@{}>", user_id),
+            Self::Ban {
+                user_id,
+                delete_message_days,
+                ..
+            } => {
+                write!(f, "Ban <@{}>", user_id)?;
+                if *delete_message_days > 0 {
+                    write!(f, " and delete {} days of messages", delete_message_days)?;
+                }
+                Ok(())
+            }
+            Self::Unban { user_id, .. } => write!(f, "Unban <@{}>", user_id),
+            Self::SetMute { user_id, mute, .. } => {
+                let verb = if *mute { "Mute" } else { "Unmute" };
+                write!(f, "{} <@{}>", verb, user_id)
+            }
+            Self::SetDeafen {
+                user_id, deafen, ..
+            } => {
+                let verb = if *deafen { "Deafen" } else { "Undeafen" };
+                write!(f, "{} <@{}>", verb, user_id)
+            }
+            Self::SetRoles {
+                user_id, role_ids, ..
+            } => {
+                if role_ids.is_empty() {
+                    return write!(f, "Remove all roles from <@{}>", user_id);
+                }
+                let roles: Vec<String> = role_ids.iter().map(|id| format!("<@&{}>", id)).collect();
+                write!(f, "Set the roles of <@{}> to {}", user_id, roles.join(", "))
+            }
+            Self::SetTimeout { user_id, until, .. } => match until {
+                Some(until) => write!(f, "Time out <@{}> until <t:{}:f>", user_id, until.as_secs()),
+                None => write!(f, "Remove the timeout of <@{}>", user_id),
+            },
+            Self::SetNickname {
+                user_id, nickname, ..
+            } => match nickname {
+                Some(nickname) => {
+                    write!(f, "Change the nickname of <@{}> to `{}`", user_id, nickname)
+                }
+                None => write!(f, "Clear the nickname of <@{}>", user_id),
+            },
+            Self::SetSlowmode {
+                channel_id,
+                seconds,
+            } => write!(
+                f,
+                "Set the slowmode of <#{}> to {} seconds",
+                channel_id, seconds
+            ),
+            Self::SetChannelPermissions { channel_id, .. } => {
+                write!(
+                    f,
+                    "Change the permissions of @everyone in <#{}>",
+                    channel_id
+                )
+            }
+            Self::SendDirectMessage { user_id, content } => {
+                write!(f, "Send <@{}> a direct message: {}", user_id, content)
+            }
+            Self::SendMessage {
+                channel_id,
+                content,
+            } => write!(f, "Send a message in <#{}>: {}", channel_id, content),
+            Self::DeleteMessages {
+                channel_id,
+                message_ids,
+            } => write!(
+                f,
+                "Delete {} messages in <#{}>",
+                message_ids.len(),
+                channel_id
+            ),
+            Self::RecordCase { target, action, .. } => {
+                write!(f, "Record a case for {}: {}", target, action)
+            }
+            Self::ScheduleUndo { timestamp, .. } => {
+                write!(f, "Undo the action <t:{}:R>", timestamp.timestamp())
+            }
+            Self::RecordEscalation {
+                user_id,
+                level,
+                display_name,
+                ..
+            } => write!(
+                f,
+                "Move <@{}> to escalation level {}: {}",
+                user_id, level, display_name
+            ),
+            Self::PardonEscalation {
+                user_id,
+                entry_number,
+                ..
+            } => write!(
+                f,
+                "Pardon escalation entry {} of <@{}>",
+                entry_number, user_id
+            ),
+            Self::OfferAppeal { user_id, .. } => {
+                write!(f, "Offer <@{}> a chance to appeal", user_id)
+            }
+            Self::RecordEvent { .. } => write!(f, "Record the event in the event log"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ActionExecutor {
     current_user: User,
     http: Arc<http::Client>,
     storage: Storage,
+    /// If set, operations are recorded here instead of being performed.
+    dry_run: Option<Arc<Mutex<Vec<Operation>>>>,
+    /// If unset, bans do not offer the banned user an appeal.
+    offer_appeals: bool,
+    /// If unset, actions that moderate a user do not record a case.
+    record_cases: bool,
 }
 
 impl ActionExecutor {
@@ -28,6 +286,73 @@ impl ActionExecutor {
             current_user,
             http,
             storage,
+            dry_run: None,
+            offer_appeals: true,
+            record_cases: true,
+        }
+    }
+
+    /// Creates a copy of the executor whose bans do not offer the banned user an appeal. Used
+    /// when the caller offers one appeal covering several actions itself.
+    pub fn without_appeals(&self) -> Self {
+        Self {
+            offer_appeals: false,
+            ..self.clone()
+        }
+    }
+
+    /// Creates a copy of the executor whose actions do not record cases. Used for actions that
+    /// follow from an earlier one, like scheduled undos and escalation rungs, which are already
+    /// recorded elsewhere.
+    pub fn without_cases(&self) -> Self {
+        Self {
+            record_cases: false,
+            ..self.clone()
+        }
+    }
+
+    /// Creates a copy of the executor that records the operations it would perform instead of
+    /// performing them. Nothing is written to Discord, Redis or the database. Reads still
+    /// happen, as they decide what would be done: the role hierarchy and permission checks read
+    /// the cache, escalations read the user's history, and toggles fetch the member's current
+    /// state.
+    pub fn dry_run(&self) -> Self {
+        Self {
+            dry_run: Some(Arc::default()),
+            ..self.clone()
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    /// Takes the operations recorded so far by a dry run, in the order they would have been
+    /// performed. Always empty if the executor is not a dry run.
+    pub fn take_operations(&self) -> Vec<Operation> {
+        match self.dry_run.as_ref() {
+            Some(operations) => std::mem::take(
+                &mut *operations
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    /// Records an operation if the executor is a dry run. Returns true if the operation was
+    /// recorded, in which case it must not be performed.
+    pub fn record_dry_run(&self, operation: impl FnOnce() -> Operation) -> bool {
+        match self.dry_run.as_ref() {
+            Some(operations) => {
+                operations
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push(operation());
+                true
+            }
+            None => false,
         }
     }
 
@@ -46,8 +371,68 @@ impl ActionExecutor {
         &self.storage
     }
 
-    #[expect(clippy::expect_used)]
     pub async fn execute_action(&self, action: &Action) -> Result<()> {
+        self.execute_action_with_context(action, &TemplateContext::default())
+            .await
+    }
+
+    /// Executes a batch of actions on the bot's own behalf and records them in the guild's event
+    /// log, along with their source and the result of each action. Failed actions do not stop
+    /// the rest of the batch from running.
+    pub async fn execute_event(
+        &self,
+        guild_id: Id<GuildMarker>,
+        mut source: EventSource,
+        actions: Vec<Action>,
+        ctx: &TemplateContext,
+    ) -> Event {
+        let mut results = Vec::with_capacity(actions.len());
+        for action in actions.iter() {
+            let mut result = ActionResult::new();
+            match self.execute_action_with_context(action, ctx).await {
+                Ok(()) => result.set_success(true),
+                Err(err) => {
+                    tracing::error!("Error while executing an automated action: {}", err);
+                    result.set_success(false);
+                    result.set_error(err.to_string());
+                }
+            }
+            results.push(result);
+        }
+
+        source.set_guild_id(guild_id.get());
+        source.set_executor_user_id(self.current_user.id.get());
+        source.set_timestamp(Utc::now().timestamp_millis() as u64);
+        let mut event = Event::new();
+        event.set_source(source);
+        event.set_actions(actions.into());
+        event.set_results(results.into());
+
+        if self.record_dry_run(|| Operation::RecordEvent { guild_id }) {
+            return event;
+        }
+        if let Err(err) = EventLog::insert(guild_id, event.clone())
+            .execute(self.storage().sql())
+            .await
+        {
+            tracing::error!("Failed to record event for guild {}: {}", guild_id, err);
+        }
+        event
+    }
+
+    /// Executes an action. Templated message content is rendered with the provided context,
+    /// supplemented with information from the action itself.
+    #[expect(clippy::expect_used)]
+    pub async fn execute_action_with_context(
+        &self,
+        action: &Action,
+        ctx: &TemplateContext,
+    ) -> Result<()> {
+        if action.has_duration() && !validation::can_undo(action) {
+            anyhow::bail!("This type of action cannot be undone, so it cannot have a duration.");
+        }
+        self.check_authorization(action).await?;
+        let action = &self.capture_previous_state(action).await?;
         let details = action
             .details
             .as_ref()
@@ -61,8 +446,18 @@ impl ActionExecutor {
             Action_oneof_details::change_role(info) => {
                 self.execute_change_role(action, info).await?
             }
+            Action_oneof_details::timeout(info) => self.execute_timeout(action, info).await?,
+            Action_oneof_details::set_nickname(info) => {
+                self.execute_set_nickname(action, info).await?
+            }
+            Action_oneof_details::set_slowmode(info) => {
+                self.execute_set_slowmode(action, info).await?
+            }
+            Action_oneof_details::lock_channel(info) => {
+                self.execute_lock_channel(action, info).await?
+            }
             Action_oneof_details::direct_message(info) => {
-                if let Err(err) = self.execute_direct_message(action, info).await {
+                if let Err(err) = self.execute_direct_message(action, info, ctx).await {
                     tracing::error!(
                         "Error while sending a message to a given channel for an action: {}",
                         err
@@ -70,7 +465,7 @@ impl ActionExecutor {
                 }
             }
             Action_oneof_details::send_message(info) => {
-                if let Err(err) = self.execute_send_message(info).await {
+                if let Err(err) = self.execute_send_message(action, info, ctx).await {
                     tracing::error!(
                         "Error while sending a message to a given channel for an action: {}",
                         err
@@ -87,33 +482,270 @@ impl ActionExecutor {
             }
         };
 
-        // Schedule undo if a duration is set
-        if action.has_duration() {
+        let case_number = match self.record_case(action).await {
+            Ok(case_number) => case_number,
+            Err(err) => {
+                tracing::error!("Failed to record a case for an action: {}", err);
+                None
+            }
+        };
+
+        // Schedule undo if a duration is set. Discord lifts timeouts on its own.
+        if action.has_duration() && !matches!(details, Action_oneof_details::timeout(_)) {
             let timestamp = Utc::now() + Duration::seconds(action.get_duration() as i64);
             let mut undo = action.clone();
             Self::invert_action(&mut undo);
             undo.clear_duration();
-            PendingAction::schedule(undo, timestamp)
+            // Undoing the action is done by the bot, not the original moderator.
+            undo.clear_authorizer_id();
+            if self.record_dry_run(|| Operation::ScheduleUndo {
+                action: undo.clone(),
+                timestamp,
+            }) {
+                return Ok(());
+            }
+            let moderator_id = action
+                .has_authorizer_id()
+                .then(|| Id::new(action.get_authorizer_id()));
+            PendingAction::schedule(undo, timestamp, moderator_id, case_number)
                 .execute(self.storage().sql())
                 .await?;
         }
         Ok(())
     }
 
-    #[expect(clippy::expect_used)]
-    fn invert_action(action: &mut Action) {
-        let details = action
-            .details
-            .as_mut()
-            .expect("Cannot invert action without a specified type");
+    /// Gets the permissions the bot and the authorizer of an action need for it to run.
+    fn required_permissions(details: &Action_oneof_details) -> Permissions {
+        match details {
+            Action_oneof_details::kick(_) => Permissions::KICK_MEMBERS,
+            Action_oneof_details::ban(_) => Permissions::BAN_MEMBERS,
+            Action_oneof_details::mute(_) => Permissions::MUTE_MEMBERS,
+            Action_oneof_details::deafen(_) => Permissions::DEAFEN_MEMBERS,
+            Action_oneof_details::change_role(_) => Permissions::MANAGE_ROLES,
+            Action_oneof_details::timeout(_) => Permissions::MODERATE_MEMBERS,
+            Action_oneof_details::set_nickname(_) => Permissions::MANAGE_NICKNAMES,
+            Action_oneof_details::set_slowmode(_) => Permissions::MANAGE_CHANNELS,
+            Action_oneof_details::lock_channel(_) => {
+                Permissions::MANAGE_CHANNELS | Permissions::MANAGE_ROLES
+            }
+            Action_oneof_details::delete_messages(_) => Permissions::MANAGE_MESSAGES,
+            _ => Permissions::empty(),
+        }
+    }
+
+    /// Checks if an action moderates its user, and so must respect the role hierarchy.
+    fn targets_member(details: &Action_oneof_details) -> bool {
+        matches!(
+            details,
+            Action_oneof_details::kick(_)
+                | Action_oneof_details::ban(_)
+                | Action_oneof_details::mute(_)
+                | Action_oneof_details::deafen(_)
+                | Action_oneof_details::change_role(_)
+                | Action_oneof_details::timeout(_)
+                | Action_oneof_details::set_nickname(_)
+        )
+    }
+
+    /// Gets the roles of a user, if they are currently a member of the guild.
+    async fn member_roles(
+        &self,
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+    ) -> Result<Option<Vec<Id<RoleMarker>>>> {
+        Ok(Member::fetch(guild_id, user_id)
+            .fetch_optional(self.storage().sql())
+            .await?
+            .filter(|member| member.present)
+            .map(|member| member.role_ids().collect()))
+    }
+
+    /// Fetches a member's roles from Discord, for members missing from the cache. Users that are
+    /// not in the guild have no roles.
+    async fn fetch_member_roles(
+        &self,
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+    ) -> Result<Vec<Id<RoleMarker>>> {
+        use http::error::ErrorType;
+        let not_found = |err: &http::Error| matches!(err.kind(), ErrorType::Response { status, .. } if status.get() == 404);
+        match self.http.guild_member(guild_id, user_id).await {
+            Ok(response) => Ok(response.model().await?.roles),
+            Err(err) if not_found(&err) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Checks that the bot, and the action's authorizer if it has one, have the permissions the
+    /// action needs and outrank the user it moderates. Guilds, the bot and targets missing from
+    /// the cache are left for Discord to check. Authorizers missing from it are fetched, as
+    /// Discord does not check them.
+    async fn check_authorization(&self, action: &Action) -> Result<()> {
+        let details = match action.details.as_ref() {
+            Some(details) => details,
+            None => return Ok(()),
+        };
+        let required = Self::required_permissions(details);
+        if required.is_empty() {
+            return Ok(());
+        }
+
+        let guild_id = Id::new(action.get_guild_id());
+        let mut guild = self.storage().redis().guild(guild_id);
+        let owner_id = match guild.fetch_resource::<Guild>(guild_id).await? {
+            Some(guild) => Id::new(guild.get_owner_id()),
+            None => return Ok(()),
+        };
+        let bot_roles = match self.member_roles(guild_id, self.current_user.id).await? {
+            Some(roles) => roles,
+            None => return Ok(()),
+        };
+        let bot_permissions = guild
+            .guild_permissions(self.current_user.id, bot_roles.iter().copied())
+            .await?;
+        if !bot_permissions.contains(required) {
+            anyhow::bail!(ActionError::BotMissingPermission(
+                required - bot_permissions
+            ));
+        }
+        let bot_roles = guild.role_set(&bot_roles).await?;
+
+        // Actions authorized by the owner or by the bot itself only need to satisfy the bot.
+        let authorizer_id = Some(action.get_authorizer_id())
+            .filter(|_| action.has_authorizer_id())
+            .map(Id::new)
+            .filter(|id| *id != owner_id && *id != self.current_user.id);
+        let authorizer_roles = match authorizer_id {
+            Some(authorizer_id) => {
+                let roles = match self.member_roles(guild_id, authorizer_id).await? {
+                    Some(roles) => roles,
+                    None => self.fetch_member_roles(guild_id, authorizer_id).await?,
+                };
+                let permissions = guild
+                    .guild_permissions(authorizer_id, roles.iter().copied())
+                    .await?;
+                if !permissions.contains(required) {
+                    anyhow::bail!(ActionError::AuthorizerMissingPermission(
+                        required - permissions
+                    ));
+                }
+                Some(guild.role_set(&roles).await?)
+            }
+            None => None,
+        };
+
+        if Self::targets_member(details) {
+            let user_id = Id::new(action.get_user_id());
+            if user_id == owner_id {
+                anyhow::bail!(ActionError::TargetIsOwner);
+            }
+            if let Some(roles) = self.member_roles(guild_id, user_id).await? {
+                let roles = guild.role_set(&roles).await?;
+                if roles >= bot_roles {
+                    anyhow::bail!(ActionError::OutranksBot);
+                }
+                if authorizer_roles.is_some_and(|authorizer| roles >= authorizer) {
+                    anyhow::bail!(ActionError::OutranksAuthorizer);
+                }
+            }
+        }
+
+        if let Action_oneof_details::change_role(info) = details {
+            for role_id in info.get_role_ids().iter().copied().map(Id::new) {
+                if guild.role_set(&[role_id]).await? >= bot_roles {
+                    anyhow::bail!(ActionError::RoleAboveBot(role_id));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fills in the state an action with a duration overwrites, so that it can be restored when
+    /// the action is undone.
+    async fn capture_previous_state(&self, action: &Action) -> Result<Action> {
+        let mut action = action.clone();
+        if !action.has_duration() {
+            return Ok(action);
+        }
+        let guild_id = Id::new(action.get_guild_id());
+        let user_id = Id::new(action.get_user_id());
+        match action.details.as_mut() {
+            Some(Action_oneof_details::set_nickname(info)) if !info.has_previous_nickname() => {
+                let member = self
+                    .http
+                    .guild_member(guild_id, user_id)
+                    .await?
+                    .model()
+                    .await?;
+                info.set_previous_nickname(member.nick.unwrap_or_default());
+            }
+            Some(Action_oneof_details::set_slowmode(info)) if !info.has_previous_seconds() => {
+                let channel_id = Id::new(info.get_channel_id());
+                let channel = self.http.channel(channel_id).await?.model().await?;
+                info.set_previous_seconds(u32::from(channel.rate_limit_per_user.unwrap_or(0)));
+            }
+            Some(Action_oneof_details::lock_channel(info))
+                if !info.has_previous_allow() && !info.has_previous_deny() =>
+            {
+                let channel_id = Id::new(info.get_channel_id());
+                let (allow, deny) = self.everyone_overwrite(guild_id, channel_id).await?;
+                info.set_previous_allow(allow.bits());
+                info.set_previous_deny(deny.bits());
+            }
+            _ => {}
+        }
+        Ok(action)
+    }
+
+    /// Records a case for an action that moderates a user or channel. Actions without an authorizer are
+    /// recorded as taken by the bot. Returns the number of the recorded case, if one was.
+    async fn record_case(&self, action: &Action) -> Result<Option<i32>> {
+        let name = match cases::case_action(action) {
+            Some(name) if self.record_cases => name,
+            _ => return Ok(None),
+        };
+        let guild_id = Id::new(action.get_guild_id());
+        let target = CaseTarget::of(action);
+        if self.record_dry_run(|| Operation::RecordCase {
+            guild_id,
+            target,
+            action: name,
+        }) {
+            return Ok(None);
+        }
+        let moderator_id = if action.has_authorizer_id() {
+            Id::new(action.get_authorizer_id())
+        } else {
+            self.current_user.id
+        };
+        let case = cases::new_case(
+            guild_id,
+            target,
+            moderator_id,
+            name,
+            Some(action.get_reason()),
+            action
+                .has_duration()
+                .then(|| StdDuration::from_secs(action.get_duration())),
+        );
+        let case = cases::record_case(&self.http, &self.storage, case).await?;
+        Ok(Some(case.case_number))
+    }
+
+    /// Turns an action into the action that undoes it. Actions are checked with
+    /// `validation::can_undo` before they are run, so anything else is left unchanged.
+    pub(crate) fn invert_action(action: &mut Action) {
+        let details = match action.details.as_mut() {
+            Some(details) => details,
+            None => return,
+        };
         match details {
             Action_oneof_details::ban(info) => {
                 info.set_field_type(match info.get_field_type() {
                     BanMember_Type::BAN => BanMember_Type::UNBAN,
                     BanMember_Type::UNBAN => BanMember_Type::BAN,
-                    BanMember_Type::SOFTBAN => {
-                        Option::<BanMember_Type>::None.expect("Cannot invert a softban")
-                    }
+                    BanMember_Type::SOFTBAN => BanMember_Type::SOFTBAN,
                 });
             }
             Action_oneof_details::escalate(info) => {
@@ -128,9 +760,27 @@ impl ActionExecutor {
             Action_oneof_details::change_role(info) => {
                 info.set_field_type(Self::invert_status(info.get_field_type()));
             }
-            _ => {
-                Option::<()>::None.expect("Cannot invert action");
+            Action_oneof_details::timeout(info) => {
+                info.set_field_type(Self::invert_status(info.get_field_type()));
+            }
+            Action_oneof_details::set_nickname(info) => {
+                let previous = info.take_previous_nickname();
+                let nickname = info.take_nickname();
+                info.set_nickname(previous);
+                info.set_previous_nickname(nickname);
+            }
+            Action_oneof_details::set_slowmode(info) => {
+                let previous = info.get_previous_seconds();
+                info.set_previous_seconds(info.get_seconds());
+                info.set_seconds(previous);
             }
+            Action_oneof_details::lock_channel(info) => {
+                info.set_field_type(Self::invert_status(info.get_field_type()));
+                if info.has_previous_allow() || info.has_previous_deny() {
+                    info.set_restore_previous(true);
+                }
+            }
+            _ => {}
         }
     }
 
@@ -145,6 +795,9 @@ impl ActionExecutor {
     async fn execute_kick(&self, action: &Action) -> Result<()> {
         let guild_id = Id::new(action.get_guild_id());
         let user_id = Id::new(action.get_user_id());
+        if self.record_dry_run(|| Operation::Kick { guild_id, user_id }) {
+            return Ok(());
+        }
         self.http
             .remove_guild_member(guild_id, user_id)
             .reason(action.get_reason())
@@ -155,14 +808,44 @@ impl ActionExecutor {
     async fn execute_ban(&self, action: &Action, info: &BanMember) -> Result<()> {
         let guild_id = Id::new(action.get_guild_id());
         let user_id = Id::new(action.get_user_id());
-        if info.get_field_type() != BanMember_Type::UNBAN {
-            self.http
+        // Must happen before the ban, as the user can no longer be messaged afterwards. The
+        // offer is withdrawn if the ban fails.
+        let mut offer = None;
+        if info.get_field_type() == BanMember_Type::BAN && self.offer_appeals {
+            let mut actions = ActionSet::new();
+            actions.mut_action().push(action.clone());
+            offer = appeals::offer(self, guild_id, user_id, actions, "banned from")
+                .await
+                .unwrap_or_else(|err| {
+                    tracing::error!("Failed to offer an appeal for a ban: {}", err);
+                    None
+                });
+        }
+        if info.get_field_type() != BanMember_Type::UNBAN
+            && !self.record_dry_run(|| Operation::Ban {
+                guild_id,
+                user_id,
+                delete_message_days: info.get_delete_message_days(),
+            })
+        {
+            let result = self
+                .http
                 .create_ban(guild_id, user_id)
                 .reason(action.get_reason())
                 .delete_message_seconds(info.get_delete_message_days() * SECONDS_IN_DAY)
-                .await?;
+                .await;
+            if let Err(err) = result {
+                if let Some(offer) = offer
+                    && let Err(err) = appeals::withdraw(self, offer).await
+                {
+                    tracing::error!("Failed to withdraw the appeal of a failed ban: {}", err);
+                }
+                return Err(err.into());
+            }
         }
-        if info.get_field_type() != BanMember_Type::BAN {
+        if info.get_field_type() != BanMember_Type::BAN
+            && !self.record_dry_run(|| Operation::Unban { guild_id, user_id })
+        {
             self.http
                 .delete_ban(guild_id, user_id)
                 .reason(action.get_reason())
@@ -212,6 +895,13 @@ impl ActionExecutor {
             }
         };
 
+        if self.record_dry_run(|| Operation::SetMute {
+            guild_id,
+            user_id,
+            mute,
+        }) {
+            return Ok(());
+        }
         self.http
             .update_guild_member(guild_id, user_id)
             .mute(mute)
@@ -238,6 +928,13 @@ impl ActionExecutor {
             }
         };
 
+        if self.record_dry_run(|| Operation::SetDeafen {
+            guild_id,
+            user_id,
+            deafen,
+        }) {
+            return Ok(());
+        }
         self.http
             .update_guild_member(guild_id, user_id)
             .deaf(deafen)
@@ -280,6 +977,13 @@ impl ActionExecutor {
         };
 
         let roles: Vec<Id<RoleMarker>> = roles.into_iter().collect();
+        if self.record_dry_run(|| Operation::SetRoles {
+            guild_id,
+            user_id,
+            role_ids: roles.clone(),
+        }) {
+            return Ok(());
+        }
         self.http
             .update_guild_member(guild_id, user_id)
             .roles(&roles)
@@ -288,8 +992,183 @@ impl ActionExecutor {
         Ok(())
     }
 
-    async fn execute_direct_message(&self, action: &Action, info: &DirectMessage) -> Result<()> {
+    async fn execute_timeout(&self, action: &Action, info: &TimeoutMember) -> Result<()> {
+        let guild_id = Id::new(action.get_guild_id());
+        let user_id = Id::new(action.get_user_id());
+        let apply = match info.get_field_type() {
+            StatusType::APPLY => true,
+            StatusType::UNAPPLY => false,
+            StatusType::TOGGLE => {
+                let member = self
+                    .http
+                    .guild_member(guild_id, user_id)
+                    .await?
+                    .model()
+                    .await?;
+                !hourai::cache::is_timed_out(member.communication_disabled_until)
+            }
+        };
+
+        let until = if apply {
+            let duration = action.get_duration();
+            if duration == 0 || duration > MAX_TIMEOUT_SECONDS {
+                anyhow::bail!("Timeouts must have a duration between 1 second and 28 days.");
+            }
+            let until = Utc::now().timestamp() + duration as i64;
+            Some(Timestamp::from_secs(until)?)
+        } else {
+            None
+        };
+
+        if self.record_dry_run(|| Operation::SetTimeout {
+            guild_id,
+            user_id,
+            until,
+        }) {
+            return Ok(());
+        }
+        self.http
+            .update_guild_member(guild_id, user_id)
+            .communication_disabled_until(until)
+            .reason(action.get_reason())
+            .await?;
+        Ok(())
+    }
+
+    async fn execute_set_nickname(&self, action: &Action, info: &SetNickname) -> Result<()> {
+        let guild_id = Id::new(action.get_guild_id());
+        let user_id = Id::new(action.get_user_id());
+        let nickname = Some(info.get_nickname()).filter(|nickname| !nickname.is_empty());
+        if self.record_dry_run(|| Operation::SetNickname {
+            guild_id,
+            user_id,
+            nickname: nickname.map(String::from),
+        }) {
+            return Ok(());
+        }
+        self.http
+            .update_guild_member(guild_id, user_id)
+            .nick(nickname)
+            .reason(action.get_reason())
+            .await?;
+        Ok(())
+    }
+
+    async fn execute_set_slowmode(&self, action: &Action, info: &SetSlowmode) -> Result<()> {
+        let channel_id = Id::new(info.get_channel_id());
+        let seconds = info.get_seconds().min(MAX_SLOWMODE_SECONDS) as u16;
+        if self.record_dry_run(|| Operation::SetSlowmode {
+            channel_id,
+            seconds,
+        }) {
+            return Ok(());
+        }
+        self.http
+            .update_channel(channel_id)
+            .rate_limit_per_user(seconds)
+            .reason(action.get_reason())
+            .await?;
+        Ok(())
+    }
+
+    /// Gets the permissions allowed and denied to @everyone by a channel's overwrite. Both are
+    /// empty if the channel has no overwrite for @everyone.
+    async fn everyone_overwrite(
+        &self,
+        guild_id: Id<GuildMarker>,
+        channel_id: Id<ChannelMarker>,
+    ) -> Result<(Permissions, Permissions)> {
+        let channel = self.http.channel(channel_id).await?.model().await?;
+        // The @everyone role shares its ID with the guild.
+        let everyone = guild_id.cast();
+        Ok(channel
+            .permission_overwrites
+            .unwrap_or_default()
+            .into_iter()
+            .find(|overwrite| overwrite.id == everyone)
+            .map(|overwrite| (overwrite.allow, overwrite.deny))
+            .unwrap_or_else(|| (Permissions::empty(), Permissions::empty())))
+    }
+
+    async fn execute_lock_channel(&self, action: &Action, info: &LockChannel) -> Result<()> {
+        let guild_id: Id<GuildMarker> = Id::new(action.get_guild_id());
+        let channel_id = Id::new(info.get_channel_id());
+        let everyone = guild_id.cast();
+
+        let (allow, deny) = if info.get_restore_previous() {
+            (
+                Permissions::from_bits_truncate(info.get_previous_allow()),
+                Permissions::from_bits_truncate(info.get_previous_deny()),
+            )
+        } else {
+            let (allow, deny) = self.everyone_overwrite(guild_id, channel_id).await?;
+            let lock = match info.get_field_type() {
+                StatusType::APPLY => true,
+                StatusType::UNAPPLY => false,
+                StatusType::TOGGLE => !deny.contains(LOCKED_PERMISSIONS),
+            };
+            if lock {
+                (allow - LOCKED_PERMISSIONS, deny | LOCKED_PERMISSIONS)
+            } else {
+                (allow, deny - LOCKED_PERMISSIONS)
+            }
+        };
+
+        if self.record_dry_run(|| Operation::SetChannelPermissions {
+            channel_id,
+            allow,
+            deny,
+        }) {
+            return Ok(());
+        }
+        let overwrite = PermissionOverwrite {
+            allow: Some(allow),
+            deny: Some(deny),
+            id: everyone,
+            kind: PermissionOverwriteType::Role,
+        };
+        self.http
+            .update_channel_permission(channel_id, &overwrite)
+            .reason(action.get_reason())
+            .await?;
+        Ok(())
+    }
+
+    async fn render_content(
+        &self,
+        action: &Action,
+        content: &str,
+        ctx: &TemplateContext,
+    ) -> Result<String> {
+        let mut ctx = ctx.clone();
+        if action.has_user_id() {
+            ctx.user_id.get_or_insert(Id::new(action.get_user_id()));
+        }
+        if action.has_reason() {
+            ctx.reason
+                .get_or_insert_with(|| action.get_reason().to_owned());
+        }
+        if action.has_guild_id() {
+            let guild_id = Id::new(action.get_guild_id());
+            crate::populate_template_context(guild_id, content, self.storage(), &mut ctx).await?;
+        }
+        Ok(template::render(content, &ctx))
+    }
+
+    async fn execute_direct_message(
+        &self,
+        action: &Action,
+        info: &DirectMessage,
+        ctx: &TemplateContext,
+    ) -> Result<()> {
         let user_id = Id::new(action.get_user_id());
+        let content = self.render_content(action, info.get_content(), ctx).await?;
+        if self.record_dry_run(|| Operation::SendDirectMessage {
+            user_id,
+            content: content.clone(),
+        }) {
+            return Ok(());
+        }
         let channel = self
             .http
             .create_private_channel(user_id)
@@ -299,22 +1178,42 @@ impl ActionExecutor {
 
         self.http
             .create_message(channel.id)
-            .content(info.get_content())
+            .content(&content)
             .await?;
         Ok(())
     }
 
-    async fn execute_send_message(&self, info: &SendMessage) -> Result<()> {
+    async fn execute_send_message(
+        &self,
+        action: &Action,
+        info: &SendMessage,
+        ctx: &TemplateContext,
+    ) -> Result<()> {
         let channel_id = Id::new(info.get_channel_id());
+        let content = self.render_content(action, info.get_content(), ctx).await?;
+        if self.record_dry_run(|| Operation::SendMessage {
+            channel_id,
+            content: content.clone(),
+        }) {
+            return Ok(());
+        }
         self.http
             .create_message(channel_id)
-            .content(info.get_content())
+            .content(&content)
             .await?;
         Ok(())
     }
 
     async fn execute_delete_messages(&self, info: &DeleteMessages) -> Result<()> {
         let channel_id = Id::new(info.get_channel_id());
+        if !info.message_ids.is_empty()
+            && self.record_dry_run(|| Operation::DeleteMessages {
+                channel_id,
+                message_ids: info.message_ids.iter().copied().map(Id::new).collect(),
+            })
+        {
+            return Ok(());
+        }
         match info.message_ids.len() {
             0 => Ok(()),
             1 => {
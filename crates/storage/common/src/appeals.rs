@@ -0,0 +1,442 @@
+use crate::{
+    Storage,
+    actions::{ActionExecutor, Operation},
+    cases,
+    escalation::EscalationManager,
+};
+use anyhow::Result;
+use chrono::Utc;
+use hourai::{
+    http,
+    interactions::proto_to_custom_id,
+    models::{
+        channel::message::{
+            Component,
+            component::{ActionRow, Button, ButtonStyle},
+        },
+        guild::Guild,
+        id::{Id, marker::*},
+        user::User,
+    },
+    proto::{
+        action::ActionSet,
+        guild_configs::ModerationConfig,
+        message_components::{AppealButton, AppealButtonOption, MessageComponentProto},
+    },
+    util::validation,
+};
+use hourai_sql::{Appeal, AppealStatus, Executor};
+use rand::{Rng, distributions::Alphanumeric};
+use std::time::Duration;
+use thiserror::Error;
+
+/// The length of the secret token used to submit appeals through the web API.
+const TOKEN_LENGTH: usize = 32;
+
+/// How long a moderator's claim on an appeal they are accepting lasts. Claims of instances that
+/// crashed while accepting an appeal can be claimed again after this.
+const ACCEPT_LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// The longest appeal a user can submit. Leaves room for the rest of the message posted to the
+/// appeals channel.
+pub const MAX_APPEAL_LENGTH: usize = 1500;
+
+#[derive(Debug, Error)]
+pub enum AppealError {
+    #[error("This appeal does not exist.")]
+    NotFound,
+    #[error("Appeals are not enabled on this server.")]
+    NotEnabled,
+    #[error("This appeal has already been submitted.")]
+    AlreadySubmitted,
+    #[error("This appeal has already been resolved.")]
+    AlreadyResolved,
+    #[error("An appeal cannot be empty.")]
+    Empty,
+    #[error("An appeal cannot be longer than {} characters.", MAX_APPEAL_LENGTH)]
+    TooLong,
+}
+
+/// Creates a button for an appeal.
+pub fn appeal_button(
+    appeal_id: i32,
+    option: AppealButtonOption,
+    label: &str,
+    style: ButtonStyle,
+) -> Result<Component> {
+    let mut button = AppealButton::new();
+    button.set_button_option(option);
+    button.set_appeal_id(appeal_id);
+
+    let mut proto = MessageComponentProto::new();
+    proto.set_appeal_button(button);
+
+    Ok(Component::Button(Button {
+        custom_id: Some(proto_to_custom_id(&proto)?),
+        disabled: false,
+        emoji: None,
+        label: Some(label.to_string()),
+        sku_id: None,
+        style,
+        url: None,
+    }))
+}
+
+/// Creates the buttons moderators accept or deny a submitted appeal with.
+pub fn resolution_buttons(appeal_id: i32) -> Result<Component> {
+    Ok(Component::ActionRow(ActionRow {
+        components: vec![
+            appeal_button(
+                appeal_id,
+                AppealButtonOption::APPEAL_BUTTON_ACCEPT,
+                "Accept",
+                ButtonStyle::Success,
+            )?,
+            appeal_button(
+                appeal_id,
+                AppealButtonOption::APPEAL_BUTTON_DENY,
+                "Deny",
+                ButtonStyle::Danger,
+            )?,
+        ],
+    }))
+}
+
+/// An appeal offered to a user, which can be withdrawn if the appealed actions fail.
+pub struct Offer {
+    pub appeal_id: i32,
+    /// The direct message the appeal was offered in, if it could be sent.
+    message: Option<(Id<ChannelMarker>, Id<MessageMarker>)>,
+}
+
+impl Offer {
+    /// Whether the user was sent the direct message offering the appeal.
+    pub fn was_sent(&self) -> bool {
+        self.message.is_some()
+    }
+}
+
+/// Offers the target of a moderation action a chance to appeal it, if the guild has appeals
+/// enabled. `description` completes the sentence "You have been ... <guild>". Must be called
+/// before the user is banned, as users cannot be messaged once they share no servers with the
+/// bot, so the offer must be withdrawn if the actions then fail. Returns the offer, if one was
+/// made.
+pub async fn offer(
+    executor: &ActionExecutor,
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+    actions: ActionSet,
+    description: &str,
+) -> Result<Option<Offer>> {
+    let config: ModerationConfig = executor
+        .storage()
+        .redis()
+        .guild(guild_id)
+        .configs()
+        .get()
+        .await?;
+    if !config.has_appeals()
+        || executor.record_dry_run(|| Operation::OfferAppeal { guild_id, user_id })
+    {
+        return Ok(None);
+    }
+
+    let mut appeal = Appeal {
+        id: 0,
+        guild_id: guild_id.get() as i64,
+        user_id: user_id.get() as i64,
+        token: rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect(),
+        actions: actions.into(),
+        escalation_entry_id: None,
+        status: AppealStatus::Open,
+        content: None,
+        created_at: Utc::now(),
+        submitted_at: None,
+        resolved_at: None,
+        resolved_by: None,
+    };
+    appeal.id = appeal.insert().fetch_one(executor.storage().sql()).await?.0;
+
+    let reasons = appeal
+        .actions
+        .0
+        .get_action()
+        .iter()
+        .map(|action| action.get_reason())
+        .find(|reason| !reason.is_empty())
+        .unwrap_or("No reason given.");
+    let content = format!(
+        "You have been {} **{}**.\nReason: {}\n\nIf you believe this was a mistake, you can \
+         appeal it with the button below. The moderators will be shown your appeal and will \
+         get back to you.\nAppeal code: `{}`",
+        description,
+        guild_name(executor.storage(), guild_id).await?,
+        reasons,
+        appeal.token
+    );
+    let button = appeal_button(
+        appeal.id,
+        AppealButtonOption::APPEAL_BUTTON_APPEAL,
+        "Appeal",
+        ButtonStyle::Primary,
+    )?;
+    let message = match send_direct_message(executor.http(), user_id, &content, &[button]).await {
+        Ok(message) => Some(message),
+        Err(err) => {
+            tracing::warn!("Failed to offer user {} an appeal: {}", user_id, err);
+            None
+        }
+    };
+
+    Ok(Some(Offer {
+        appeal_id: appeal.id,
+        message,
+    }))
+}
+
+/// Withdraws an appeal offered for actions that then failed, deleting the appeal and the
+/// message that offered it.
+pub async fn withdraw(executor: &ActionExecutor, offer: Offer) -> Result<()> {
+    if let Some((channel_id, message_id)) = offer.message {
+        executor
+            .http()
+            .delete_message(channel_id, message_id)
+            .await?;
+    }
+    Appeal::delete(offer.appeal_id)
+        .execute(executor.storage().sql())
+        .await?;
+    Ok(())
+}
+
+/// Submits an appeal written by the user and posts it to the guild's appeals channel for the
+/// moderators to accept or deny.
+pub async fn submit(
+    http: &http::Client,
+    storage: &Storage,
+    appeal_id: i32,
+    content: &str,
+) -> Result<Appeal> {
+    let content = content.trim();
+    if content.is_empty() {
+        anyhow::bail!(AppealError::Empty);
+    } else if content.chars().count() > MAX_APPEAL_LENGTH {
+        anyhow::bail!(AppealError::TooLong);
+    }
+
+    let appeal = Appeal::fetch(appeal_id)
+        .fetch_optional(storage.sql())
+        .await?
+        .ok_or(AppealError::NotFound)?;
+    let config: ModerationConfig = storage
+        .redis()
+        .guild(appeal.guild_id())
+        .configs()
+        .get()
+        .await?;
+    if !config.has_appeals() {
+        anyhow::bail!(AppealError::NotEnabled);
+    }
+
+    let appeal = Appeal::submit(appeal_id, content)
+        .fetch_optional(storage.sql())
+        .await?
+        .ok_or(AppealError::AlreadySubmitted)?;
+
+    http.create_message(Id::new(config.get_appeals().get_channel_id()))
+        .content(&describe_appeal(&appeal))
+        .components(&[resolution_buttons(appeal.id)?])
+        .await?;
+
+    Ok(appeal)
+}
+
+/// Accepts or denies a submitted appeal and lets the user know. Accepting an appeal undoes the
+/// appealed actions that can be undone, and pardons the appealed escalation. The appeal is
+/// claimed first, so it cannot be accepted or denied twice at once, and is only marked as
+/// accepted once everything was undone. A failed acceptance releases the claim so it can be
+/// retried, and a retry skips what was already undone.
+pub async fn resolve(
+    executor: &ActionExecutor,
+    appeal_id: i32,
+    moderator: &User,
+    accept: bool,
+) -> Result<Appeal> {
+    let sql = executor.storage().sql();
+    if Appeal::fetch(appeal_id)
+        .fetch_optional(sql)
+        .await?
+        .is_none()
+    {
+        anyhow::bail!(AppealError::NotFound);
+    }
+
+    let status = if accept {
+        let appeal = Appeal::claim_acceptance(appeal_id, moderator.id, ACCEPT_LEASE)
+            .fetch_optional(sql)
+            .await?
+            .ok_or(AppealError::AlreadyResolved)?;
+        if let Err(err) = undo_appealed(executor, &appeal, moderator).await {
+            sql.execute(Appeal::release(appeal_id, moderator.id))
+                .await?;
+            return Err(err);
+        }
+        AppealStatus::Accepted
+    } else {
+        AppealStatus::Denied
+    };
+    let appeal = Appeal::resolve(appeal_id, status, moderator.id)
+        .fetch_optional(sql)
+        .await?
+        .ok_or(AppealError::AlreadyResolved)?;
+
+    let content = format!(
+        "Your appeal in **{}** has been {}.",
+        guild_name(executor.storage(), appeal.guild_id()).await?,
+        if accept { "accepted" } else { "denied" }
+    );
+    if let Err(err) = send_direct_message(executor.http(), appeal.user_id(), &content, &[]).await {
+        tracing::warn!(
+            "Failed to notify user {} of their appeal: {}",
+            appeal.user_id(),
+            err
+        );
+    }
+
+    Ok(appeal)
+}
+
+/// Undoes the actions of an accepted appeal. Actions that were already undone, such as by an
+/// earlier attempt to accept the appeal, are skipped.
+async fn undo_appealed(executor: &ActionExecutor, appeal: &Appeal, moderator: &User) -> Result<()> {
+    let reason = format!("Appeal accepted by {}", moderator.name);
+    for action in appeal.actions.0.get_action() {
+        // Escalations are undone through the escalation history below.
+        if action.has_escalate() || !validation::can_undo(action) {
+            continue;
+        }
+        let mut undo = action.clone();
+        ActionExecutor::invert_action(&mut undo);
+        undo.clear_duration();
+        undo.set_reason(reason.clone());
+        undo.set_authorizer_id(moderator.id.get());
+        match executor.execute_action(&undo).await {
+            Ok(()) => {}
+            Err(err) if is_already_undone(&err) => {
+                tracing::debug!("Skipping an undone action of appeal {}: {}", appeal.id, err);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if let Some(entry_id) = appeal.escalation_entry_id {
+        let history = EscalationManager::new(executor.clone())
+            .guild(appeal.guild_id())
+            .await?
+            .fetch_history(appeal.user_id())
+            .await?;
+        // Pardoned entries no longer count, so they are left as they are.
+        let number = history
+            .entries()
+            .position(|entry| entry.id == entry_id && !entry.is_revoked())
+            .map(|idx| idx + 1);
+        if let Some(number) = number {
+            history.pardon(moderator, number, &reason, false).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks if Discord refused an undo because there is nothing left to undo: the user is no
+/// longer banned, or is no longer in the guild.
+fn is_already_undone(err: &anyhow::Error) -> bool {
+    use http::{api_error::ApiError, error::ErrorType};
+    const UNKNOWN_MEMBER: u64 = 10007;
+    const UNKNOWN_BAN: u64 = 10026;
+    match err.downcast_ref::<http::Error>().map(http::Error::kind) {
+        Some(ErrorType::Response {
+            error: ApiError::General(error),
+            ..
+        }) => matches!(error.code, UNKNOWN_MEMBER | UNKNOWN_BAN),
+        _ => false,
+    }
+}
+
+/// Describes an appeal for the moderators, including how it was resolved, if it was.
+pub fn describe_appeal(appeal: &Appeal) -> String {
+    let mut actions: Vec<&str> = appeal
+        .actions
+        .0
+        .get_action()
+        .iter()
+        .filter_map(cases::case_action)
+        .collect();
+    if appeal.escalation_entry_id.is_some() {
+        actions.push("Escalation");
+    }
+    actions.dedup();
+
+    let mut description = format!(
+        "**Appeal #{} from <@{}>**\nAppealing: {}\nOffered: <t:{}:R>",
+        appeal.id,
+        appeal.user_id,
+        if actions.is_empty() {
+            "Unknown".to_string()
+        } else {
+            actions.join(", ")
+        },
+        appeal.created_at.timestamp()
+    );
+    match (appeal.status, appeal.resolved_by) {
+        (AppealStatus::Accepted, Some(moderator)) => {
+            description.push_str(&format!("\nAccepted by <@{}>", moderator));
+        }
+        (AppealStatus::Denied, Some(moderator)) => {
+            description.push_str(&format!("\nDenied by <@{}>", moderator));
+        }
+        (AppealStatus::Accepting, Some(moderator)) => {
+            description.push_str(&format!("\nBeing accepted by <@{}>", moderator));
+        }
+        _ => {}
+    }
+    if let Some(content) = appeal.content.as_ref() {
+        description.push_str("\n>>> ");
+        description.push_str(content);
+    }
+    description
+}
+
+async fn guild_name(storage: &Storage, guild_id: Id<GuildMarker>) -> Result<String> {
+    Ok(storage
+        .redis()
+        .guild(guild_id)
+        .fetch_resource::<Guild>(guild_id)
+        .await?
+        .map(|guild| guild.get_name().to_owned())
+        .unwrap_or_else(|| "a server".to_owned()))
+}
+
+/// Sends a user a direct message. Returns the channel and ID of the sent message.
+async fn send_direct_message(
+    http: &http::Client,
+    user_id: Id<UserMarker>,
+    content: &str,
+    buttons: &[Component],
+) -> Result<(Id<ChannelMarker>, Id<MessageMarker>)> {
+    let channel = http.create_private_channel(user_id).await?.model().await?;
+    let request = http.create_message(channel.id).content(content);
+    let message = if buttons.is_empty() {
+        request.await?
+    } else {
+        request
+            .components(&[Component::ActionRow(ActionRow {
+                components: buttons.to_vec(),
+            })])
+            .await?
+    };
+    Ok((channel.id, message.model().await?.id))
+}
@@ -0,0 +1,239 @@
+use crate::Storage;
+use anyhow::Result;
+use chrono::Utc;
+use hourai::{
+    http,
+    models::id::{
+        Id,
+        marker::{ChannelMarker, GuildMarker, UserMarker},
+    },
+    proto::{action::*, guild_configs::LoggingConfig},
+};
+use hourai_sql::{Case, CaseReasonEdit, Executor};
+use std::time::Duration;
+
+fn status_name(
+    status: StatusType,
+    apply: &'static str,
+    unapply: &'static str,
+    toggle: &'static str,
+) -> &'static str {
+    match status {
+        StatusType::APPLY => apply,
+        StatusType::UNAPPLY => unapply,
+        StatusType::TOGGLE => toggle,
+    }
+}
+
+/// Gets the name an action is recorded under in the case log. Actions that do not moderate a
+/// user or channel, like sending messages or escalating, are not recorded. Escalations are recorded
+/// through the actions of the rung they land on instead.
+pub fn case_action(action: &Action) -> Option<&'static str> {
+    Some(match action.details.as_ref()? {
+        Action_oneof_details::kick(_) => "Kick",
+        Action_oneof_details::ban(info) => match info.get_field_type() {
+            BanMember_Type::BAN => "Ban",
+            BanMember_Type::UNBAN => "Unban",
+            BanMember_Type::SOFTBAN => "Softban",
+        },
+        Action_oneof_details::mute(info) => {
+            status_name(info.get_field_type(), "Mute", "Unmute", "Toggle mute")
+        }
+        Action_oneof_details::deafen(info) => {
+            status_name(info.get_field_type(), "Deafen", "Undeafen", "Toggle deafen")
+        }
+        Action_oneof_details::change_role(info) => status_name(
+            info.get_field_type(),
+            "Add role",
+            "Remove role",
+            "Toggle role",
+        ),
+        Action_oneof_details::timeout(info) => status_name(
+            info.get_field_type(),
+            "Timeout",
+            "Remove timeout",
+            "Toggle timeout",
+        ),
+        Action_oneof_details::set_nickname(_) => "Set nickname",
+        Action_oneof_details::set_slowmode(_) => "Set slowmode",
+        Action_oneof_details::lock_channel(info) => status_name(
+            info.get_field_type(),
+            "Lock channel",
+            "Unlock channel",
+            "Toggle channel lock",
+        ),
+        _ => return None,
+    })
+}
+
+/// What a case was recorded against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseTarget {
+    User(Id<UserMarker>),
+    Channel(Id<ChannelMarker>),
+}
+
+impl CaseTarget {
+    /// Gets what an action is recorded against: the channel for actions on channels, and the
+    /// user otherwise.
+    pub fn of(action: &Action) -> Self {
+        match action.details.as_ref() {
+            Some(Action_oneof_details::set_slowmode(info)) => {
+                Self::Channel(Id::new(info.get_channel_id()))
+            }
+            Some(Action_oneof_details::lock_channel(info)) => {
+                Self::Channel(Id::new(info.get_channel_id()))
+            }
+            _ => Self::User(Id::new(action.get_user_id())),
+        }
+    }
+}
+
+impl std::fmt::Display for CaseTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::User(user_id) => write!(f, "<@{}>", user_id),
+            Self::Channel(channel_id) => write!(f, "<#{}>", channel_id),
+        }
+    }
+}
+
+/// Creates an unnumbered case. The case number is assigned by `record_case`.
+pub fn new_case(
+    guild_id: Id<GuildMarker>,
+    target: CaseTarget,
+    moderator_id: Id<UserMarker>,
+    action: &str,
+    reason: Option<&str>,
+    duration: Option<Duration>,
+) -> Case {
+    Case {
+        guild_id: guild_id.get() as i64,
+        case_number: 0,
+        action: action.to_owned(),
+        user_id: match target {
+            CaseTarget::User(user_id) => Some(user_id.get() as i64),
+            CaseTarget::Channel(_) => None,
+        },
+        channel_id: match target {
+            CaseTarget::Channel(channel_id) => Some(channel_id.get() as i64),
+            CaseTarget::User(_) => None,
+        },
+        moderator_id: moderator_id.get() as i64,
+        reason: reason.filter(|reason| !reason.is_empty()).map(String::from),
+        duration: duration.map(|duration| duration.as_secs() as i64),
+        modlog_channel_id: None,
+        modlog_message_id: None,
+        timestamp: Utc::now(),
+    }
+}
+
+/// Formats a case for display in Discord.
+pub fn describe_case(case: &Case) -> String {
+    let target = match (case.user_id(), case.channel_id()) {
+        (Some(user_id), _) => format!("User: <@{}>", user_id),
+        (None, Some(channel_id)) => format!("Channel: <#{}>", channel_id),
+        (None, None) => "User: Unknown".to_owned(),
+    };
+    let mut description = format!(
+        "**Case #{}** | {}\n{}\nModerator: <@{}>\nReason: {}",
+        case.case_number,
+        case.action,
+        target,
+        case.moderator_id,
+        case.reason.as_deref().unwrap_or("No reason provided"),
+    );
+    if let Some(duration) = case.duration {
+        let duration = Duration::from_secs(duration as u64);
+        description.push_str(&format!(
+            "\nDuration: {}",
+            humantime::format_duration(duration)
+        ));
+    }
+    description.push_str(&format!("\nDate: <t:{}:f>", case.timestamp.timestamp()));
+    description
+}
+
+/// Assigns a case the guild's next case number and stores it. If the guild has a modlog, the
+/// case is posted there and linked to the posted message.
+pub async fn record_case(http: &http::Client, storage: &Storage, mut case: Case) -> Result<Case> {
+    case.case_number = case.insert().fetch_one(storage.sql()).await?.0;
+
+    let config: LoggingConfig = storage
+        .redis()
+        .guild(case.guild_id())
+        .configs()
+        .get()
+        .await?;
+    if config.has_modlog_channel_id() {
+        let modlog_id = Id::new(config.get_modlog_channel_id());
+        let message = http
+            .create_message(modlog_id)
+            .content(&describe_case(&case))
+            .await?
+            .model()
+            .await?;
+        storage
+            .sql()
+            .execute(case.set_modlog_message(modlog_id, message.id))
+            .await?;
+        case.modlog_channel_id = Some(modlog_id.get() as i64);
+        case.modlog_message_id = Some(message.id.get() as i64);
+    }
+
+    Ok(case)
+}
+
+/// Changes the reason of a case, keeping the previous reason in the case's edit history, and
+/// edits the case's modlog message to match.
+///
+/// Discord does not support changing the reason of an existing ban or audit log entry, so those
+/// keep the original reason.
+pub async fn update_reason(
+    http: &http::Client,
+    storage: &Storage,
+    case: &mut Case,
+    editor_id: Id<UserMarker>,
+    reason: &str,
+) -> Result<()> {
+    let edit = CaseReasonEdit {
+        guild_id: case.guild_id,
+        case_number: case.case_number,
+        editor_id: editor_id.get() as i64,
+        previous_reason: case.reason.clone(),
+        reason: reason.to_owned(),
+        timestamp: Utc::now(),
+    };
+    let mut txn = storage.sql().begin().await?;
+    txn.execute(edit.insert()).await?;
+    txn.execute(case.set_reason(reason)).await?;
+    txn.commit().await?;
+    case.reason = Some(reason.to_owned());
+
+    let modlog_message =
+        case.modlog_channel_id
+            .zip(case.modlog_message_id)
+            .and_then(|(channel_id, message_id)| {
+                Some((
+                    Id::new_checked(channel_id as u64)?,
+                    Id::new_checked(message_id as u64)?,
+                ))
+            });
+    if let Some((channel_id, message_id)) = modlog_message {
+        let content = describe_case(case);
+        let result = http
+            .update_message(channel_id, message_id)
+            .content(Some(&content))
+            .await;
+        if let Err(err) = result {
+            tracing::warn!(
+                "Failed to edit the modlog message of case #{} in guild {}: {}",
+                case.case_number,
+                case.guild_id,
+                err
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,8 @@
-use crate::{Storage, actions::ActionExecutor};
+use crate::{
+    Storage,
+    actions::{ActionExecutor, Operation},
+    appeals,
+};
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use hourai::{
@@ -8,15 +12,16 @@ use hourai::{
         marker::{GuildMarker, UserMarker},
     },
     proto::{
-        escalation::EscalationLadderRung,
+        escalation::{EscalationLadder, EscalationLadderRung, EscalationMode},
         guild_configs::{LoggingConfig, ModerationConfig},
     },
+    util::template::{self, TemplateContext},
 };
 use hourai::{
     models::user::User,
     proto::action::{Action, ActionSet},
 };
-use hourai_sql::{EscalationEntry, Executor, PendingDeescalation};
+use hourai_sql::{Appeal, EscalationEntry, Executor, PendingAction, PendingDeescalation};
 use std::{
     cmp::{max, min},
     collections::HashSet,
@@ -24,20 +29,109 @@ use std::{
 };
 use thiserror::Error;
 
+/// The notification sent to escalated users if neither the rung nor the ladder has one, and the
+/// ladder opted into notifying users.
+const DEFAULT_NOTIFICATION: &str = "You have been escalated to **{escalation.rung}** in \
+     **{guild}**.\nReason: {reason}\nNext level: {escalation.next_rung}\nExpires: \
+     {escalation.expiration}";
+
 pub struct Escalation {
     pub current_level: i64,
     pub entry: EscalationEntry,
     pub current_rung: Option<EscalationLadderRung>,
     pub next_rung: Option<EscalationLadderRung>,
     pub expiration: Option<DateTime<Utc>>,
+    /// Why the user could not be sent their notification, if it failed.
+    pub notify_error: Option<String>,
 }
 
 impl Escalation {
     pub fn expiration(&self) -> String {
-        self.expiration
-            .map(|exp| format!("<t:{}:R>", exp.timestamp()))
-            .unwrap_or_else(|| "Never".into())
+        format_expiration(self.expiration)
+    }
+}
+
+fn uses_points(ladder: &EscalationLadder) -> bool {
+    ladder.get_mode() == EscalationMode::ESCALATION_MODE_POINTS
+}
+
+/// Describes an escalation level as reported by `EscalationHistory::current_level`, i.e.
+/// "at rung 2", or "at 6 points" if the ladder uses points.
+pub fn describe_level(ladder: &EscalationLadder, level: i64) -> String {
+    if uses_points(ladder) {
+        format!("at {} points", level)
+    } else if level < 0 {
+        "not escalated".to_owned()
+    } else {
+        format!("at rung {}", level + 1)
+    }
+}
+
+/// Points of an escalation that still count towards a user's total.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ActivePoints {
+    entry_id: i32,
+    points: i64,
+    /// When the points stop counting, if they expire.
+    expiration: Option<DateTime<Utc>>,
+}
+
+/// Replays a points history, in order, to find the points still counting at `now`, oldest
+/// first. Deescalations take away points from the oldest escalations that still count, so they
+/// cannot leave behind a credit that absorbs later escalations once those points expire.
+/// Pardoned entries are ignored.
+fn active_points<'a>(
+    entries: impl Iterator<Item = &'a EscalationEntry>,
+    lifetime: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<ActivePoints> {
+    let mut active: Vec<ActivePoints> = Vec::new();
+    for entry in entries.filter(|entry| !entry.is_revoked()) {
+        active.retain(|points| points.expiration.is_none_or(|exp| exp > entry.timestamp));
+        let delta = entry.level_delta as i64;
+        if delta > 0 {
+            active.push(ActivePoints {
+                entry_id: entry.id,
+                points: delta,
+                expiration: lifetime.map(|lifetime| entry.timestamp + lifetime),
+            });
+            continue;
+        }
+        let mut removed = -delta;
+        for points in active.iter_mut() {
+            let taken = min(removed, points.points);
+            points.points -= taken;
+            removed -= taken;
+        }
+        active.retain(|points| points.points > 0);
     }
+    active.retain(|points| points.expiration.is_none_or(|exp| exp > now));
+    active
+}
+
+/// How far the scheduled undo of an escalation's action can be from the escalation's own
+/// expiry. Escalations are recorded after their actions have run and scheduled their undos.
+const UNDO_TOLERANCE_SECS: i64 = 300;
+
+/// Checks if a scheduled action is the undo of a timed action expiring at `expires`, given the
+/// action's inverse. Both target the same user.
+fn is_scheduled_undo(scheduled: &PendingAction, inverse: &Action, expires: DateTime<Utc>) -> bool {
+    let same_kind = match (
+        scheduled.action().details.as_ref(),
+        inverse.details.as_ref(),
+    ) {
+        (Some(scheduled), Some(inverse)) => {
+            std::mem::discriminant(scheduled) == std::mem::discriminant(inverse)
+        }
+        _ => false,
+    };
+    same_kind && (scheduled.timestamp() - expires).num_seconds().abs() <= UNDO_TOLERANCE_SECS
+}
+
+fn format_expiration(expiration: Option<DateTime<Utc>>) -> String {
+    expiration
+        .map(|exp| format!("<t:{}:R>", exp.timestamp()))
+        .unwrap_or_else(|| "Never".into())
 }
 
 #[derive(Debug, Error)]
@@ -46,6 +140,21 @@ pub enum EscalationError {
     NoReason,
     #[error("No escalation ladder has been configured.")]
     NoLadderConfigured,
+    #[error("Escalation entry {0} does not exist.")]
+    NoSuchEntry(usize),
+    #[error("Escalation entry {0} has already been pardoned.")]
+    AlreadyPardoned(usize),
+}
+
+/// The result of pardoning an escalation entry.
+pub struct Pardon {
+    /// The 1-indexed position of the entry in the user's history.
+    pub entry_number: usize,
+    pub display_name: String,
+    pub current_level: i64,
+    pub expiration: Option<DateTime<Utc>>,
+    /// The timed actions of the entry that were undone.
+    pub undone: Vec<Action>,
 }
 
 #[derive(Clone)]
@@ -170,10 +279,20 @@ impl EscalationHistory {
         self.manager.executor()
     }
 
-    /// The current escalation level of the guild member.
+    /// Checks if the ladder uses weighted points that expire individually, instead of levels.
+    #[inline(always)]
+    pub fn uses_points(&self) -> bool {
+        uses_points(self.config().get_escalation_ladder())
+    }
+
+    /// The current escalation level of the guild member, or their active points if the ladder
+    /// uses points. Pardoned entries are ignored.
     pub fn current_level(&self) -> i64 {
+        if self.uses_points() {
+            return self.active_points(Utc::now());
+        }
         let mut level: i64 = -1;
-        for entry in self.entries() {
+        for entry in self.entries().filter(|entry| !entry.is_revoked()) {
             level = std::cmp::max(-1, level + entry.level_delta as i64);
         }
         level
@@ -208,21 +327,85 @@ impl EscalationHistory {
             anyhow::bail!(EscalationError::NoLadderConfigured);
         }
 
-        let current_level = max(-1, self.current_level() + diff);
+        let floor = if self.uses_points() { 0 } else { -1 };
+        let previous_level = self.current_level();
+        let current_level = max(floor, previous_level + diff);
+        // Points are only taken away down to zero, so a deescalation cannot leave a credit.
+        let level_delta = if self.uses_points() {
+            current_level - previous_level
+        } else {
+            diff
+        };
         let current_rung = self.get_rung(current_level);
+        let expiration = if self.uses_points() {
+            // The points being added expire, not the rung.
+            self.point_lifetime()
+                .filter(|_| diff > 0)
+                .map(|lifetime| Utc::now() + lifetime)
+        } else {
+            current_rung
+                .filter(|rung| rung.has_deescalation_period())
+                .map(|rung| Utc::now() + Duration::seconds(rung.get_deescalation_period() as i64))
+        };
         let mut actions = ActionSet::new();
-        if execute {
-            if let Some(rung) = current_rung {
-                for rung_action in rung.get_action() {
-                    let mut action = rung_action.clone();
-                    action.set_user_id(self.user_id().get());
-                    action.set_guild_id(self.guild_id().get());
-                    action.set_reason(reason.to_string());
-                    self.executor().execute_action(&action).await?;
-                    actions.mut_action().push(action);
+        let mut offer = None;
+        let mut notify_error = None;
+        if execute && let Some(rung) = current_rung {
+            for rung_action in rung.get_action() {
+                let mut action = rung_action.clone();
+                action.set_user_id(self.user_id().get());
+                action.set_guild_id(self.guild_id().get());
+                action.set_reason(reason.to_string());
+                action.set_authorizer_id(authorizer.id.get());
+                actions.mut_action().push(action);
+            }
+            if diff > 0 {
+                // Must happen before the actions, as banned users can no longer be messaged.
+                let description = format!("escalated to {} in", rung.get_display_name());
+                offer = appeals::offer(
+                    self.executor(),
+                    self.guild_id(),
+                    self.user_id(),
+                    actions.clone(),
+                    &description,
+                )
+                .await
+                .unwrap_or_else(|err| {
+                    tracing::error!("Failed to offer an appeal for an escalation: {}", err);
+                    None
+                });
+                // The appeal's message already tells the user of the escalation.
+                if !offer.as_ref().is_some_and(appeals::Offer::was_sent)
+                    && let Err(err) = self.notify(current_level, rung, reason, expiration).await
+                {
+                    tracing::warn!(
+                        "Failed to notify user {} of their escalation: {}",
+                        self.user_id(),
+                        err
+                    );
+                    notify_error = Some(err.to_string());
                 }
             }
-        } else {
+            // The escalation's appeal covers any bans on the rung, and its modlog entry
+            // records the rung's actions, so they do not record cases of their own.
+            let executor = self.executor().without_appeals().without_cases();
+            for action in actions.get_action() {
+                if let Err(err) = executor.execute_action(action).await {
+                    if let Some(offer) = offer
+                        && let Err(err) = appeals::withdraw(self.executor(), offer).await
+                    {
+                        tracing::error!(
+                            "Failed to withdraw the appeal of a failed escalation: {}",
+                            err
+                        );
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        // Points added below the first threshold do not apply any actions, so they are recorded
+        // like deescalations are.
+        if actions.get_action().is_empty() {
             let mut action = Action::new();
             action.set_user_id(self.user_id().get());
             action.set_guild_id(self.guild_id().get());
@@ -233,53 +416,421 @@ impl EscalationHistory {
 
         let display_name = match current_rung {
             Some(rung) if diff >= 0 => rung.get_display_name(),
+            None if self.uses_points() && diff > 0 => "Points Added",
             _ => "Deescalate",
         };
 
-        let entry = self.create_entry(authorizer, actions, display_name, diff);
+        let entry = self.create_entry(authorizer, actions, display_name, level_delta);
+        let mut escalation = Escalation {
+            current_level,
+            entry,
+            current_rung: current_rung.cloned(),
+            next_rung: self.next_rung(current_level).cloned(),
+            expiration,
+            notify_error,
+        };
+
+        if self
+            .executor()
+            .record_dry_run(|| Operation::RecordEscalation {
+                guild_id: self.guild_id(),
+                user_id: self.user_id(),
+                level: current_level,
+                display_name: display_name.to_string(),
+            })
+        {
+            return Ok(escalation);
+        }
+
         let mut txn = self.storage().sql().begin().await?;
-        let entry_id: i32 = entry.insert().fetch_one(&mut txn).await?.0;
+        let entry_id: i32 = escalation.entry.insert().fetch_one(&mut txn).await?.0;
+        escalation.entry.id = entry_id;
+        if let Some(offer) = offer {
+            txn.execute(Appeal::set_escalation_entry(offer.appeal_id, entry_id))
+                .await?;
+        }
 
         // Schedule the pending deescalation
-        let mut expiration = None;
-        if let Some(rung) = current_rung {
-            if rung.has_deescalation_period() {
-                let exp = Utc::now() + Duration::seconds(rung.get_deescalation_period() as i64);
-                expiration = Some(exp);
-                let pending = PendingDeescalation {
+        if self.uses_points() {
+            match self.next_point_expiry(Some(&escalation.entry), Utc::now()) {
+                Some(pending) => txn.execute(pending.insert()).await?,
+                None => {
+                    txn.execute(PendingDeescalation::delete(self.guild_id(), self.user_id()))
+                        .await?
+                }
+            };
+        } else if let Some(expiration) = expiration {
+            let pending = PendingDeescalation {
+                guild_id: self.guild_id().get() as i64,
+                user_id: self.user_id().get() as i64,
+                expiration,
+                amount: -1,
+                entry_id,
+                attempts: 0,
+            };
+            txn.execute(pending.insert()).await?;
+        } else if current_rung.is_none() {
+            txn.execute(PendingDeescalation::delete(self.guild_id(), self.user_id()))
+                .await?;
+        }
+        txn.commit().await?;
+
+        self.log_to_modlog(&mut escalation, diff).await?;
+
+        Ok(escalation)
+    }
+
+    /// Pardons an entry of the user's history, numbered from 1 in chronological order. The entry
+    /// is kept, but no longer counts towards the user's escalation level, and the pending
+    /// deescalation is rescheduled as if it never happened. If `undo` is set, the entry's timed
+    /// actions that have not expired yet, like temporary bans, are undone once the entry is
+    /// revoked, and their scheduled undos are cancelled.
+    pub async fn pardon(
+        mut self,
+        authorizer: &User,
+        number: usize,
+        reason: &str,
+        undo: bool,
+    ) -> Result<Pardon> {
+        if reason.is_empty() {
+            anyhow::bail!(EscalationError::NoReason);
+        }
+        let idx = number
+            .checked_sub(1)
+            .filter(|idx| *idx < self.entries.len())
+            .ok_or(EscalationError::NoSuchEntry(number))?;
+        if self.entries[idx].is_revoked() {
+            anyhow::bail!(EscalationError::AlreadyPardoned(number));
+        }
+
+        let now = Utc::now();
+        // The inverses of the entry's timed actions that have not expired yet, along with when
+        // they were scheduled to be undone.
+        let mut inverses = Vec::new();
+        if undo {
+            let entry = &self.entries[idx];
+            for action in entry.action.0.get_action() {
+                let expires = entry.timestamp + Duration::seconds(action.get_duration() as i64);
+                if !action.has_duration() || action.has_escalate() || expires <= now {
+                    continue;
+                }
+                let mut inverse = action.clone();
+                ActionExecutor::invert_action(&mut inverse);
+                inverse.clear_duration();
+                inverse.set_authorizer_id(authorizer.id.get());
+                inverse.set_reason(format!("Pardoned: {}", reason));
+                inverses.push((inverse, expires));
+            }
+        }
+
+        self.entries[idx].revoked_at = Some(now);
+        self.entries[idx].revoked_by = Some(authorizer.id.get() as i64);
+        let current_level = self.current_level();
+        // Reschedule the pending deescalation from the latest entry that still counts.
+        let latest = self.entries.iter().rev().find(|entry| !entry.is_revoked());
+        let pending = if self.uses_points() {
+            self.next_point_expiry(None, now)
+        } else {
+            latest
+                .zip(self.get_rung(current_level))
+                .filter(|(_, rung)| rung.has_deescalation_period())
+                .map(|(entry, rung)| PendingDeescalation {
                     guild_id: self.guild_id().get() as i64,
                     user_id: self.user_id().get() as i64,
-                    expiration: exp,
+                    expiration: entry.timestamp
+                        + Duration::seconds(rung.get_deescalation_period() as i64),
                     amount: -1,
-                    entry_id,
-                };
-                txn.execute(pending.insert()).await?;
+                    entry_id: entry.id,
+                    attempts: 0,
+                })
+        };
+        let expiration = pending.as_ref().map(|pending| pending.expiration);
+
+        let dry_run = self
+            .executor()
+            .record_dry_run(|| Operation::PardonEscalation {
+                guild_id: self.guild_id(),
+                user_id: self.user_id(),
+                entry_number: number,
+            });
+        if !dry_run {
+            // The entry is revoked before its actions are undone, so a failure to revoke it
+            // does not leave it counting with its actions already undone.
+            let mut txn = self.storage().sql().begin().await?;
+            txn.execute(self.entries[idx].revoke(authorizer.id, now))
+                .await?;
+            match pending {
+                Some(pending) => txn.execute(pending.insert()).await?,
+                None => {
+                    txn.execute(PendingDeescalation::delete(self.guild_id(), self.user_id()))
+                        .await?
+                }
+            };
+            // Undone actions must not be undone again when their scheduled undo comes due.
+            if !inverses.is_empty() {
+                let scheduled =
+                    PendingAction::fetch_guild(self.guild_id(), Some(self.user_id()), i64::MAX)
+                        .fetch_all(&mut txn)
+                        .await?;
+                for scheduled in scheduled {
+                    if inverses
+                        .iter()
+                        .any(|(inverse, expires)| is_scheduled_undo(&scheduled, inverse, *expires))
+                    {
+                        PendingAction::cancel(self.guild_id(), scheduled.id())
+                            .fetch_optional(&mut txn)
+                            .await?;
+                    }
+                }
             }
-        } else {
-            txn.execute(PendingDeescalation::delete(self.guild_id(), self.user_id()))
+            txn.commit().await?;
+        }
+
+        let mut undone = Vec::new();
+        let executor = self.executor().without_cases();
+        for (inverse, _) in inverses {
+            match executor.execute_action(&inverse).await {
+                Ok(()) => undone.push(inverse),
+                Err(err) => tracing::warn!(
+                    "Failed to undo an action while pardoning entry {}: {}",
+                    self.entries[idx].id,
+                    err
+                ),
+            }
+        }
+
+        let pardon = Pardon {
+            entry_number: number,
+            display_name: self.entries[idx].display_name.clone(),
+            current_level,
+            expiration,
+            undone,
+        };
+        if !dry_run {
+            self.log_pardon_to_modlog(authorizer, &pardon, reason)
                 .await?;
         }
-        txn.commit().await?;
+        Ok(pardon)
+    }
+
+    async fn log_pardon_to_modlog(
+        &self,
+        authorizer: &User,
+        pardon: &Pardon,
+        reason: &str,
+    ) -> Result<()> {
+        let config: LoggingConfig = self
+            .storage()
+            .redis()
+            .guild(self.guild_id())
+            .configs()
+            .get()
+            .await?;
+        if !config.has_modlog_channel_id() {
+            return Ok(());
+        }
+        let expiration = pardon
+            .expiration
+            .map(|exp| format!("<t:{}:R>", exp.timestamp()))
+            .unwrap_or_else(|| "Never".into());
+        let msg = format!(
+            ":leftwards_arrow_with_hook: **<@{}> pardoned escalation entry {} ({}) of <@{}>**\n\
+             Reason: {}\nUndone actions: {}\nNow {}\nExpiration: {}",
+            authorizer.id,
+            pardon.entry_number,
+            pardon.display_name,
+            self.user_id(),
+            reason,
+            pardon.undone.len(),
+            describe_level(self.config().get_escalation_ladder(), pardon.current_level),
+            expiration
+        );
+        self.http()
+            .create_message(Id::new(config.get_modlog_channel_id()))
+            .content(&msg)
+            .await?;
+        Ok(())
+    }
+
+    /// Sends the user the notification of the rung they are being escalated to. Uses the
+    /// ladder's default notification if the rung has none, and the built-in one if the ladder
+    /// opted into it. Rungs that direct message the user themselves send no notification.
+    async fn notify(
+        &self,
+        level: i64,
+        rung: &EscalationLadderRung,
+        reason: &str,
+        expiration: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        if rung
+            .get_action()
+            .iter()
+            .any(|action| action.has_direct_message())
+        {
+            return Ok(());
+        }
+        let ladder = self.config().get_escalation_ladder();
+        let notification = if rung.has_notification() {
+            rung.get_notification()
+        } else if ladder.has_default_notification() {
+            ladder.get_default_notification()
+        } else if ladder.get_notify_users() {
+            DEFAULT_NOTIFICATION
+        } else {
+            return Ok(());
+        };
+        let mut ctx = TemplateContext {
+            user_id: Some(self.user_id()),
+            reason: Some(reason.to_owned()),
+            escalation_rung: Some(rung.get_display_name().to_owned()),
+            escalation_next_rung: Some(
+                self.next_rung(level)
+                    .map_or("None", |rung| rung.get_display_name())
+                    .to_owned(),
+            ),
+            escalation_expiration: Some(format_expiration(expiration)),
+            ..Default::default()
+        };
+        crate::populate_template_context(self.guild_id(), notification, self.storage(), &mut ctx)
+            .await?;
+        let content = template::render(notification, &ctx);
+
+        let user_id = self.user_id();
+        if self
+            .executor()
+            .record_dry_run(|| Operation::SendDirectMessage {
+                user_id,
+                content: content.clone(),
+            })
+        {
+            return Ok(());
+        }
+        let http = self.executor().http();
+        let channel = http.create_private_channel(user_id).await?.model().await?;
+        http.create_message(channel.id).content(&content).await?;
+        Ok(())
+    }
+
+    /// Records that the points of an escalation expired, then schedules the next expiry. Points
+    /// stop counting towards the total on their own, so the recorded entry does not change it;
+    /// it keeps the history and modlog up to date. `amount` is the negated points that expired.
+    pub async fn expire_points(&self, authorizer: &User, amount: i64) -> Result<Escalation> {
+        let now = Utc::now();
+        let current_level = self.current_level();
+        let current_rung = self.get_rung(current_level);
+        let pending = self.next_point_expiry(None, now);
+
+        let mut action = Action::new();
+        action.set_user_id(self.user_id().get());
+        action.set_guild_id(self.guild_id().get());
+        action.set_reason("Points expired".to_owned());
+        action.mut_escalate().set_amount(amount);
+        let mut actions = ActionSet::new();
+        actions.mut_action().push(action);
 
-        let escalation = Escalation {
+        let display_name = "Points Expired";
+        let mut escalation = Escalation {
             current_level,
-            entry,
+            entry: self.create_entry(authorizer, actions, display_name, 0),
             current_rung: current_rung.cloned(),
-            next_rung: self.get_rung(current_level + 1).cloned(),
-            expiration,
+            next_rung: self.next_rung(current_level).cloned(),
+            expiration: pending.as_ref().map(|pending| pending.expiration),
+            notify_error: None,
         };
 
-        self.log_to_modlog(&escalation, diff).await?;
+        if self
+            .executor()
+            .record_dry_run(|| Operation::RecordEscalation {
+                guild_id: self.guild_id(),
+                user_id: self.user_id(),
+                level: current_level,
+                display_name: display_name.to_owned(),
+            })
+        {
+            return Ok(escalation);
+        }
+
+        let mut txn = self.storage().sql().begin().await?;
+        escalation.entry.id = escalation.entry.insert().fetch_one(&mut txn).await?.0;
+        match pending {
+            Some(pending) => txn.execute(pending.insert()).await?,
+            None => {
+                txn.execute(PendingDeescalation::delete(self.guild_id(), self.user_id()))
+                    .await?
+            }
+        };
+        txn.commit().await?;
+
+        self.log_to_modlog(&mut escalation, amount).await?;
 
         Ok(escalation)
     }
 
+    fn point_lifetime(&self) -> Option<Duration> {
+        let ladder = self.config().get_escalation_ladder();
+        ladder
+            .has_point_lifetime()
+            .then(|| Duration::seconds(ladder.get_point_lifetime() as i64))
+    }
+
+    /// The points of the escalations that have not been pardoned, deescalated or expired by
+    /// `now`.
+    fn active_points(&self, now: DateTime<Utc>) -> i64 {
+        active_points(self.entries(), self.point_lifetime(), now)
+            .iter()
+            .map(|points| points.points)
+            .sum()
+    }
+
+    /// Finds the escalation whose remaining points expire next, including `added` if it has not
+    /// been recorded in the history yet.
+    fn next_point_expiry(
+        &self,
+        added: Option<&EscalationEntry>,
+        now: DateTime<Utc>,
+    ) -> Option<PendingDeescalation> {
+        // Every escalation's points last as long, so the oldest ones expire first.
+        let next = active_points(self.entries().chain(added), self.point_lifetime(), now)
+            .into_iter()
+            .next()?;
+        Some(PendingDeescalation {
+            guild_id: self.guild_id().get() as i64,
+            user_id: self.user_id().get() as i64,
+            expiration: next.expiration?,
+            amount: -next.points,
+            entry_id: next.entry_id,
+            attempts: 0,
+        })
+    }
+
+    /// The rung the user would reach by escalating further.
+    fn next_rung(&self, level: i64) -> Option<&EscalationLadderRung> {
+        if !self.uses_points() {
+            return self.get_rung(level + 1);
+        }
+        let rungs = self.config().get_escalation_ladder().get_rung();
+        rungs
+            .iter()
+            .find(|rung| rung.get_threshold() as i64 > level)
+            .or(rungs.last())
+    }
+
     fn get_rung(&self, level: i64) -> Option<&EscalationLadderRung> {
+        if self.uses_points() {
+            // The highest rung whose threshold has been reached.
+            return self
+                .config()
+                .get_escalation_ladder()
+                .get_rung()
+                .iter()
+                .rev()
+                .find(|rung| rung.get_threshold() as i64 <= level && rung.get_threshold() > 0);
+        }
         if level < 0 {
             None
         } else {
             let rungs = self.config().get_escalation_ladder().get_rung();
-            let idx = min(level as usize, rungs.len() - 1);
+            let idx = min(level as usize, rungs.len().checked_sub(1)?);
             Some(&rungs[idx])
         }
     }
@@ -293,6 +844,7 @@ impl EscalationHistory {
     ) -> EscalationEntry {
         let authorizer_name = format!("{}#{:04}", authorizer.name, authorizer.discriminator);
         EscalationEntry {
+            id: 0,
             guild_id: self.guild_id().get() as i64,
             subject_id: self.user_id().get() as i64,
             authorizer_id: authorizer.id.get() as i64,
@@ -301,10 +853,14 @@ impl EscalationHistory {
             timestamp: Utc::now(),
             action: actions.into(),
             level_delta: diff as i32,
+            modlog_channel_id: None,
+            modlog_message_id: None,
+            revoked_at: None,
+            revoked_by: None,
         }
     }
 
-    async fn log_to_modlog(&self, escalation: &Escalation, diff: i64) -> Result<()> {
+    async fn log_to_modlog(&self, escalation: &mut Escalation, diff: i64) -> Result<()> {
         let config: LoggingConfig = self
             .storage()
             .redis()
@@ -312,6 +868,9 @@ impl EscalationHistory {
             .configs()
             .get()
             .await?;
+        if !config.has_modlog_channel_id() {
+            return Ok(());
+        }
         let modlog_id = Id::new(config.get_modlog_channel_id());
         let arrow = if diff > 0 { "up" } else { "down" };
         let esc = if diff > 0 { "escalated" } else { "deescalated" };
@@ -332,7 +891,7 @@ impl EscalationHistory {
             }
             reasons.push_str(reason);
         }
-        let msg = format!(
+        let mut msg = format!(
             ":arrow_{}: **<@{}> {} <@{}>**\nReason: {}\nAction: {}\nExpiration: {}",
             arrow,
             escalation.entry.authorizer_id,
@@ -342,8 +901,109 @@ impl EscalationHistory {
             escalation.entry.display_name,
             escalation.expiration()
         );
-        self.http().create_message(modlog_id).content(&msg).await?;
+        if let Some(err) = escalation.notify_error.as_ref() {
+            msg.push_str(&format!("\nCould not notify the user: {}", err));
+        }
+        let message = self
+            .http()
+            .create_message(modlog_id)
+            .content(&msg)
+            .await?
+            .model()
+            .await?;
+        self.storage()
+            .sql()
+            .execute(escalation.entry.set_modlog_message(modlog_id, message.id))
+            .await?;
+        escalation.entry.modlog_channel_id = Some(modlog_id.get() as i64);
+        escalation.entry.modlog_message_id = Some(message.id.get() as i64);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(id: i32, minutes: i64, level_delta: i32) -> EscalationEntry {
+        EscalationEntry {
+            id,
+            guild_id: 1,
+            subject_id: 2,
+            authorizer_id: 3,
+            authorizer_name: "user3#0000".to_owned(),
+            display_name: "Test".to_owned(),
+            timestamp: start() + Duration::minutes(minutes),
+            action: ActionSet::new().into(),
+            level_delta,
+            modlog_channel_id: None,
+            modlog_message_id: None,
+            revoked_at: None,
+            revoked_by: None,
+        }
+    }
+
+    fn start() -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    }
+
+    fn total(entries: &[EscalationEntry], lifetime: Option<i64>, minutes: i64) -> i64 {
+        active_points(
+            entries.iter(),
+            lifetime.map(Duration::minutes),
+            start() + Duration::minutes(minutes),
+        )
+        .iter()
+        .map(|points| points.points)
+        .sum()
+    }
+
+    #[test]
+    fn test_points_expire_individually() {
+        let entries = [entry(1, 0, 3), entry(2, 30, 2)];
+        assert_eq!(total(&entries, Some(60), 45), 5);
+        assert_eq!(total(&entries, Some(60), 60), 2);
+        assert_eq!(total(&entries, Some(60), 90), 0);
+        assert_eq!(total(&entries, None, 1000), 5);
+    }
+
+    #[test]
+    fn test_deescalation_leaves_no_credit() {
+        // Taking away more points than are active only takes away what is there.
+        let entries = [entry(1, 0, 2), entry(2, 10, -5), entry(3, 20, 3)];
+        assert_eq!(total(&entries, Some(60), 30), 3);
+        // The deescalation took away the first escalation's points, so their expiry changes
+        // nothing.
+        let entries = [entry(1, 0, 4), entry(2, 10, 3), entry(3, 20, -5)];
+        assert_eq!(total(&entries, Some(60), 30), 2);
+        assert_eq!(total(&entries, Some(60), 65), 2);
+        assert_eq!(total(&entries, Some(60), 75), 0);
+    }
+
+    #[test]
+    fn test_pardoned_points_do_not_count() {
+        let mut entries = [entry(1, 0, 3), entry(2, 10, 2)];
+        entries[0].revoked_at = Some(start() + Duration::minutes(20));
+        assert_eq!(total(&entries, None, 30), 2);
+    }
+
+    #[test]
+    fn test_next_expiry_is_oldest_remaining_points() {
+        let entries = [entry(1, 0, 4), entry(2, 10, 3), entry(3, 20, -5)];
+        let active = active_points(
+            entries.iter(),
+            Some(Duration::minutes(60)),
+            start() + Duration::minutes(30),
+        );
+        assert_eq!(
+            active,
+            vec![ActivePoints {
+                entry_id: 2,
+                points: 2,
+                expiration: Some(start() + Duration::minutes(70)),
+            }]
+        );
+    }
+}
@@ -2,6 +2,8 @@
 extern crate delegate;
 
 pub mod actions;
+pub mod appeals;
+pub mod cases;
 pub mod escalation;
 mod storage;
 
@@ -11,6 +13,7 @@ use anyhow::Result;
 use hourai::{
     interactions::InteractionError,
     models::{
+        channel::Channel,
         guild::{Guild, Permissions, Role},
         id::{
             Id,
@@ -18,8 +21,12 @@ use hourai::{
         },
     },
     proto::cache::CachedRoleProto,
+    util::{
+        template::{self, TemplateContext},
+        validation::{self, GuildResources, Validate, ValidationError},
+    },
 };
-use hourai_redis::RedisClient;
+use hourai_redis::{CachedGuildConfig, RedisClient};
 use hourai_sql::{Member, SqlPool};
 use rand::Rng;
 
@@ -116,3 +123,78 @@ pub async fn is_moderator(
             .any(|role| role.get_role_id() == role_id.get())
     }))
 }
+
+/// Fills in the guild-wide values of a template context that are referenced by the template
+/// and have not already been provided.
+pub async fn populate_template_context(
+    guild_id: Id<GuildMarker>,
+    template: &str,
+    storage: &Storage,
+    ctx: &mut TemplateContext,
+) -> Result<()> {
+    for placeholder in template::placeholders(template) {
+        match placeholder {
+            "guild" if ctx.guild_name.is_none() => {
+                ctx.guild_name = storage
+                    .redis()
+                    .guild(guild_id)
+                    .fetch_resource::<Guild>(guild_id)
+                    .await?
+                    .map(|guild| guild.get_name().to_owned());
+            }
+            "guild.member_count" if ctx.member_count.is_none() => {
+                let (count,) = Member::count_guild_members(guild_id, true)
+                    .fetch_one(storage.sql())
+                    .await?;
+                ctx.member_count = Some(count as u64);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Gets the channels and roles a guild's configs can reference from the guild's cache. Returns
+/// `None` if nothing is cached for the guild.
+pub async fn guild_resources(
+    guild_id: Id<GuildMarker>,
+    redis: &RedisClient,
+) -> Result<Option<GuildResources>> {
+    let mut guild = redis.guild(guild_id);
+    let resources = GuildResources {
+        channel_ids: guild
+            .fetch_all_resources::<Channel>()
+            .await?
+            .into_keys()
+            .map(Id::get)
+            .collect(),
+        role_ids: guild
+            .fetch_all_resources::<Role>()
+            .await?
+            .into_keys()
+            .map(Id::get)
+            .collect(),
+    };
+    let cached = !resources.channel_ids.is_empty() || !resources.role_ids.is_empty();
+    Ok(cached.then_some(resources))
+}
+
+/// Validates a guild config, then saves it. References to channels and roles are checked against
+/// the guild's cache. Only problems the new config introduces are rejected. Problems the stored
+/// config already had, like references to since deleted roles, do not block unrelated changes
+/// and are returned as warnings instead.
+pub async fn set_config<T>(
+    guild_id: Id<GuildMarker>,
+    storage: &Storage,
+    config: T,
+) -> Result<Vec<ValidationError>>
+where
+    T: ::protobuf::Message + CachedGuildConfig + Validate,
+{
+    let resources = guild_resources(guild_id, storage.redis()).await?;
+    let mut configs = storage.redis().guild(guild_id).configs();
+    let stored: T = configs.get().await?;
+    let warnings = validation::validate_change(&stored, &config, resources.as_ref())?;
+    configs.set(config).await?;
+    Ok(warnings)
+}
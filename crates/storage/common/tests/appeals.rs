@@ -0,0 +1,136 @@
+use hourai::{
+    http,
+    models::id::Id,
+    proto::{
+        action::{Action, ActionSet, BanMember_Type},
+        guild_configs::ModerationConfig,
+    },
+};
+use hourai_sql::{Appeal, AppealStatus};
+use hourai_storage::{
+    actions::ActionExecutor,
+    appeals::{self, AppealError},
+};
+use std::sync::Arc;
+
+use hourai_testing::{guild_id, storage, user};
+
+fn ban_actions() -> ActionSet {
+    let mut ban = Action::new();
+    ban.mut_ban().set_field_type(BanMember_Type::BAN);
+    let mut actions = ActionSet::new();
+    actions.mut_action().push(ban);
+    actions
+}
+
+#[tokio::test]
+async fn test_withdraw_offer() {
+    let storage = match storage()
+        .await
+        .expect("Failed to connect to the test databases")
+    {
+        Some(storage) => storage,
+        None => return,
+    };
+    let guild_id = guild_id();
+    let mut config = ModerationConfig::new();
+    config.mut_appeals().set_channel_id(1);
+    storage
+        .redis()
+        .guild(guild_id)
+        .configs()
+        .set(config)
+        .await
+        .expect("Failed to store the test config");
+
+    let http = Arc::new(http::Client::new(String::new()));
+    let executor = ActionExecutor::new(user(1), http, storage.clone());
+    let offer = appeals::offer(
+        &executor,
+        guild_id,
+        Id::new(2),
+        ban_actions(),
+        "banned from",
+    )
+    .await
+    .expect("Failed to offer an appeal")
+    .expect("Appeals are enabled");
+    let appeal_id = offer.appeal_id;
+    assert!(
+        Appeal::fetch(appeal_id)
+            .fetch_optional(storage.sql())
+            .await
+            .expect("Failed to fetch the appeal")
+            .is_some()
+    );
+
+    appeals::withdraw(&executor, offer)
+        .await
+        .expect("Failed to withdraw the appeal");
+    assert!(
+        Appeal::fetch(appeal_id)
+            .fetch_optional(storage.sql())
+            .await
+            .expect("Failed to fetch the appeal")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_failed_acceptance_leaves_appeal_submitted() {
+    let storage = match storage()
+        .await
+        .expect("Failed to connect to the test databases")
+    {
+        Some(storage) => storage,
+        None => return,
+    };
+    let guild_id = guild_id();
+    let mut config = ModerationConfig::new();
+    config.mut_appeals().set_channel_id(1);
+    storage
+        .redis()
+        .guild(guild_id)
+        .configs()
+        .set(config)
+        .await
+        .expect("Failed to store the test config");
+
+    // Without a token, every request to Discord fails, including the unban.
+    let http = Arc::new(http::Client::new(String::new()));
+    let executor = ActionExecutor::new(user(1), http, storage.clone());
+    let offer = appeals::offer(
+        &executor,
+        guild_id,
+        Id::new(2),
+        ban_actions(),
+        "banned from",
+    )
+    .await
+    .expect("Failed to offer an appeal")
+    .expect("Appeals are enabled");
+
+    // Open appeals cannot be resolved until they are submitted.
+    let err = appeals::resolve(&executor, offer.appeal_id, &user(3), true)
+        .await
+        .expect_err("Resolved an appeal that was not submitted");
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(AppealError::AlreadyResolved)
+    ));
+
+    Appeal::submit(offer.appeal_id, "Please")
+        .fetch_one(storage.sql())
+        .await
+        .expect("Failed to submit the appeal");
+    assert!(
+        appeals::resolve(&executor, offer.appeal_id, &user(3), true)
+            .await
+            .is_err()
+    );
+    let appeal = Appeal::fetch(offer.appeal_id)
+        .fetch_one(storage.sql())
+        .await
+        .expect("Failed to fetch the appeal");
+    assert_eq!(appeal.status, AppealStatus::Submitted);
+}
@@ -0,0 +1,202 @@
+use hourai::{
+    http,
+    models::id::Id,
+    proto::{
+        action::{Action, BanMember_Type},
+        escalation::EscalationLadderRung,
+        guild_configs::ModerationConfig,
+    },
+};
+use hourai_sql::EscalationEntry;
+use hourai_storage::{
+    actions::{ActionExecutor, Operation},
+    escalation::EscalationManager,
+};
+use std::sync::Arc;
+
+use hourai_testing::{guild_id, storage, user};
+
+fn rung(display_name: &str, actions: Vec<Action>) -> EscalationLadderRung {
+    let mut rung = EscalationLadderRung::new();
+    rung.set_display_name(display_name.to_owned());
+    rung.set_action(actions.into());
+    rung
+}
+
+#[tokio::test]
+async fn test_escalation_ladder_dry_run() {
+    let storage = match storage()
+        .await
+        .expect("Failed to connect to the test databases")
+    {
+        Some(storage) => storage,
+        None => return,
+    };
+    let guild_id = guild_id();
+    let user_id = Id::new(2);
+
+    let mut ban = Action::new();
+    ban.mut_ban().set_field_type(BanMember_Type::BAN);
+    ban.set_duration(3600);
+    let mut config = ModerationConfig::new();
+    let ladder = config.mut_escalation_ladder();
+    ladder.mut_rung().push(rung("Warning", Vec::new()));
+    ladder.mut_rung().push(rung("Temporary Ban", vec![ban]));
+    storage
+        .redis()
+        .guild(guild_id)
+        .configs()
+        .set(config)
+        .await
+        .expect("Failed to store the test config");
+
+    let http = Arc::new(http::Client::new(String::new()));
+    let executor = ActionExecutor::new(user(1), http, storage.clone()).dry_run();
+    let history = EscalationManager::new(executor.clone())
+        .guild(guild_id)
+        .await
+        .expect("Failed to load the test config")
+        .fetch_history(user_id)
+        .await
+        .expect("Failed to fetch the escalation history");
+    let escalation = history
+        .apply_delta(&user(3), "Test", 2, true)
+        .await
+        .expect("Failed to escalate");
+    assert_eq!(escalation.current_level, 1);
+    assert_eq!(escalation.entry.display_name, "Temporary Ban");
+
+    let operations = executor.take_operations();
+    assert!(operations.contains(&Operation::Ban {
+        guild_id,
+        user_id,
+        delete_message_days: 0,
+    }));
+    assert!(
+        operations
+            .iter()
+            .any(|op| matches!(op, Operation::ScheduleUndo { .. }))
+    );
+    assert!(operations.contains(&Operation::RecordEscalation {
+        guild_id,
+        user_id,
+        level: 1,
+        display_name: "Temporary Ban".to_owned(),
+    }));
+    // Escalation rungs are recorded by their escalation, not as cases of their own.
+    assert!(
+        !operations
+            .iter()
+            .any(|op| matches!(op, Operation::RecordCase { .. }))
+    );
+
+    // Nothing was written.
+    let entries = EscalationEntry::fetch(guild_id, user_id)
+        .fetch_all(storage.sql())
+        .await
+        .expect("Failed to fetch escalation entries");
+    assert!(entries.is_empty());
+}
+
+#[tokio::test]
+async fn test_event_dry_run() {
+    let storage = match storage()
+        .await
+        .expect("Failed to connect to the test databases")
+    {
+        Some(storage) => storage,
+        None => return,
+    };
+    let guild_id = guild_id();
+    let user_id = Id::new(2);
+
+    let http = Arc::new(http::Client::new(String::new()));
+    let executor = ActionExecutor::new(user(1), http, storage.clone()).dry_run();
+    let mut kick = Action::new();
+    kick.set_guild_id(guild_id.get());
+    kick.set_user_id(user_id.get());
+    kick.mut_kick();
+    let event = executor
+        .execute_event(
+            guild_id,
+            Default::default(),
+            vec![kick],
+            &Default::default(),
+        )
+        .await;
+
+    assert!(
+        event
+            .get_results()
+            .iter()
+            .all(|result| result.get_success())
+    );
+    assert_eq!(
+        executor.take_operations(),
+        vec![
+            Operation::Kick { guild_id, user_id },
+            Operation::RecordCase {
+                guild_id,
+                target: hourai_storage::cases::CaseTarget::User(user_id),
+                action: "Kick",
+            },
+            Operation::RecordEvent { guild_id },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_escalation_notification_dry_run() {
+    let storage = match storage()
+        .await
+        .expect("Failed to connect to the test databases")
+    {
+        Some(storage) => storage,
+        None => return,
+    };
+    let http = Arc::new(http::Client::new(String::new()));
+    let executor = ActionExecutor::new(user(1), http, storage.clone()).dry_run();
+    let user_id = Id::new(2);
+
+    for notify_users in [false, true] {
+        let guild_id = guild_id();
+        let mut config = ModerationConfig::new();
+        let ladder = config.mut_escalation_ladder();
+        ladder.mut_rung().push(rung("Warning", Vec::new()));
+        ladder.set_notify_users(notify_users);
+        storage
+            .redis()
+            .guild(guild_id)
+            .configs()
+            .set(config)
+            .await
+            .expect("Failed to store the test config");
+
+        EscalationManager::new(executor.clone())
+            .guild(guild_id)
+            .await
+            .expect("Failed to load the test config")
+            .fetch_history(user_id)
+            .await
+            .expect("Failed to fetch the escalation history")
+            .apply_delta(&user(3), "Test", 1, true)
+            .await
+            .expect("Failed to escalate");
+
+        let messages: Vec<_> = executor
+            .take_operations()
+            .into_iter()
+            .filter_map(|op| match op {
+                Operation::SendDirectMessage { content, .. } => Some(content),
+                _ => None,
+            })
+            .collect();
+        if notify_users {
+            // The built-in message, at the top of the ladder.
+            assert_eq!(messages.len(), 1);
+            assert!(messages[0].contains("Next level: None"));
+        } else {
+            assert!(messages.is_empty());
+        }
+    }
+}
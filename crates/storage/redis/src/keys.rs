@@ -23,6 +23,21 @@ pub enum CacheKey {
     VoiceState(TwilightId<GuildMarker>),
     /// Resume State
     ResumeState(/* Name */ String),
+    /// Per-server message filter rule hit counts. Stored as hashes of rule key to hit count,
+    /// bucketed by the hour since the Unix epoch.
+    FilterStats(TwilightId<GuildMarker>, /* Hour */ u64),
+    /// Active automation rule cooldowns. Present while the rule cannot fire in the given scope,
+    /// expiring with the cooldown.
+    AutoCooldown(
+        TwilightId<GuildMarker>,
+        /* Rule */ u64,
+        /* Scope */ u64,
+    ),
+    /// Per-server counts of automation rule firings, bucketed by the minute since the Unix
+    /// epoch.
+    AutoFirings(TwilightId<GuildMarker>, /* Minute */ u64),
+    /// Action sets awaiting confirmation from a preview's buttons. Expire if not confirmed.
+    StoredActions(/* ID */ u64),
 }
 
 impl CacheKey {
@@ -34,6 +49,10 @@ impl CacheKey {
             Self::Guild(_) => 4_u8,
             Self::VoiceState(_) => 5_u8,
             Self::ResumeState(_) => 6_u8,
+            Self::FilterStats(_, _) => 7_u8,
+            Self::AutoCooldown(_, _, _) => 8_u8,
+            Self::AutoFirings(_, _) => 9_u8,
+            Self::StoredActions(_) => 10_u8,
         }
     }
 }
@@ -51,6 +70,16 @@ impl ToRedisArgs for CacheKey {
             Self::ResumeState(key) => {
                 PrefixedKey(self.prefix(), key.as_str()).write_redis_args(out)
             }
+            Self::FilterStats(id, hour) => {
+                PrefixedKey(self.prefix(), (id.get(), *hour)).write_redis_args(out)
+            }
+            Self::AutoCooldown(id, rule, scope) => {
+                PrefixedKey(self.prefix(), (id.get(), *rule, *scope)).write_redis_args(out)
+            }
+            Self::AutoFirings(id, minute) => {
+                PrefixedKey(self.prefix(), (id.get(), *minute)).write_redis_args(out)
+            }
+            Self::StoredActions(id) => PrefixedKey(self.prefix(), *id).write_redis_args(out),
         }
     }
 }
@@ -160,6 +189,16 @@ impl ToRedisArgs for PrefixedKey<(u64, u64)> {
     }
 }
 
+impl ToRedisArgs for PrefixedKey<(u64, u64, u64)> {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        let mut key_enc = [self.0; 25];
+        BigEndian::write_u64(&mut key_enc[1..9], self.1.0);
+        BigEndian::write_u64(&mut key_enc[9..17], self.1.1);
+        BigEndian::write_u64(&mut key_enc[17..25], self.1.2);
+        out.write_arg(&key_enc[..]);
+    }
+}
+
 impl ToRedisArgs for PrefixedKey<&str> {
     fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
         self.0.write_redis_args(out);
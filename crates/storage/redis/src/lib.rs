@@ -20,13 +20,15 @@ use hourai::{
         id::{Id as TwilightId, marker::*},
         voice::VoiceState,
     },
-    proto::cache::*,
+    proto::{action::ActionSet, cache::*},
 };
 use std::{
     borrow::Cow,
     cmp::{Ord, Ordering},
     collections::{HashMap, HashSet},
     ops::Deref,
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tracing::debug;
 
@@ -76,6 +78,10 @@ impl RedisClient {
     pub fn resume_states(&self) -> ResumeStates {
         ResumeStates(self.clone())
     }
+
+    pub fn stored_actions(&self) -> StoredActions {
+        StoredActions(self.clone())
+    }
 }
 
 pub struct OnlineStatus(RedisClient);
@@ -121,9 +127,23 @@ impl OnlineStatus {
     }
 }
 
+/// Per-guild, per-config counters that are incremented every time a config is written by this
+/// process. Used to invalidate in-memory caches derived from the configs.
+static CONFIG_GENERATIONS: LazyLock<dashmap::DashMap<(TwilightId<GuildMarker>, u8), u64>> =
+    LazyLock::new(dashmap::DashMap::new);
+
 pub struct GuildConfig(GuildCache);
 
 impl GuildConfig {
+    /// Gets the current generation of a config. This changes every time the config is written
+    /// via `set`. Writes from other processes are not tracked.
+    pub fn generation<T: CachedGuildConfig>(&self) -> u64 {
+        CONFIG_GENERATIONS
+            .get(&(self.0.guild_id, T::SUBKEY))
+            .map(|generation| *generation)
+            .unwrap_or(0)
+    }
+
     pub async fn fetch<T: ::protobuf::Message + CachedGuildConfig>(&mut self) -> Result<Option<T>> {
         let key = CacheKey::GuildConfigs(self.0.guild_id);
         let response: Option<Compressed<Protobuf<T>>> = self
@@ -150,6 +170,9 @@ impl GuildConfig {
             .connection_mut()
             .hset(key, vec![T::SUBKEY], Compressed(Protobuf(value)))
             .await?;
+        *CONFIG_GENERATIONS
+            .entry((self.0.guild_id, T::SUBKEY))
+            .or_insert(0) += 1;
         Ok(())
     }
 }
@@ -292,6 +315,115 @@ impl VoiceStateCache {
     }
 }
 
+pub struct FilterStats(GuildCache);
+
+impl FilterStats {
+    /// The number of seconds in a single bucket.
+    const BUCKET_SIZE: u64 = 3600;
+    /// How long buckets are kept before expiring. Slightly longer than the longest window
+    /// queried to avoid dropping buckets at the edge of the window.
+    const RETENTION: usize = 32 * 24 * 3600;
+
+    fn current_bucket() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() / Self::BUCKET_SIZE)
+            .unwrap_or(0)
+    }
+
+    /// Records a single hit for a message filter rule in the current bucket.
+    pub async fn record_hit(&mut self, rule: &str) -> Result<()> {
+        let key = CacheKey::FilterStats(self.0.guild_id, Self::current_bucket());
+        let _: () = redis::pipe()
+            .atomic()
+            .hincr(&key, rule, 1_u64)
+            .ignore()
+            .expire(&key, Self::RETENTION)
+            .ignore()
+            .query_async(self.0.redis.connection_mut())
+            .await?;
+        Ok(())
+    }
+
+    /// Gets the total number of hits for each rule over each window, given as a number of hours
+    /// back from now, including the current partial hour. The buckets are fetched once for all
+    /// windows. Rules without any hits in a window are not included in its totals.
+    pub async fn hits<const N: usize>(
+        &mut self,
+        windows: [u64; N],
+    ) -> Result<[HashMap<String, u64>; N]> {
+        let current = Self::current_bucket();
+        let longest = windows.iter().copied().max().unwrap_or(0);
+        let mut pipe = redis::pipe();
+        for bucket in (0..longest).map_while(|age| current.checked_sub(age)) {
+            pipe.hgetall(CacheKey::FilterStats(self.0.guild_id, bucket));
+        }
+        // Ordered from the current bucket back.
+        let buckets: Vec<HashMap<String, u64>> =
+            pipe.query_async(self.0.redis.connection_mut()).await?;
+        Ok(windows.map(|hours| {
+            let mut totals = HashMap::new();
+            for (rule, count) in buckets.iter().take(hours as usize).flatten() {
+                *totals.entry(rule.clone()).or_insert(0) += count;
+            }
+            totals
+        }))
+    }
+}
+
+pub struct AutoLimits(GuildCache);
+
+impl AutoLimits {
+    /// How long firing counts are kept. Only the current minute is ever read.
+    const FIRINGS_RETENTION: usize = 120;
+
+    /// Starts a cooldown for a rule in a scope, unless one is already active. Returns true if
+    /// the cooldown was started, and false if the rule is still cooling down.
+    pub async fn try_start_cooldown(
+        &mut self,
+        rule: u64,
+        scope: u64,
+        seconds: u64,
+    ) -> Result<bool> {
+        let key = CacheKey::AutoCooldown(self.0.guild_id, rule, scope);
+        let started: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(1_u8)
+            .arg("NX")
+            .arg("EX")
+            .arg(seconds)
+            .query_async(self.0.redis.connection_mut())
+            .await?;
+        Ok(started.is_some())
+    }
+
+    /// Ends a rule's cooldown in a scope early. Used when a rule that started its cooldown is
+    /// then suppressed for another reason and does not fire.
+    pub async fn clear_cooldown(&mut self, rule: u64, scope: u64) -> Result<()> {
+        let key = CacheKey::AutoCooldown(self.0.guild_id, rule, scope);
+        let _: () = self.0.redis.connection_mut().del(key).await?;
+        Ok(())
+    }
+
+    /// Records a rule firing in the current minute. Returns the number of firings in the
+    /// current minute, including this one.
+    pub async fn record_firing(&mut self) -> Result<u64> {
+        let minute = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() / 60)
+            .unwrap_or(0);
+        let key = CacheKey::AutoFirings(self.0.guild_id, minute);
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1_u64)
+            .expire(&key, Self::FIRINGS_RETENTION)
+            .ignore()
+            .query_async(self.0.redis.connection_mut())
+            .await?;
+        Ok(count)
+    }
+}
+
 #[derive(Clone)]
 pub struct GuildCache {
     guild_id: TwilightId<GuildMarker>,
@@ -307,6 +439,14 @@ impl GuildCache {
         VoiceStateCache(self.clone())
     }
 
+    pub fn filter_stats(&self) -> FilterStats {
+        FilterStats(self.clone())
+    }
+
+    pub fn auto_limits(&self) -> AutoLimits {
+        AutoLimits(self.clone())
+    }
+
     pub async fn save(&mut self, guild: &hourai::models::guild::Guild) -> Result<()> {
         assert!(self.guild_id == guild.id);
         let key = CacheKey::Guild(guild.id);
@@ -314,7 +454,7 @@ impl GuildCache {
         pipe.atomic().del(key).ignore();
         pipe.add_command(self.save_resource_cmd(guild.id, guild))
             .ignore();
-        for channel in guild.channels.iter() {
+        for channel in guild.channels.iter().chain(guild.threads.iter()) {
             pipe.add_command(self.save_resource_cmd(channel.id, channel))
                 .ignore();
         }
@@ -557,7 +697,7 @@ impl GuildResource for Guild {
     type Subkey = ();
     const PREFIX: u8 = 1_u8;
 
-    #[expect(clippy::expect_used)]
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
     fn from_key(_: GuildKey) -> TwilightId<Self::Marker> {
         Option::<TwilightId<Self::Marker>>::None
             .expect("Converting GuildKey to Id<GuildMarker> is not supported")
@@ -592,7 +732,7 @@ impl GuildResource for PartialGuild {
     type Subkey = ();
     const PREFIX: u8 = 1_u8;
 
-    #[expect(clippy::expect_used)]
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
     fn from_key(_: GuildKey) -> TwilightId<Self::Marker> {
         Option::<TwilightId<Self::Marker>>::None
             .expect("Converting GuildKey to Id<GuildMarker> is not supported")
@@ -627,7 +767,7 @@ impl GuildResource for Channel {
     type Subkey = u64;
     const PREFIX: u8 = 3_u8;
 
-    #[expect(clippy::expect_used)]
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
     fn from_key(key: GuildKey) -> TwilightId<Self::Marker> {
         if let GuildKey::Channel(id) = key {
             id
@@ -646,6 +786,9 @@ impl ToProto for Channel {
         if let Some(ref name) = self.name {
             proto.set_name(name.clone());
         }
+        if let Some(parent_id) = self.parent_id {
+            proto.set_parent_id(parent_id.get());
+        }
         proto
     }
 }
@@ -655,7 +798,7 @@ impl GuildResource for Role {
     type Subkey = u64;
     const PREFIX: u8 = 2_u8;
 
-    #[expect(clippy::expect_used)]
+    #[expect(clippy::expect_used, clippy::unnecessary_literal_unwrap)]
     fn from_key(key: GuildKey) -> TwilightId<Self::Marker> {
         if let GuildKey::Role(id) = key {
             id
@@ -677,6 +820,42 @@ impl ToProto for Role {
     }
 }
 
+/// Action sets previewed by moderators, kept until the preview is confirmed or cancelled.
+pub struct StoredActions(RedisClient);
+
+impl StoredActions {
+    /// How long stored actions can be confirmed for. Matches the lifetime of the interaction
+    /// token used to report the results.
+    const LIFETIME: usize = 900;
+
+    pub async fn store(&mut self, id: u64, actions: ActionSet) -> Result<()> {
+        let _: () = self
+            .0
+            .connection_mut()
+            .set_ex(
+                CacheKey::StoredActions(id),
+                Protobuf(actions),
+                Self::LIFETIME,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a stored action set and returns it. Returns None if it expired or was already
+    /// taken, so a set is only ever taken once.
+    pub async fn take(&mut self, id: u64) -> Result<Option<ActionSet>> {
+        let key = CacheKey::StoredActions(id);
+        let (actions,): (Option<Protobuf<ActionSet>>,) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .del(&key)
+            .ignore()
+            .query_async(self.0.connection_mut())
+            .await?;
+        Ok(actions.map(|actions| actions.0))
+    }
+}
+
 pub struct ResumeStates(RedisClient);
 
 impl ResumeStates {
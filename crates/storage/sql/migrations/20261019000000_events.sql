@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS events (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    "timestamp" TIMESTAMPTZ NOT NULL,
+    data BYTEA NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_guild_id_idx ON events (guild_id, "timestamp");
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS scheduled_rules (
+    guild_id BIGINT NOT NULL,
+    rule_id BIGINT NOT NULL,
+    next_run TIMESTAMPTZ NOT NULL,
+    data BYTEA NOT NULL,
+    PRIMARY KEY (guild_id, rule_id)
+);
+CREATE INDEX IF NOT EXISTS scheduled_rules_next_run_idx ON scheduled_rules (next_run);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS case_counters (
+    guild_id BIGINT PRIMARY KEY,
+    last_case INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cases (
+    guild_id BIGINT NOT NULL,
+    case_number INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    user_id BIGINT NOT NULL,
+    moderator_id BIGINT NOT NULL,
+    reason TEXT,
+    duration BIGINT,
+    modlog_message_id BIGINT,
+    "timestamp" TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (guild_id, case_number)
+);
+CREATE INDEX IF NOT EXISTS cases_user_id_idx ON cases (guild_id, user_id);
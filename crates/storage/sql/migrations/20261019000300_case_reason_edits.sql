@@ -0,0 +1,13 @@
+ALTER TABLE cases ADD COLUMN IF NOT EXISTS modlog_channel_id BIGINT;
+
+CREATE TABLE IF NOT EXISTS case_reason_edits (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    case_number INTEGER NOT NULL,
+    editor_id BIGINT NOT NULL,
+    previous_reason TEXT,
+    reason TEXT NOT NULL,
+    "timestamp" TIMESTAMPTZ NOT NULL,
+    FOREIGN KEY (guild_id, case_number) REFERENCES cases (guild_id, case_number) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS case_reason_edits_case_idx ON case_reason_edits (guild_id, case_number);
@@ -0,0 +1,2 @@
+ALTER TABLE members ADD COLUMN IF NOT EXISTS joined_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS members_joined_at_idx ON members (guild_id, joined_at);
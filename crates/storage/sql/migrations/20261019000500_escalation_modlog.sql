@@ -0,0 +1,4 @@
+ALTER TABLE escalation_histories ADD COLUMN IF NOT EXISTS modlog_channel_id BIGINT;
+ALTER TABLE escalation_histories ADD COLUMN IF NOT EXISTS modlog_message_id BIGINT;
+CREATE INDEX IF NOT EXISTS escalation_histories_subject_idx
+    ON escalation_histories (guild_id, subject_id);
@@ -0,0 +1,2 @@
+ALTER TABLE escalation_histories ADD COLUMN IF NOT EXISTS revoked_at TIMESTAMPTZ;
+ALTER TABLE escalation_histories ADD COLUMN IF NOT EXISTS revoked_by BIGINT;
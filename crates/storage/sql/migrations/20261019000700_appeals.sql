@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS appeals (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    actions BYTEA NOT NULL,
+    escalation_entry_id INTEGER REFERENCES escalation_histories(id),
+    status SMALLINT NOT NULL DEFAULT 0,
+    content TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    submitted_at TIMESTAMPTZ,
+    resolved_at TIMESTAMPTZ,
+    resolved_by BIGINT
+);
+CREATE INDEX IF NOT EXISTS appeals_user_idx ON appeals (guild_id, user_id);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS notes (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    author_id BIGINT NOT NULL,
+    content TEXT NOT NULL,
+    "timestamp" TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX IF NOT EXISTS notes_user_id_idx ON notes (guild_id, user_id);
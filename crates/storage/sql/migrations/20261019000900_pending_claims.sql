@@ -0,0 +1,31 @@
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS failed_at TIMESTAMPTZ;
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS last_error TEXT;
+CREATE INDEX IF NOT EXISTS pending_actions_timestamp_idx ON pending_actions ("timestamp")
+    WHERE failed_at IS NULL;
+
+ALTER TABLE pending_deescalations ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
+ALTER TABLE pending_deescalations ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pending_deescalations ADD COLUMN IF NOT EXISTS failed_at TIMESTAMPTZ;
+ALTER TABLE pending_deescalations ADD COLUMN IF NOT EXISTS last_error TEXT;
+CREATE INDEX IF NOT EXISTS pending_deescalations_expiration_idx
+    ON pending_deescalations (expiration) WHERE failed_at IS NULL;
+
+-- Wakes up the schedulers when new work is scheduled. The channel is the table's name.
+CREATE OR REPLACE FUNCTION notify_pending_change() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify(TG_TABLE_NAME, '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS pending_actions_notify ON pending_actions;
+CREATE TRIGGER pending_actions_notify
+    AFTER INSERT ON pending_actions
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_pending_change();
+
+DROP TRIGGER IF EXISTS pending_deescalations_notify ON pending_deescalations;
+CREATE TRIGGER pending_deescalations_notify
+    AFTER INSERT OR UPDATE OF expiration ON pending_deescalations
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_pending_change();
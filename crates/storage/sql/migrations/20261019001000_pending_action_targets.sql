@@ -0,0 +1,5 @@
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS guild_id BIGINT;
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS user_id BIGINT;
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS moderator_id BIGINT;
+ALTER TABLE pending_actions ADD COLUMN IF NOT EXISTS case_number INTEGER;
+CREATE INDEX IF NOT EXISTS pending_actions_guild_id_idx ON pending_actions (guild_id, user_id);
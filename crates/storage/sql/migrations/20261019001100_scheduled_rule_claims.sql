@@ -0,0 +1 @@
+ALTER TABLE scheduled_rules ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
@@ -0,0 +1,4 @@
+-- Actions on channels, like locks and slowmodes, are recorded against the channel instead of a
+-- user.
+ALTER TABLE cases ALTER COLUMN user_id DROP NOT NULL;
+ALTER TABLE cases ADD COLUMN IF NOT EXISTS channel_id BIGINT;
@@ -0,0 +1,6 @@
+-- Wakes up the pending action scheduler when a failed action is retried, not only when one is
+-- scheduled, so retries are not delayed until the scheduler next polls.
+DROP TRIGGER IF EXISTS pending_actions_notify ON pending_actions;
+CREATE TRIGGER pending_actions_notify
+    AFTER INSERT OR UPDATE OF timestamp ON pending_actions
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_pending_change();
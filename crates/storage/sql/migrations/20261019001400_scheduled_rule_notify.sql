@@ -0,0 +1,5 @@
+-- Wakes up the scheduled event scheduler when an event is scheduled, so it does not need to poll.
+DROP TRIGGER IF EXISTS scheduled_rules_notify ON scheduled_rules;
+CREATE TRIGGER scheduled_rules_notify
+    AFTER INSERT ON scheduled_rules
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_pending_change();
@@ -8,12 +8,17 @@ use hourai::{
         id::{Id, marker::*},
         util::{Timestamp, image_hash::ImageHash},
     },
-    proto::action::{Action, ActionSet},
+    proto::{
+        action::{Action, ActionSet},
+        auto_config::ScheduledEvent,
+        event::Event,
+    },
 };
 use sqlx::{
     QueryBuilder,
     types::chrono::{DateTime, NaiveDateTime, Utc},
 };
+use std::time::Duration;
 
 pub type SqlDatabase = sqlx::Postgres;
 pub type SqlQuery<'a> = sqlx::query::Query<
@@ -299,6 +304,7 @@ pub struct Member {
     pub present: bool,
     pub premium_since: Option<DateTime<Utc>>,
     pub avatar: Option<String>,
+    pub joined_at: Option<DateTime<Utc>>,
 }
 
 impl From<(Id<GuildMarker>, &TwilightMember)> for Member {
@@ -313,6 +319,7 @@ impl From<(Id<GuildMarker>, &TwilightMember)> for Member {
             present: true,
             premium_since: premium,
             avatar: member.avatar.map(|hash| hash.to_string()),
+            joined_at: member.joined_at.as_ref().map(to_datetime),
         }
     }
 }
@@ -329,6 +336,7 @@ impl From<(Id<GuildMarker>, TwilightMember)> for Member {
             present: true,
             premium_since: premium,
             avatar: member.avatar.map(|hash| hash.to_string()),
+            joined_at: member.joined_at.as_ref().map(to_datetime),
         }
     }
 }
@@ -345,6 +353,7 @@ impl From<&MemberUpdate> for Member {
             present: true,
             premium_since: premium,
             avatar: member.avatar.map(|hash| hash.to_string()),
+            joined_at: member.joined_at.as_ref().map(to_datetime),
         }
     }
 }
@@ -388,15 +397,17 @@ impl Member {
                 present,
                 bot,
                 premium_since,
-                avatar
+                avatar,
+                joined_at
             ) \
-            VALUES ($1, $2, $3, $4, true, $5, $6, $7) \
+            VALUES ($1, $2, $3, $4, true, $5, $6, $7, $8) \
             ON CONFLICT ON CONSTRAINT members_pkey \
             DO UPDATE SET \
                 role_ids = excluded.role_ids, \
                 nickname = excluded.nickname, \
                 premium_since = excluded.premium_since, \
                 avatar = excluded.avatar, \
+                joined_at = COALESCE(excluded.joined_at, members.joined_at), \
                 bot = excluded.bot, \
                 last_seen = now(), \
                 present = true",
@@ -408,6 +419,7 @@ impl Member {
         .bind(self.bot)
         .bind(self.premium_since)
         .bind(self.avatar)
+        .bind(self.joined_at)
     }
 
     pub fn has_nitro<'a>(user_id: Id<UserMarker>) -> SqlQueryAs<'a, (bool,)> {
@@ -462,6 +474,45 @@ impl Member {
             .bind(role_ids)
     }
 
+    /// Finds the present, non-bot members of a guild that match all of the provided criteria,
+    /// newest joins first. `name_pattern` is a Postgres regex matched against the member's
+    /// nickname and current username.
+    pub fn find_matching<'a>(
+        guild_id: Id<GuildMarker>,
+        joined_after: Option<DateTime<Utc>>,
+        created_after: Option<Id<UserMarker>>,
+        missing_role: Option<Id<RoleMarker>>,
+        name_pattern: Option<&'a str>,
+        limit: i64,
+    ) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "SELECT * FROM members \
+             WHERE guild_id = $1 AND present AND NOT bot \
+               AND ($2::timestamptz IS NULL OR joined_at >= $2) \
+               AND ($3::bigint IS NULL OR user_id >= $3) \
+               AND ($4::bigint IS NULL OR NOT ($4 = ANY(role_ids))) \
+               AND ($5::text IS NULL OR nickname ~ $5 OR ( \
+                   SELECT name FROM usernames \
+                   WHERE usernames.user_id = members.user_id \
+                   ORDER BY timestamp DESC \
+                   LIMIT 1) ~ $5) \
+             ORDER BY joined_at DESC NULLS LAST \
+             LIMIT $6",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(joined_after)
+        .bind(created_after.map(|id| id.get() as i64))
+        .bind(missing_role.map(|id| id.get() as i64))
+        .bind(name_pattern)
+        .bind(limit)
+    }
+
+    /// Checks that a pattern is a valid Postgres regex, as used by `find_matching`. Fails with a
+    /// database error if it is not.
+    pub fn check_pattern<'a>(pattern: &'a str) -> SqlQuery<'a> {
+        sqlx::query("SELECT '' ~ $1").bind(pattern)
+    }
+
     /// Marks all members as not present in preparation for repopulating the column.
     pub fn clear_present_shard<'a>(shard_id: u64, shard_total: u64) -> SqlQuery<'a> {
         sqlx::query("UPDATE members SET present = false WHERE (guild_id >> 22) % $2 = $1")
@@ -488,6 +539,8 @@ impl Member {
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct EscalationEntry {
+    /// Assigned when the entry is inserted.
+    pub id: i32,
     pub guild_id: i64,
     pub subject_id: i64,
     pub authorizer_id: i64,
@@ -496,6 +549,12 @@ pub struct EscalationEntry {
     pub timestamp: DateTime<Utc>,
     pub action: Protobuf<ActionSet>,
     pub level_delta: i32,
+    pub modlog_channel_id: Option<i64>,
+    pub modlog_message_id: Option<i64>,
+    /// Set when the entry is pardoned. Pardoned entries no longer count towards the user's
+    /// escalation level.
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_by: Option<i64>,
 }
 
 impl EscalationEntry {
@@ -545,6 +604,401 @@ impl EscalationEntry {
         .bind(self.level_delta)
         .bind(self.timestamp)
     }
+
+    pub fn set_modlog_message<'a>(
+        &self,
+        channel_id: Id<ChannelMarker>,
+        message_id: Id<MessageMarker>,
+    ) -> SqlQuery<'a> {
+        sqlx::query(
+            "UPDATE escalation_histories SET modlog_channel_id = $2, modlog_message_id = $3 \
+             WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(channel_id.get() as i64)
+        .bind(message_id.get() as i64)
+    }
+
+    pub fn revoke<'a>(
+        &self,
+        revoked_by: Id<UserMarker>,
+        revoked_at: DateTime<Utc>,
+    ) -> SqlQuery<'a> {
+        sqlx::query(
+            "UPDATE escalation_histories SET revoked_by = $2, revoked_at = $3 WHERE id = $1",
+        )
+        .bind(self.id)
+        .bind(revoked_by.get() as i64)
+        .bind(revoked_at)
+    }
+
+    #[inline(always)]
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+/// A moderation action taken against a user, numbered per guild.
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct Case {
+    pub guild_id: i64,
+    /// Assigned when the case is inserted.
+    pub case_number: i32,
+    pub action: String,
+    /// The user the action was taken against. Unset for actions on channels.
+    pub user_id: Option<i64>,
+    /// The channel the action was taken in, for actions on channels.
+    pub channel_id: Option<i64>,
+    pub moderator_id: i64,
+    pub reason: Option<String>,
+    /// How long the action lasts, in seconds. Unset for permanent actions.
+    pub duration: Option<i64>,
+    pub modlog_channel_id: Option<i64>,
+    pub modlog_message_id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Case {
+    pub fn guild_id(&self) -> Id<GuildMarker> {
+        Id::new(self.guild_id as u64)
+    }
+
+    pub fn user_id(&self) -> Option<Id<UserMarker>> {
+        self.user_id.and_then(|id| Id::new_checked(id as u64))
+    }
+
+    pub fn channel_id(&self) -> Option<Id<ChannelMarker>> {
+        self.channel_id.and_then(|id| Id::new_checked(id as u64))
+    }
+
+    pub fn moderator_id(&self) -> Id<UserMarker> {
+        Id::new(self.moderator_id as u64)
+    }
+
+    pub fn fetch<'a>(guild_id: Id<GuildMarker>, case_number: i32) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as("SELECT * FROM cases WHERE guild_id = $1 AND case_number = $2")
+            .bind(guild_id.get() as i64)
+            .bind(case_number)
+    }
+
+    /// Fetches the most recent cases against a user, newest first.
+    pub fn fetch_user<'a>(
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        limit: i64,
+    ) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "SELECT * FROM cases WHERE guild_id = $1 AND user_id = $2 \
+             ORDER BY case_number DESC LIMIT $3",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(user_id.get() as i64)
+        .bind(limit)
+    }
+
+    /// Inserts the case with the guild's next case number, returning the number.
+    pub fn insert<'a>(&'a self) -> SqlQueryAs<'a, (i32,)> {
+        sqlx::query_as(
+            "WITH counter AS ( \
+                INSERT INTO case_counters (guild_id, last_case) VALUES ($1, 1) \
+                ON CONFLICT ON CONSTRAINT case_counters_pkey \
+                DO UPDATE SET last_case = case_counters.last_case + 1 \
+                RETURNING last_case \
+            ) \
+            INSERT INTO cases ( \
+                guild_id, \
+                case_number, \
+                action, \
+                user_id, \
+                channel_id, \
+                moderator_id, \
+                reason, \
+                duration, \
+                modlog_message_id, \
+                timestamp \
+            ) \
+            SELECT $1, last_case, $2, $3, $4, $5, $6, $7, $8, $9 FROM counter \
+            RETURNING case_number",
+        )
+        .bind(self.guild_id)
+        .bind(&self.action)
+        .bind(self.user_id)
+        .bind(self.channel_id)
+        .bind(self.moderator_id)
+        .bind(&self.reason)
+        .bind(self.duration)
+        .bind(self.modlog_message_id)
+        .bind(self.timestamp)
+    }
+
+    pub fn set_modlog_message<'a>(
+        &self,
+        channel_id: Id<ChannelMarker>,
+        message_id: Id<MessageMarker>,
+    ) -> SqlQuery<'a> {
+        sqlx::query(
+            "UPDATE cases SET modlog_channel_id = $3, modlog_message_id = $4 \
+             WHERE guild_id = $1 AND case_number = $2",
+        )
+        .bind(self.guild_id)
+        .bind(self.case_number)
+        .bind(channel_id.get() as i64)
+        .bind(message_id.get() as i64)
+    }
+
+    pub fn set_reason<'a>(&self, reason: &'a str) -> SqlQuery<'a> {
+        sqlx::query("UPDATE cases SET reason = $3 WHERE guild_id = $1 AND case_number = $2")
+            .bind(self.guild_id)
+            .bind(self.case_number)
+            .bind(reason)
+    }
+}
+
+/// A change to the reason of a case.
+#[derive(Debug, sqlx::FromRow)]
+pub struct CaseReasonEdit {
+    pub guild_id: i64,
+    pub case_number: i32,
+    pub editor_id: i64,
+    pub previous_reason: Option<String>,
+    pub reason: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl CaseReasonEdit {
+    pub fn editor_id(&self) -> Id<UserMarker> {
+        Id::new(self.editor_id as u64)
+    }
+
+    /// Fetches the reason edits of a case, oldest first.
+    pub fn fetch<'a>(guild_id: Id<GuildMarker>, case_number: i32) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "SELECT guild_id, case_number, editor_id, previous_reason, reason, timestamp \
+             FROM case_reason_edits WHERE guild_id = $1 AND case_number = $2 \
+             ORDER BY timestamp",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(case_number)
+    }
+
+    pub fn insert<'a>(&'a self) -> SqlQuery<'a> {
+        sqlx::query(
+            "INSERT INTO case_reason_edits \
+             (guild_id, case_number, editor_id, previous_reason, reason, timestamp) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(self.guild_id)
+        .bind(self.case_number)
+        .bind(self.editor_id)
+        .bind(&self.previous_reason)
+        .bind(&self.reason)
+        .bind(self.timestamp)
+    }
+}
+
+/// A non-punitive note left on a user by a moderator.
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct Note {
+    /// Assigned when the note is inserted.
+    pub id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub author_id: i64,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Note {
+    /// Fetches the most recent notes on a user, newest first.
+    pub fn fetch_user<'a>(
+        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
+        limit: i64,
+    ) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "SELECT * FROM notes WHERE guild_id = $1 AND user_id = $2 \
+             ORDER BY id DESC LIMIT $3",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(user_id.get() as i64)
+        .bind(limit)
+    }
+
+    pub fn insert(&self) -> SqlQueryAs<'_, (i32,)> {
+        sqlx::query_as(
+            "INSERT INTO notes (guild_id, user_id, author_id, content, timestamp) \
+             VALUES ($1, $2, $3, $4, $5) \
+             RETURNING id",
+        )
+        .bind(self.guild_id)
+        .bind(self.user_id)
+        .bind(self.author_id)
+        .bind(&self.content)
+        .bind(self.timestamp)
+    }
+
+    /// Deletes a note, returning it if it existed in the guild.
+    pub fn delete<'a>(guild_id: Id<GuildMarker>, id: i32) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as("DELETE FROM notes WHERE guild_id = $1 AND id = $2 RETURNING *")
+            .bind(guild_id.get() as i64)
+            .bind(id)
+    }
+}
+
+/// Where an appeal is in its lifecycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, sqlx::Type)]
+#[repr(i16)]
+pub enum AppealStatus {
+    /// Offered to the user, but not submitted yet.
+    Open = 0,
+    /// Submitted by the user and waiting on the moderators.
+    Submitted = 1,
+    Accepted = 2,
+    Denied = 3,
+    /// Being accepted by a moderator, whose undoing of the appealed actions has not finished.
+    Accepting = 4,
+}
+
+/// An appeal of a ban or escalation, offered to the user when the action was taken.
+#[derive(Debug, sqlx::FromRow)]
+pub struct Appeal {
+    /// Assigned when the appeal is inserted.
+    pub id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    /// Secret given only to the user, used to submit the appeal through the web API.
+    pub token: String,
+    /// The actions being appealed.
+    pub actions: Protobuf<ActionSet>,
+    pub escalation_entry_id: Option<i32>,
+    pub status: AppealStatus,
+    pub content: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<i64>,
+}
+
+impl Appeal {
+    pub fn guild_id(&self) -> Id<GuildMarker> {
+        Id::new(self.guild_id as u64)
+    }
+
+    pub fn user_id(&self) -> Id<UserMarker> {
+        Id::new(self.user_id as u64)
+    }
+
+    pub fn fetch<'a>(id: i32) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as("SELECT * FROM appeals WHERE id = $1").bind(id)
+    }
+
+    pub fn fetch_by_token(token: &str) -> SqlQueryAs<'_, Self> {
+        sqlx::query_as("SELECT * FROM appeals WHERE token = $1").bind(token)
+    }
+
+    pub fn insert(&self) -> SqlQueryAs<'_, (i32,)> {
+        sqlx::query_as(
+            "INSERT INTO appeals ( \
+                guild_id, \
+                user_id, \
+                token, \
+                actions, \
+                escalation_entry_id, \
+                status, \
+                created_at \
+            ) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7) \
+            RETURNING id",
+        )
+        .bind(self.guild_id)
+        .bind(self.user_id)
+        .bind(&self.token)
+        .bind(&self.actions)
+        .bind(self.escalation_entry_id)
+        .bind(self.status)
+        .bind(self.created_at)
+    }
+
+    pub fn set_escalation_entry<'a>(id: i32, entry_id: i32) -> SqlQuery<'a> {
+        sqlx::query("UPDATE appeals SET escalation_entry_id = $2 WHERE id = $1")
+            .bind(id)
+            .bind(entry_id)
+    }
+
+    pub fn delete<'a>(id: i32) -> SqlQuery<'a> {
+        sqlx::query("DELETE FROM appeals WHERE id = $1").bind(id)
+    }
+
+    /// Submits an open appeal. Returns no rows if the appeal is not open.
+    pub fn submit(id: i32, content: &str) -> SqlQueryAs<'_, Self> {
+        sqlx::query_as(
+            "UPDATE appeals SET status = $2, content = $3, submitted_at = now() \
+             WHERE id = $1 AND status = $4 \
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(AppealStatus::Submitted)
+        .bind(content)
+        .bind(AppealStatus::Open)
+    }
+
+    /// Claims a submitted appeal for a moderator to accept it, so no one else can accept or deny
+    /// it meanwhile. Claims older than `lease`, such as those of a crashed instance, can be
+    /// claimed again. Returns no rows if the appeal cannot be claimed.
+    pub fn claim_acceptance<'a>(
+        id: i32,
+        resolved_by: Id<UserMarker>,
+        lease: Duration,
+    ) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "UPDATE appeals SET status = $2, resolved_by = $3, resolved_at = now() \
+             WHERE id = $1 AND (status = $4 OR \
+                (status = $2 AND resolved_at < now() - make_interval(secs => $5))) \
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(AppealStatus::Accepting)
+        .bind(resolved_by.get() as i64)
+        .bind(AppealStatus::Submitted)
+        .bind(lease.as_secs_f64())
+    }
+
+    /// Returns an appeal that `resolved_by` failed to accept to the moderators. Does nothing if
+    /// their claim was taken over by someone else.
+    pub fn release<'a>(id: i32, resolved_by: Id<UserMarker>) -> SqlQuery<'a> {
+        sqlx::query(
+            "UPDATE appeals SET status = $2, resolved_by = NULL, resolved_at = NULL \
+             WHERE id = $1 AND status = $3 AND resolved_by = $4",
+        )
+        .bind(id)
+        .bind(AppealStatus::Submitted)
+        .bind(AppealStatus::Accepting)
+        .bind(resolved_by.get() as i64)
+    }
+
+    /// Denies a submitted appeal, or accepts one that `resolved_by` claimed with
+    /// `claim_acceptance`. Returns no rows if the appeal is not in that state.
+    pub fn resolve<'a>(
+        id: i32,
+        status: AppealStatus,
+        resolved_by: Id<UserMarker>,
+    ) -> SqlQueryAs<'a, Self> {
+        let from = if status == AppealStatus::Accepted {
+            AppealStatus::Accepting
+        } else {
+            AppealStatus::Submitted
+        };
+        sqlx::query_as(
+            "UPDATE appeals SET status = $2, resolved_by = $3, resolved_at = now() \
+             WHERE id = $1 AND status = $4 AND (status = $5 OR resolved_by = $3) \
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(status)
+        .bind(resolved_by.get() as i64)
+        .bind(from)
+        .bind(AppealStatus::Submitted)
+    }
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -554,6 +1008,9 @@ pub struct PendingDeescalation {
     pub expiration: DateTime<Utc>,
     pub amount: i64,
     pub entry_id: i32,
+    /// How many times the deescalation has been claimed. Identifies the latest claim, along with
+    /// the expiration, since rescheduling resets it.
+    pub attempts: i32,
 }
 
 impl PendingDeescalation {
@@ -581,7 +1038,11 @@ impl PendingDeescalation {
             DO UPDATE SET \
                 amount = excluded.amount, \
                 expiration = excluded.expiration, \
-                entry_id = excluded.entry_id \
+                entry_id = excluded.entry_id, \
+                locked_until = NULL, \
+                attempts = 0, \
+                failed_at = NULL, \
+                last_error = NULL \
             ",
         )
         .bind(self.guild_id)
@@ -591,40 +1052,447 @@ impl PendingDeescalation {
         .bind(self.entry_id)
     }
 
+    pub fn fetch<'a>(guild_id: Id<GuildMarker>, user_id: Id<UserMarker>) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as("SELECT * FROM pending_deescalations WHERE guild_id = $1 AND user_id = $2")
+            .bind(guild_id.get() as i64)
+            .bind(user_id.get() as i64)
+    }
+
     pub fn delete<'a>(guild_id: Id<GuildMarker>, user_id: Id<UserMarker>) -> SqlQuery<'a> {
         sqlx::query("DELETE FROM pending_deescalations WHERE guild_id = $1 AND user_id = $2")
             .bind(guild_id.get() as i64)
             .bind(user_id.get() as i64)
     }
 
-    pub fn fetch_expired<'a>() -> SqlQueryAs<'a, Self> {
-        sqlx::query_as("SELECT * FROM pending_deescalations WHERE expiration < now()")
+    /// The notification channel woken up when a deescalation is scheduled.
+    pub const CHANNEL: &'static str = "pending_deescalations";
+
+    /// Claims up to `limit` due deescalations for `lease`. Claimed deescalations are skipped by
+    /// other claims until they are finished, retried, or the lease runs out.
+    pub fn claim_due<'a>(lease: Duration, limit: i64) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "UPDATE pending_deescalations \
+             SET locked_until = now() + make_interval(secs => $1), attempts = attempts + 1 \
+             WHERE (guild_id, user_id) IN ( \
+                SELECT guild_id, user_id FROM pending_deescalations \
+                WHERE expiration < now() AND failed_at IS NULL \
+                  AND (locked_until IS NULL OR locked_until < now()) \
+                ORDER BY expiration LIMIT $2 \
+                FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING guild_id, user_id, expiration, amount, entry_id, attempts",
+        )
+        .bind(lease.as_secs_f64())
+        .bind(limit)
+    }
+
+    /// Fetches the number of seconds until the next deescalation can be claimed, if any are
+    /// scheduled. Negative if one is already due.
+    pub fn seconds_until_due<'a>() -> SqlQueryAs<'a, (Option<f64>,)> {
+        sqlx::query_as(
+            "SELECT EXTRACT(EPOCH FROM min(GREATEST(expiration, locked_until)) - now())::float8 \
+             FROM pending_deescalations WHERE failed_at IS NULL",
+        )
+    }
+
+    /// Removes a claimed deescalation once it has run. Does nothing if the deescalation was
+    /// rescheduled or claimed again since, so the caller can tell it may run twice.
+    pub fn finish(&self) -> SqlQuery<'_> {
+        sqlx::query(
+            "DELETE FROM pending_deescalations \
+             WHERE guild_id = $1 AND user_id = $2 AND attempts = $3 AND expiration = $4",
+        )
+        .bind(self.guild_id)
+        .bind(self.user_id)
+        .bind(self.attempts)
+        .bind(self.expiration)
+    }
+
+    /// Releases a claimed deescalation that failed to run, retrying it after an exponential
+    /// backoff. After `max_attempts` it is marked as failed and no longer retried. Returns
+    /// whether it was marked as failed, or no rows if it was rescheduled or claimed again since.
+    pub fn retry<'a>(
+        &self,
+        error: &'a str,
+        max_attempts: i32,
+        base_delay: Duration,
+        max_delay: Duration,
+    ) -> SqlQueryAs<'a, (bool,)> {
+        sqlx::query_as(
+            "UPDATE pending_deescalations SET \
+                locked_until = NULL, \
+                last_error = $3, \
+                failed_at = CASE WHEN attempts >= $4 THEN now() END, \
+                expiration = now() + \
+                    make_interval(secs => least($5 * power(2, attempts - 1), $6)) \
+             WHERE guild_id = $1 AND user_id = $2 AND attempts = $7 AND expiration = $8 \
+             RETURNING failed_at IS NOT NULL",
+        )
+        .bind(self.guild_id)
+        .bind(self.user_id)
+        .bind(error)
+        .bind(max_attempts)
+        .bind(base_delay.as_secs_f64())
+        .bind(max_delay.as_secs_f64())
+        .bind(self.attempts)
+        .bind(self.expiration)
     }
 }
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct PendingAction {
     id: i32,
+    timestamp: DateTime<Utc>,
+    attempts: i32,
+    failed_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    /// Unset for actions scheduled before targets were recorded.
+    guild_id: Option<i64>,
+    user_id: Option<i64>,
+    /// The moderator that took the action being undone, if it was not taken by the bot.
+    moderator_id: Option<i64>,
+    /// The case recorded for the action being undone, if one was.
+    case_number: Option<i32>,
     data: Protobuf<Action>,
 }
 
 impl PendingAction {
+    /// The notification channel woken up when an action is scheduled.
+    pub const CHANNEL: &'static str = "pending_actions";
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// When the action is next due to run.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    /// When the action was given up on after failing too many times.
+    pub fn failed_at(&self) -> Option<DateTime<Utc>> {
+        self.failed_at
+    }
+
+    /// The error of the latest failed attempt to run the action.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn guild_id(&self) -> Option<Id<GuildMarker>> {
+        self.guild_id.and_then(|id| Id::new_checked(id as u64))
+    }
+
+    pub fn user_id(&self) -> Option<Id<UserMarker>> {
+        self.user_id.and_then(|id| Id::new_checked(id as u64))
+    }
+
+    pub fn moderator_id(&self) -> Option<Id<UserMarker>> {
+        self.moderator_id.and_then(|id| Id::new_checked(id as u64))
+    }
+
+    pub fn case_number(&self) -> Option<i32> {
+        self.case_number
+    }
+
+    /// How many times the action has been claimed, including the current claim.
+    pub fn attempts(&self) -> i32 {
+        self.attempts
+    }
+
     pub fn action(&self) -> &Action {
         &self.data.0
     }
 
-    pub fn fetch_expired<'a>() -> SqlQueryAs<'a, Self> {
-        sqlx::query_as("SELECT id, data FROM pending_actions WHERE timestamp < now()")
+    /// Claims up to `limit` due actions for `lease`. Claimed actions are skipped by other claims
+    /// until they are deleted, retried, or the lease runs out.
+    pub fn claim_due<'a>(lease: Duration, limit: i64) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "UPDATE pending_actions \
+             SET locked_until = now() + make_interval(secs => $1), attempts = attempts + 1 \
+             WHERE id IN ( \
+                SELECT id FROM pending_actions \
+                WHERE timestamp < now() AND failed_at IS NULL \
+                  AND (locked_until IS NULL OR locked_until < now()) \
+                ORDER BY timestamp LIMIT $2 \
+                FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING *",
+        )
+        .bind(lease.as_secs_f64())
+        .bind(limit)
+    }
+
+    /// Fetches the number of seconds until the next action can be claimed, if any are
+    /// scheduled. Negative if one is already due.
+    pub fn seconds_until_due<'a>() -> SqlQueryAs<'a, (Option<f64>,)> {
+        sqlx::query_as(
+            "SELECT EXTRACT(EPOCH FROM min(GREATEST(timestamp, locked_until)) - now())::float8 \
+             FROM pending_actions WHERE failed_at IS NULL",
+        )
+    }
+
+    /// Releases a claimed action that failed to run, retrying it after an exponential backoff.
+    /// After `max_attempts` it is marked as failed and no longer retried. Returns whether it was
+    /// marked as failed, or no rows if the action has been claimed again since.
+    pub fn retry<'a>(
+        &self,
+        error: &'a str,
+        max_attempts: i32,
+        base_delay: Duration,
+        max_delay: Duration,
+    ) -> SqlQueryAs<'a, (bool,)> {
+        sqlx::query_as(
+            "UPDATE pending_actions SET \
+                locked_until = NULL, \
+                last_error = $2, \
+                failed_at = CASE WHEN attempts >= $3 THEN now() END, \
+                timestamp = now() + \
+                    make_interval(secs => least($4 * power(2, attempts - 1), $5)) \
+             WHERE id = $1 AND attempts = $6 \
+             RETURNING failed_at IS NOT NULL",
+        )
+        .bind(self.id)
+        .bind(error)
+        .bind(max_attempts)
+        .bind(base_delay.as_secs_f64())
+        .bind(max_delay.as_secs_f64())
+        .bind(self.attempts)
+    }
+
+    /// Marks a claimed action that cannot succeed as failed without retrying it. Failed actions
+    /// are kept so moderators can see them. Does nothing if the action has been claimed again
+    /// since.
+    pub fn fail<'a>(&self, error: &'a str) -> SqlQuery<'a> {
+        sqlx::query(
+            "UPDATE pending_actions \
+             SET locked_until = NULL, last_error = $2, failed_at = now() \
+             WHERE id = $1 AND attempts = $3",
+        )
+        .bind(self.id)
+        .bind(error)
+        .bind(self.attempts)
+    }
+
+    /// Removes a claimed action once it has run. Does nothing if the action has been claimed
+    /// again since, such as after its lease ran out, so the caller can tell it may run twice.
+    pub fn finish<'a>(&self) -> SqlQuery<'a> {
+        sqlx::query("DELETE FROM pending_actions WHERE id = $1 AND attempts = $2")
+            .bind(self.id)
+            .bind(self.attempts)
+    }
+
+    /// Claims a single action in a guild to run it ahead of schedule. Fails to find actions that
+    /// are already running.
+    pub fn claim<'a>(guild_id: Id<GuildMarker>, id: i32, lease: Duration) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "UPDATE pending_actions \
+             SET locked_until = now() + make_interval(secs => $3), attempts = attempts + 1 \
+             WHERE guild_id = $1 AND id = $2 \
+               AND (locked_until IS NULL OR locked_until < now()) \
+             RETURNING *",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(id)
+        .bind(lease.as_secs_f64())
+    }
+
+    /// Releases a claimed action without changing when it is due.
+    pub fn release<'a>(&self) -> SqlQuery<'a> {
+        sqlx::query("UPDATE pending_actions SET locked_until = NULL WHERE id = $1").bind(self.id)
+    }
+
+    /// Fetches the actions scheduled in a guild, optionally only those targeting a user,
+    /// soonest first.
+    pub fn fetch_guild<'a>(
+        guild_id: Id<GuildMarker>,
+        user_id: Option<Id<UserMarker>>,
+        limit: i64,
+    ) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "SELECT * FROM pending_actions \
+             WHERE guild_id = $1 AND ($2::BIGINT IS NULL OR user_id = $2) \
+             ORDER BY timestamp LIMIT $3",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(user_id.map(|id| id.get() as i64))
+        .bind(limit)
+    }
+
+    /// Schedules an action. The guild and user are taken from the action.
+    pub fn schedule<'a>(
+        action: Action,
+        timestamp: impl Into<DateTime<Utc>>,
+        moderator_id: Option<Id<UserMarker>>,
+        case_number: Option<i32>,
+    ) -> SqlQuery<'a> {
+        sqlx::query(
+            "INSERT INTO pending_actions \
+                (timestamp, guild_id, user_id, moderator_id, case_number, data) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(timestamp.into())
+        .bind(action.has_guild_id().then(|| action.get_guild_id() as i64))
+        .bind(action.has_user_id().then(|| action.get_user_id() as i64))
+        .bind(moderator_id.map(|id| id.get() as i64))
+        .bind(case_number)
+        .bind(Protobuf(action))
+    }
+
+    /// Fetches up to `limit` actions without a recorded guild, such as those scheduled before
+    /// targets were recorded, starting after the action with ID `after`.
+    pub fn fetch_untargeted<'a>(after: i32, limit: i64) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "SELECT * FROM pending_actions WHERE guild_id IS NULL AND id > $1 \
+             ORDER BY id LIMIT $2",
+        )
+        .bind(after)
+        .bind(limit)
+    }
+
+    /// Records the guild and user targeted by the action from the action itself.
+    pub fn set_targets<'a>(&self) -> SqlQuery<'a> {
+        let action = self.action();
+        sqlx::query("UPDATE pending_actions SET guild_id = $2, user_id = $3 WHERE id = $1")
+            .bind(self.id)
+            .bind(action.has_guild_id().then(|| action.get_guild_id() as i64))
+            .bind(action.has_user_id().then(|| action.get_user_id() as i64))
+    }
+
+    /// Cancels an action scheduled in a guild, returning it if it existed and was not running.
+    pub fn cancel<'a>(guild_id: Id<GuildMarker>, id: i32) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "DELETE FROM pending_actions \
+             WHERE guild_id = $1 AND id = $2 \
+               AND (locked_until IS NULL OR locked_until < now()) \
+             RETURNING *",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(id)
+    }
+}
+
+/// The next run of a scheduled AutoConfig event.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ScheduledRule {
+    guild_id: i64,
+    rule_id: i64,
+    next_run: DateTime<Utc>,
+    data: Protobuf<ScheduledEvent>,
+}
+
+impl ScheduledRule {
+    /// The notification channel woken up when an event is scheduled.
+    pub const CHANNEL: &'static str = "scheduled_rules";
+
+    pub fn guild_id(&self) -> Id<GuildMarker> {
+        Id::new(self.guild_id as u64)
+    }
+
+    pub fn rule_id(&self) -> u64 {
+        self.rule_id as u64
+    }
+
+    pub fn next_run(&self) -> DateTime<Utc> {
+        self.next_run
+    }
+
+    pub fn event(&self) -> &ScheduledEvent {
+        &self.data.0
+    }
+
+    /// Claims up to `limit` due events for `lease`. Claimed events are skipped by other claims
+    /// until their next run is set or the lease runs out, so each run is only started by one
+    /// instance.
+    pub fn claim_due<'a>(lease: Duration, limit: i64) -> SqlQueryAs<'a, Self> {
+        sqlx::query_as(
+            "UPDATE scheduled_rules SET locked_until = now() + make_interval(secs => $1) \
+             WHERE (guild_id, rule_id) IN ( \
+                SELECT guild_id, rule_id FROM scheduled_rules \
+                WHERE next_run < now() AND (locked_until IS NULL OR locked_until < now()) \
+                ORDER BY next_run LIMIT $2 \
+                FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING guild_id, rule_id, next_run, data",
+        )
+        .bind(lease.as_secs_f64())
+        .bind(limit)
+    }
+
+    /// Fetches the number of seconds until the next event can be claimed, if any are
+    /// scheduled. Negative if one is already due.
+    pub fn seconds_until_due<'a>() -> SqlQueryAs<'a, (Option<f64>,)> {
+        sqlx::query_as(
+            "SELECT EXTRACT(EPOCH FROM min(GREATEST(next_run, locked_until)) - now())::float8 \
+             FROM scheduled_rules",
+        )
+    }
+
+    /// Schedules an event. Events that are already scheduled keep their existing next run.
+    pub fn insert<'a>(
+        guild_id: Id<GuildMarker>,
+        rule_id: u64,
+        next_run: DateTime<Utc>,
+        event: ScheduledEvent,
+    ) -> SqlQuery<'a> {
+        sqlx::query(
+            "INSERT INTO scheduled_rules (guild_id, rule_id, next_run, data) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT ON CONSTRAINT scheduled_rules_pkey DO NOTHING",
+        )
+        .bind(guild_id.get() as i64)
+        .bind(rule_id as i64)
+        .bind(next_run)
+        .bind(Protobuf(event))
+    }
+
+    /// Removes all of the scheduled events in a guild other than the provided ones.
+    pub fn delete_others<'a>(
+        guild_id: Id<GuildMarker>,
+        rule_ids: impl IntoIterator<Item = u64>,
+    ) -> SqlQuery<'a> {
+        let rule_ids: Vec<i64> = rule_ids.into_iter().map(|id| id as i64).collect();
+        sqlx::query("DELETE FROM scheduled_rules WHERE guild_id = $1 AND NOT (rule_id = ANY($2))")
+            .bind(guild_id.get() as i64)
+            .bind(rule_ids)
     }
 
-    pub fn schedule<'a>(action: Action, timestamp: impl Into<DateTime<Utc>>) -> SqlQuery<'a> {
-        sqlx::query("INSERT INTO pending_actions (timestamp, data) VALUES ($1, $2)")
-            .bind(timestamp.into())
-            .bind(Protobuf(action))
+    /// Sets when the event next runs, releasing the claim on it.
+    pub fn set_next_run<'a>(&self, next_run: DateTime<Utc>) -> SqlQuery<'a> {
+        sqlx::query(
+            "UPDATE scheduled_rules SET next_run = $3, locked_until = NULL \
+             WHERE guild_id = $1 AND rule_id = $2",
+        )
+        .bind(self.guild_id)
+        .bind(self.rule_id)
+        .bind(next_run)
     }
 
     pub fn delete<'a>(&self) -> SqlQuery<'a> {
-        sqlx::query("DELETE FROM pending_actions WHERE id = $1").bind(self.id)
+        sqlx::query("DELETE FROM scheduled_rules WHERE guild_id = $1 AND rule_id = $2")
+            .bind(self.guild_id)
+            .bind(self.rule_id)
+    }
+}
+
+/// A record of actions the bot took, along with what caused them.
+#[derive(Debug, sqlx::FromRow)]
+pub struct EventLog {
+    pub id: i64,
+    pub guild_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub data: Protobuf<Event>,
+}
+
+impl EventLog {
+    pub fn event(&self) -> &Event {
+        &self.data.0
+    }
+
+    pub fn insert<'a>(guild_id: Id<GuildMarker>, event: Event) -> SqlQuery<'a> {
+        sqlx::query("INSERT INTO events (guild_id, timestamp, data) VALUES ($1, now(), $2)")
+            .bind(guild_id.get() as i64)
+            .bind(Protobuf(event))
     }
 }
 
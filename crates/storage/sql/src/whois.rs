@@ -1,17 +1,24 @@
-use crate::{SqlPool, Username};
+use crate::{Note, SqlPool, Username};
 use anyhow::Result;
 use hourai::{
     models::{
         guild::Member,
-        id::{Id, marker::UserMarker},
+        id::{
+            Id,
+            marker::{GuildMarker, UserMarker},
+        },
         user::User,
     },
     util::whois,
 };
 use std::fmt::Write;
-use twilight_util::builder::embed::EmbedBuilder;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFieldBuilder};
 
 const USERNAME_LIMIT: u64 = 20;
+/// The most notes shown in a whois embed.
+const NOTE_LIMIT: i64 = 5;
+/// Discord's limit on the length of an embed field's value.
+const FIELD_VALUE_LIMIT: usize = 1024;
 
 pub async fn user(sql: &SqlPool, user: &User) -> Result<EmbedBuilder> {
     let description = build_description(sql, user.id).await?;
@@ -31,6 +38,40 @@ pub async fn member(sql: &SqlPool, member: &Member) -> Result<EmbedBuilder> {
     Ok(builder)
 }
 
+/// Adds the most recent moderator notes on a user to a whois embed. Notes are only meant for
+/// moderators, so this should only be used when the embed is shown to them.
+pub async fn notes(
+    sql: &SqlPool,
+    builder: EmbedBuilder,
+    guild_id: Id<GuildMarker>,
+    user_id: Id<UserMarker>,
+) -> Result<EmbedBuilder> {
+    let notes = Note::fetch_user(guild_id, user_id, NOTE_LIMIT)
+        .fetch_all(sql)
+        .await?;
+    if notes.is_empty() {
+        return Ok(builder);
+    }
+    let mut value = String::new();
+    for note in notes.iter() {
+        let line = format!(
+            "`#{}` <t:{}:d> <@{}>: {}\n",
+            note.id,
+            note.timestamp.timestamp(),
+            note.author_id,
+            note.content
+        );
+        if value.len() + line.len() > FIELD_VALUE_LIMIT {
+            break;
+        }
+        value.push_str(&line);
+    }
+    if value.is_empty() {
+        value.push_str("Too long to show. Use `/note list` to see them.");
+    }
+    Ok(builder.field(EmbedFieldBuilder::new("Notes", value)))
+}
+
 async fn build_description(sql: &SqlPool, user_id: Id<UserMarker>) -> Result<Option<String>> {
     let usernames = Username::fetch(user_id, Some(USERNAME_LIMIT))
         .fetch_all(sql)
@@ -0,0 +1,131 @@
+use hourai::{
+    models::id::{Id, marker::GuildMarker},
+    proto::action::ActionSet,
+};
+use hourai_sql::{Appeal, AppealStatus, Executor, SqlPool};
+use hourai_testing::{guild_id, pool};
+use std::time::Duration;
+
+/// Inserts an appeal that is waiting on the moderators, and returns its ID.
+async fn submitted(pool: &SqlPool, guild_id: Id<GuildMarker>) -> anyhow::Result<i32> {
+    let appeal = Appeal {
+        id: 0,
+        guild_id: guild_id.get() as i64,
+        user_id: 2,
+        token: guild_id.to_string(),
+        actions: ActionSet::new().into(),
+        escalation_entry_id: None,
+        status: AppealStatus::Submitted,
+        content: Some("Please".to_owned()),
+        created_at: chrono::Utc::now(),
+        submitted_at: None,
+        resolved_at: None,
+        resolved_by: None,
+    };
+    let (id,) = appeal.insert().fetch_one(pool).await?;
+    Ok(id)
+}
+
+#[tokio::test]
+async fn test_appeal_accepted_once() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let id = submitted(&pool, guild_id())
+        .await
+        .expect("Failed to insert an appeal");
+    let lease = Duration::from_secs(60);
+
+    let claimed = Appeal::claim_acceptance(id, Id::new(3), lease)
+        .fetch_optional(&pool)
+        .await
+        .expect("Failed to claim the appeal");
+    assert_eq!(
+        claimed.map(|appeal| appeal.status),
+        Some(AppealStatus::Accepting)
+    );
+    // While it is being accepted, no one else can accept or deny it.
+    assert!(
+        Appeal::claim_acceptance(id, Id::new(4), lease)
+            .fetch_optional(&pool)
+            .await
+            .expect("Failed to claim the appeal")
+            .is_none()
+    );
+    for status in [AppealStatus::Accepted, AppealStatus::Denied] {
+        assert!(
+            Appeal::resolve(id, status, Id::new(4))
+                .fetch_optional(&pool)
+                .await
+                .expect("Failed to resolve the appeal")
+                .is_none()
+        );
+    }
+    let result = pool
+        .execute(Appeal::release(id, Id::new(4)))
+        .await
+        .expect("Failed to release the appeal");
+    assert_eq!(result.rows_affected(), 0);
+
+    let accepted = Appeal::resolve(id, AppealStatus::Accepted, Id::new(3))
+        .fetch_optional(&pool)
+        .await
+        .expect("Failed to accept the appeal");
+    assert_eq!(
+        accepted.map(|appeal| appeal.status),
+        Some(AppealStatus::Accepted)
+    );
+    pool.execute(Appeal::delete(id))
+        .await
+        .expect("Failed to remove the appeal");
+}
+
+#[tokio::test]
+async fn test_released_appeal_can_be_resolved() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let id = submitted(&pool, guild_id())
+        .await
+        .expect("Failed to insert an appeal");
+
+    Appeal::claim_acceptance(id, Id::new(3), Duration::from_secs(60))
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to claim the appeal");
+    // Without a lease, the claim is stale, as if the moderator's instance crashed.
+    Appeal::claim_acceptance(id, Id::new(4), Duration::ZERO)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to take over the stale claim");
+    assert!(
+        Appeal::resolve(id, AppealStatus::Accepted, Id::new(3))
+            .fetch_optional(&pool)
+            .await
+            .expect("Failed to accept the appeal")
+            .is_none()
+    );
+
+    let result = pool
+        .execute(Appeal::release(id, Id::new(4)))
+        .await
+        .expect("Failed to release the appeal");
+    assert_eq!(result.rows_affected(), 1);
+    let denied = Appeal::resolve(id, AppealStatus::Denied, Id::new(3))
+        .fetch_optional(&pool)
+        .await
+        .expect("Failed to deny the appeal");
+    assert_eq!(
+        denied.map(|appeal| appeal.status),
+        Some(AppealStatus::Denied)
+    );
+    pool.execute(Appeal::delete(id))
+        .await
+        .expect("Failed to remove the appeal");
+}
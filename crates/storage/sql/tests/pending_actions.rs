@@ -0,0 +1,271 @@
+use hourai::{
+    models::id::{Id, marker::GuildMarker},
+    proto::action::{Action, ActionSet},
+};
+use hourai_sql::{
+    EscalationEntry, Executor, PendingAction, PendingDeescalation, SqlPool, postgres::PgListener,
+};
+use hourai_testing::{guild_id, pool};
+use std::time::Duration;
+
+/// Schedules an action that is already due, and returns its ID.
+async fn schedule(pool: &SqlPool, guild_id: Id<GuildMarker>) -> anyhow::Result<i32> {
+    let mut action = Action::new();
+    action.set_guild_id(guild_id.get());
+    action.set_user_id(2);
+    action.mut_kick();
+    pool.execute(PendingAction::schedule(
+        action,
+        chrono::Utc::now(),
+        None,
+        None,
+    ))
+    .await?;
+    Ok(PendingAction::fetch_guild(guild_id, None, 1)
+        .fetch_one(pool)
+        .await?
+        .id())
+}
+
+#[tokio::test]
+async fn test_finish_requires_latest_claim() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let guild_id = guild_id();
+    let id = schedule(&pool, guild_id)
+        .await
+        .expect("Failed to schedule an action");
+
+    // A lease that has already run out, so the action can be claimed again.
+    let stale = PendingAction::claim(guild_id, id, Duration::ZERO)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to claim the action");
+    let current = PendingAction::claim(guild_id, id, Duration::from_secs(60))
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to claim the action again");
+
+    let result = pool
+        .execute(stale.finish())
+        .await
+        .expect("Failed to finish the action");
+    assert_eq!(result.rows_affected(), 0);
+    let result = pool
+        .execute(current.finish())
+        .await
+        .expect("Failed to finish the action");
+    assert_eq!(result.rows_affected(), 1);
+}
+
+#[tokio::test]
+async fn test_failed_actions_are_kept() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let guild_id = guild_id();
+    let id = schedule(&pool, guild_id)
+        .await
+        .expect("Failed to schedule an action");
+
+    let claimed = PendingAction::claim(guild_id, id, Duration::from_secs(60))
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to claim the action");
+    pool.execute(claimed.fail("Missing Permissions"))
+        .await
+        .expect("Failed to mark the action as failed");
+
+    let failed = PendingAction::fetch_guild(guild_id, None, 1)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to fetch the failed action");
+    assert!(failed.failed_at().is_some());
+    assert_eq!(failed.last_error(), Some("Missing Permissions"));
+    pool.execute(PendingAction::cancel(guild_id, id))
+        .await
+        .expect("Failed to remove the action");
+}
+
+#[tokio::test]
+async fn test_retry_wakes_up_scheduler() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let guild_id = guild_id();
+    let id = schedule(&pool, guild_id)
+        .await
+        .expect("Failed to schedule an action");
+    let claimed = PendingAction::claim(guild_id, id, Duration::from_secs(60))
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to claim the action");
+
+    let mut listener = PgListener::connect_with(&pool)
+        .await
+        .expect("Failed to connect the listener");
+    listener
+        .listen(PendingAction::CHANNEL)
+        .await
+        .expect("Failed to listen for scheduled actions");
+    let (failed,) = claimed
+        .retry(
+            "Internal Server Error",
+            8,
+            Duration::from_secs(10),
+            Duration::from_secs(60),
+        )
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to retry the action");
+    assert!(!failed);
+    tokio::time::timeout(Duration::from_secs(5), listener.recv())
+        .await
+        .expect("Retrying did not notify the scheduler")
+        .expect("Failed to receive the notification");
+
+    pool.execute(PendingAction::cancel(guild_id, id))
+        .await
+        .expect("Failed to remove the action");
+}
+
+#[tokio::test]
+async fn test_set_targets_from_action() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let guild_id = guild_id();
+    let mut action = Action::new();
+    action.set_guild_id(guild_id.get());
+    action.set_user_id(2);
+    action.mut_kick();
+    // Scheduled the way actions were before their targets were recorded.
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO pending_actions (timestamp, data) VALUES (now(), $1) RETURNING id",
+    )
+    .bind(hourai_sql::Protobuf(action))
+    .fetch_one(&pool)
+    .await
+    .expect("Failed to schedule an action");
+
+    let untargeted = PendingAction::fetch_untargeted(id - 1, 1)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to fetch the untargeted action");
+    assert_eq!(untargeted.id(), id);
+    assert_eq!(untargeted.guild_id(), None);
+    pool.execute(untargeted.set_targets())
+        .await
+        .expect("Failed to set the action's targets");
+
+    let pending = PendingAction::fetch_guild(guild_id, Some(Id::new(2)), 1)
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to fetch the action by its targets");
+    assert_eq!(pending.id(), id);
+    assert_eq!(pending.guild_id(), Some(guild_id));
+    pool.execute(PendingAction::cancel(guild_id, id))
+        .await
+        .expect("Failed to remove the action");
+}
+
+/// Claims every due deescalation and returns the one in `guild_id`.
+async fn claim_deescalation(
+    pool: &SqlPool,
+    guild_id: Id<GuildMarker>,
+    lease: Duration,
+) -> anyhow::Result<PendingDeescalation> {
+    PendingDeescalation::claim_due(lease, i64::MAX)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .find(|pending| pending.guild_id() == guild_id)
+        .ok_or_else(|| anyhow::anyhow!("The deescalation was not claimed"))
+}
+
+#[tokio::test]
+async fn test_deescalation_finish_requires_latest_claim() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let guild_id = guild_id();
+    let entry = EscalationEntry {
+        id: 0,
+        guild_id: guild_id.get() as i64,
+        subject_id: 2,
+        authorizer_id: 3,
+        authorizer_name: "user3".to_owned(),
+        display_name: "Warning".to_owned(),
+        timestamp: chrono::Utc::now(),
+        action: ActionSet::new().into(),
+        level_delta: 1,
+        modlog_channel_id: None,
+        modlog_message_id: None,
+        revoked_at: None,
+        revoked_by: None,
+    };
+    let (entry_id,) = entry
+        .insert()
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to record an escalation");
+    let pending = PendingDeescalation {
+        guild_id: guild_id.get() as i64,
+        user_id: 2,
+        expiration: chrono::Utc::now(),
+        amount: -1,
+        entry_id,
+        attempts: 0,
+    };
+    pool.execute(pending.insert())
+        .await
+        .expect("Failed to schedule a deescalation");
+
+    // A lease that has already run out, so the deescalation can be claimed again.
+    let stale = claim_deescalation(&pool, guild_id, Duration::ZERO)
+        .await
+        .expect("Failed to claim the deescalation");
+    let current = claim_deescalation(&pool, guild_id, Duration::from_secs(60))
+        .await
+        .expect("Failed to claim the deescalation again");
+
+    assert!(
+        stale
+            .retry(
+                "Internal Server Error",
+                8,
+                Duration::from_secs(10),
+                Duration::from_secs(60),
+            )
+            .fetch_optional(&pool)
+            .await
+            .expect("Failed to retry the deescalation")
+            .is_none()
+    );
+    let result = pool
+        .execute(stale.finish())
+        .await
+        .expect("Failed to finish the deescalation");
+    assert_eq!(result.rows_affected(), 0);
+    let result = pool
+        .execute(current.finish())
+        .await
+        .expect("Failed to finish the deescalation");
+    assert_eq!(result.rows_affected(), 1);
+}
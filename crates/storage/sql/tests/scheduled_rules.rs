@@ -0,0 +1,45 @@
+use hourai::proto::auto_config::ScheduledEvent;
+use hourai_sql::{Executor, ScheduledRule, postgres::PgListener};
+use hourai_testing::{guild_id, pool};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_scheduling_wakes_up_scheduler() {
+    let Some(pool) = pool()
+        .await
+        .expect("Failed to connect to Postgres test database")
+    else {
+        return;
+    };
+    let guild_id = guild_id();
+    let mut listener = PgListener::connect_with(&pool)
+        .await
+        .expect("Failed to connect the listener");
+    listener
+        .listen(ScheduledRule::CHANNEL)
+        .await
+        .expect("Failed to listen for scheduled events");
+
+    let next_run = chrono::Utc::now() + chrono::Duration::hours(1);
+    pool.execute(ScheduledRule::insert(
+        guild_id,
+        1,
+        next_run,
+        ScheduledEvent::new(),
+    ))
+    .await
+    .expect("Failed to schedule an event");
+    tokio::time::timeout(Duration::from_secs(5), listener.recv())
+        .await
+        .expect("Scheduling did not notify the scheduler")
+        .expect("Failed to receive the notification");
+    let (seconds,) = ScheduledRule::seconds_until_due()
+        .fetch_one(&pool)
+        .await
+        .expect("Failed to fetch the next scheduled event");
+    assert!(seconds.is_some_and(|seconds| seconds <= 3600.0));
+
+    pool.execute(ScheduledRule::delete_others(guild_id, []))
+        .await
+        .expect("Failed to remove the event");
+}
@@ -0,0 +1,70 @@
+//! Fixtures shared by the tests that run against live databases. Each connection helper returns
+//! None when its database is not configured, so the tests using it are skipped.
+
+use hourai::models::{
+    id::{Id, marker::GuildMarker},
+    user::User,
+};
+use hourai_redis::{RedisClient, aio::ConnectionManager};
+use hourai_sql::SqlPool;
+use hourai_storage::Storage;
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Connects to the database named by `DATABASE_URL` and applies the migrations.
+pub async fn pool() -> anyhow::Result<Option<SqlPool>> {
+    let Some(database_url) = env("DATABASE_URL") else {
+        println!("Skipping test: DATABASE_URL not set");
+        return Ok(None);
+    };
+    let pool = hourai_sql::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await?;
+    hourai_sql::migrate(&pool).await?;
+    Ok(Some(pool))
+}
+
+/// Connects to the databases named by `DATABASE_URL` and `REDIS_URL`.
+pub async fn storage() -> anyhow::Result<Option<Storage>> {
+    let Some(redis_url) = env("REDIS_URL") else {
+        println!("Skipping test: REDIS_URL not set");
+        return Ok(None);
+    };
+    let Some(sql) = pool().await? else {
+        return Ok(None);
+    };
+    let redis = ConnectionManager::new(hourai_redis::Client::open(redis_url)?).await?;
+    Ok(Some(Storage::new(sql, RedisClient::new(redis))))
+}
+
+/// A user with the given ID. User 1 is a bot, to stand in for Hourai itself.
+pub fn user(id: u64) -> User {
+    User {
+        accent_color: None,
+        avatar: None,
+        avatar_decoration: None,
+        avatar_decoration_data: None,
+        banner: None,
+        bot: id == 1,
+        discriminator: 0,
+        email: None,
+        flags: None,
+        global_name: None,
+        id: Id::new(id),
+        locale: None,
+        mfa_enabled: None,
+        name: format!("user{}", id),
+        premium_type: None,
+        public_flags: None,
+        system: None,
+        verified: None,
+    }
+}
+
+/// A guild that does not exist, so the test does not read or overwrite real data.
+pub fn guild_id() -> Id<GuildMarker> {
+    Id::new(rand::random::<u64>() >> 1 | 1)
+}